@@ -37,6 +37,16 @@ fi
 # Run tests #
 #############
 RUST_BACKTRACE=1 cargo test -- --test-threads=1
+RUST_BACKTRACE=1 cargo test -p psa-crypto --features std,pkcs11,tracing,cose,live-operations -- --test-threads=1
+
+# PKCS#11 bridge, against a SoftHSM token
+SOFTHSM2_CONF=$(mktemp)
+export SOFTHSM2_CONF
+SOFTHSM2_TOKENS=$(mktemp -d)
+echo "directories.tokendir = $SOFTHSM2_TOKENS" > "$SOFTHSM2_CONF"
+softhsm2-util --init-token --free --label psa-crypto --so-pin 123456 --pin 1234
+PKCS11_MODULE=/usr/lib/softhsm/libsofthsm2.so RUST_BACKTRACE=1 cargo test -p psa-crypto --features pkcs11 --test pkcs11 -- --ignored
+rm -rf "$SOFTHSM2_TOKENS" "$SOFTHSM2_CONF"
 
 # Remove mbedtls directory if it exists
 rm -rf psa-crypto/mbedtls
@@ -52,6 +62,9 @@ popd
 pushd psa-crypto
 cargo build --no-default-features --features std
 cargo build --no-default-features --features operations,std
+cargo build --no-default-features --features pkcs11
+cargo build --no-default-features --features cose
+cargo build --release --no-default-features --features live-operations
 cargo build --no-default-features
 
 # Test dynamic linking
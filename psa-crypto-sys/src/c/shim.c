@@ -434,6 +434,12 @@ shim_PSA_AEAD_TAG_LENGTH(psa_key_type_t key_type, size_t key_bits, psa_algorithm
     return PSA_AEAD_TAG_LENGTH(key_type, key_bits, alg);
 }
 
+size_t
+shim_PSA_AEAD_NONCE_LENGTH(psa_key_type_t key_type, psa_algorithm_t alg)
+{
+    return PSA_AEAD_NONCE_LENGTH(key_type, alg);
+}
+
 size_t
 shim_PSA_AEAD_ENCRYPT_OUTPUT_SIZE(psa_key_type_t key_type, psa_algorithm_t aead_alg, size_t plaintext_length)
 {
@@ -445,3 +451,144 @@ shim_PSA_AEAD_DECRYPT_OUTPUT_SIZE(psa_key_type_t key_type, psa_algorithm_t aead_
 {
     return PSA_AEAD_DECRYPT_OUTPUT_SIZE(key_type, aead_alg, ciphertext_length);
 }
+
+size_t
+shim_PSA_CIPHER_UPDATE_OUTPUT_SIZE(psa_key_type_t key_type, psa_algorithm_t alg, size_t input_length)
+{
+    return PSA_CIPHER_UPDATE_OUTPUT_SIZE(key_type, alg, input_length);
+}
+
+size_t
+shim_PSA_CIPHER_FINISH_OUTPUT_SIZE(psa_key_type_t key_type, psa_algorithm_t alg)
+{
+    return PSA_CIPHER_FINISH_OUTPUT_SIZE(key_type, alg);
+}
+
+size_t
+shim_PSA_CIPHER_IV_LENGTH(psa_key_type_t key_type, psa_algorithm_t alg)
+{
+    return PSA_CIPHER_IV_LENGTH(key_type, alg);
+}
+
+size_t
+shim_PSA_CIPHER_ENCRYPT_OUTPUT_SIZE(psa_key_type_t key_type, psa_algorithm_t alg, size_t input_length)
+{
+    return PSA_CIPHER_ENCRYPT_OUTPUT_SIZE(key_type, alg, input_length);
+}
+
+/* Whether the hash, AEAD or asymmetric algorithm was enabled with its PSA_WANT_ALG_xxx macro
+ * when the library was configured. Hash parameters are checked as well, and AEAD algorithms are
+ * compared without their tag length. */
+int
+shim_PSA_WANT_ALG(psa_algorithm_t alg)
+{
+    int supported = 0;
+
+    if (PSA_ALG_IS_HASH(alg)) {
+#if defined(PSA_WANT_ALG_MD2)
+        supported |= alg == PSA_ALG_MD2;
+#endif
+#if defined(PSA_WANT_ALG_MD4)
+        supported |= alg == PSA_ALG_MD4;
+#endif
+#if defined(PSA_WANT_ALG_MD5)
+        supported |= alg == PSA_ALG_MD5;
+#endif
+#if defined(PSA_WANT_ALG_RIPEMD160)
+        supported |= alg == PSA_ALG_RIPEMD160;
+#endif
+#if defined(PSA_WANT_ALG_SHA_1)
+        supported |= alg == PSA_ALG_SHA_1;
+#endif
+#if defined(PSA_WANT_ALG_SHA_224)
+        supported |= alg == PSA_ALG_SHA_224;
+#endif
+#if defined(PSA_WANT_ALG_SHA_256)
+        supported |= alg == PSA_ALG_SHA_256;
+#endif
+#if defined(PSA_WANT_ALG_SHA_384)
+        supported |= alg == PSA_ALG_SHA_384;
+#endif
+#if defined(PSA_WANT_ALG_SHA_512)
+        supported |= alg == PSA_ALG_SHA_512;
+#endif
+#if defined(PSA_WANT_ALG_SHA_512_224)
+        supported |= alg == PSA_ALG_SHA_512_224;
+#endif
+#if defined(PSA_WANT_ALG_SHA_512_256)
+        supported |= alg == PSA_ALG_SHA_512_256;
+#endif
+#if defined(PSA_WANT_ALG_SHA3_224)
+        supported |= alg == PSA_ALG_SHA3_224;
+#endif
+#if defined(PSA_WANT_ALG_SHA3_256)
+        supported |= alg == PSA_ALG_SHA3_256;
+#endif
+#if defined(PSA_WANT_ALG_SHA3_384)
+        supported |= alg == PSA_ALG_SHA3_384;
+#endif
+#if defined(PSA_WANT_ALG_SHA3_512)
+        supported |= alg == PSA_ALG_SHA3_512;
+#endif
+        return supported;
+    }
+
+    if (PSA_ALG_IS_AEAD(alg)) {
+        psa_algorithm_t base = PSA_ALG_AEAD_WITH_DEFAULT_LENGTH_TAG(alg);
+#if defined(PSA_WANT_ALG_CCM)
+        supported |= base == PSA_ALG_CCM;
+#endif
+#if defined(PSA_WANT_ALG_GCM)
+        supported |= base == PSA_ALG_GCM;
+#endif
+#if defined(PSA_WANT_ALG_CHACHA20_POLY1305)
+        supported |= base == PSA_ALG_CHACHA20_POLY1305;
+#endif
+        (void) base;
+        return supported;
+    }
+
+    if (PSA_ALG_IS_SIGN(alg)) {
+        psa_algorithm_t hash = PSA_ALG_SIGN_GET_HASH(alg);
+        if (hash != 0 && hash != PSA_ALG_ANY_HASH && !shim_PSA_WANT_ALG(hash)) {
+            return 0;
+        }
+        /* The raw variant is part of PKCS#1 v1.5 signatures, Mbed TLS does not always have a
+         * macro of its own for it. */
+#if defined(PSA_WANT_ALG_RSA_PKCS1V15_SIGN) || defined(PSA_WANT_ALG_RSA_PKCS1V15_SIGN_RAW)
+        supported |= PSA_ALG_IS_RSA_PKCS1V15_SIGN(alg);
+#endif
+#if defined(PSA_WANT_ALG_RSA_PSS)
+        supported |= PSA_ALG_IS_RSA_PSS(alg);
+#endif
+#if defined(PSA_WANT_ALG_ECDSA)
+        supported |= PSA_ALG_IS_RANDOMIZED_ECDSA(alg);
+#endif
+#if defined(PSA_WANT_ALG_DETERMINISTIC_ECDSA)
+        supported |= PSA_ALG_IS_DETERMINISTIC_ECDSA(alg);
+#endif
+        return supported;
+    }
+
+    if (PSA_ALG_IS_ASYMMETRIC_ENCRYPTION(alg)) {
+#if defined(PSA_WANT_ALG_RSA_PKCS1V15_CRYPT)
+        supported |= alg == PSA_ALG_RSA_PKCS1V15_CRYPT;
+#endif
+#if defined(PSA_WANT_ALG_RSA_OAEP)
+        supported |= PSA_ALG_IS_RSA_OAEP(alg) && shim_PSA_WANT_ALG(PSA_ALG_RSA_OAEP_GET_HASH(alg));
+#endif
+        return supported;
+    }
+
+    if (PSA_ALG_IS_KEY_AGREEMENT(alg)) {
+#if defined(PSA_WANT_ALG_ECDH)
+        supported |= PSA_ALG_IS_ECDH(alg);
+#endif
+#if defined(PSA_WANT_ALG_FFDH)
+        supported |= PSA_ALG_IS_FFDH(alg);
+#endif
+        return supported;
+    }
+
+    return 0;
+}
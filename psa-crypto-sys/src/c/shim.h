@@ -12,6 +12,9 @@ const psa_key_derivation_step_t shim_PSA_KEY_DERIVATION_INPUT_SALT = PSA_KEY_DER
 const psa_key_derivation_step_t shim_PSA_KEY_DERIVATION_INPUT_INFO = PSA_KEY_DERIVATION_INPUT_INFO;
 const psa_key_derivation_step_t shim_PSA_KEY_DERIVATION_INPUT_SEED = PSA_KEY_DERIVATION_INPUT_SEED;
 
+const size_t shim_PSA_VENDOR_RSA_MAX_KEY_BITS = PSA_VENDOR_RSA_MAX_KEY_BITS;
+const size_t shim_PSA_VENDOR_ECC_MAX_CURVE_BITS = PSA_VENDOR_ECC_MAX_CURVE_BITS;
+
 psa_algorithm_t shim_get_key_algorithm(const psa_key_attributes_t *attributes);
 size_t shim_get_key_bits(const psa_key_attributes_t *attributes);
 psa_key_id_t shim_get_key_id(const psa_key_attributes_t *attributes);
@@ -91,5 +94,12 @@ size_t shim_PSA_HASH_LENGTH(psa_algorithm_t alg);
 size_t shim_PSA_MAC_LENGTH(psa_key_type_t key_type, size_t key_bits, psa_algorithm_t alg);
 size_t shim_PSA_MAC_TRUNCATED_LENGTH(psa_algorithm_t alg);
 size_t shim_PSA_AEAD_TAG_LENGTH(psa_key_type_t key_type, size_t key_bits, psa_algorithm_t alg);
+size_t shim_PSA_AEAD_NONCE_LENGTH(psa_key_type_t key_type, psa_algorithm_t alg);
 size_t shim_PSA_AEAD_ENCRYPT_OUTPUT_SIZE(psa_key_type_t key_type, psa_algorithm_t aead_alg, size_t plaintext_length);
 size_t shim_PSA_AEAD_DECRYPT_OUTPUT_SIZE(psa_key_type_t key_type, psa_algorithm_t aead_alg, size_t ciphertext_length);
+size_t shim_PSA_CIPHER_UPDATE_OUTPUT_SIZE(psa_key_type_t key_type, psa_algorithm_t alg, size_t input_length);
+size_t shim_PSA_CIPHER_FINISH_OUTPUT_SIZE(psa_key_type_t key_type, psa_algorithm_t alg);
+size_t shim_PSA_CIPHER_IV_LENGTH(psa_key_type_t key_type, psa_algorithm_t alg);
+size_t shim_PSA_CIPHER_ENCRYPT_OUTPUT_SIZE(psa_key_type_t key_type, psa_algorithm_t alg, size_t input_length);
+
+int shim_PSA_WANT_ALG(psa_algorithm_t alg);
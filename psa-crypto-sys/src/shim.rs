@@ -21,6 +21,10 @@ pub const PSA_KEY_DERIVATION_INPUT_INFO: psa_key_derivation_step_t =
 pub const PSA_KEY_DERIVATION_INPUT_SEED: psa_key_derivation_step_t =
     psa_crypto_binding::shim_PSA_KEY_DERIVATION_INPUT_SEED;
 
+pub const PSA_VENDOR_RSA_MAX_KEY_BITS: usize = psa_crypto_binding::shim_PSA_VENDOR_RSA_MAX_KEY_BITS;
+pub const PSA_VENDOR_ECC_MAX_CURVE_BITS: usize =
+    psa_crypto_binding::shim_PSA_VENDOR_ECC_MAX_CURVE_BITS;
+
 pub unsafe fn psa_get_key_bits(attributes: *const psa_key_attributes_t) -> usize {
     psa_crypto_binding::shim_get_key_bits(attributes)
 }
@@ -365,6 +369,10 @@ pub fn PSA_AEAD_TAG_LENGTH(
     unsafe { psa_crypto_binding::shim_PSA_AEAD_TAG_LENGTH(key_type, key_bits, alg) }
 }
 
+pub fn PSA_AEAD_NONCE_LENGTH(key_type: psa_key_type_t, alg: psa_algorithm_t) -> usize {
+    unsafe { psa_crypto_binding::shim_PSA_AEAD_NONCE_LENGTH(key_type, alg) }
+}
+
 pub unsafe fn PSA_AEAD_ENCRYPT_OUTPUT_SIZE(
     key_type: psa_key_type_t,
     alg: psa_algorithm_t,
@@ -380,3 +388,36 @@ pub unsafe fn PSA_AEAD_DECRYPT_OUTPUT_SIZE(
 ) -> usize {
     psa_crypto_binding::shim_PSA_AEAD_DECRYPT_OUTPUT_SIZE(key_type, alg, ciphertext_length)
 }
+
+pub unsafe fn PSA_CIPHER_UPDATE_OUTPUT_SIZE(
+    key_type: psa_key_type_t,
+    alg: psa_algorithm_t,
+    input_length: usize,
+) -> usize {
+    psa_crypto_binding::shim_PSA_CIPHER_UPDATE_OUTPUT_SIZE(key_type, alg, input_length)
+}
+
+pub unsafe fn PSA_CIPHER_FINISH_OUTPUT_SIZE(
+    key_type: psa_key_type_t,
+    alg: psa_algorithm_t,
+) -> usize {
+    psa_crypto_binding::shim_PSA_CIPHER_FINISH_OUTPUT_SIZE(key_type, alg)
+}
+
+pub fn PSA_CIPHER_IV_LENGTH(key_type: psa_key_type_t, alg: psa_algorithm_t) -> usize {
+    unsafe { psa_crypto_binding::shim_PSA_CIPHER_IV_LENGTH(key_type, alg) }
+}
+
+pub unsafe fn PSA_CIPHER_ENCRYPT_OUTPUT_SIZE(
+    key_type: psa_key_type_t,
+    alg: psa_algorithm_t,
+    input_length: usize,
+) -> usize {
+    psa_crypto_binding::shim_PSA_CIPHER_ENCRYPT_OUTPUT_SIZE(key_type, alg, input_length)
+}
+
+/// Whether the hash, AEAD or asymmetric algorithm is enabled in the configuration of the
+/// library, through its `PSA_WANT_ALG_xxx` macro
+pub fn PSA_WANT_ALG(alg: psa_algorithm_t) -> bool {
+    unsafe { psa_crypto_binding::shim_PSA_WANT_ALG(alg) == 1 }
+}
@@ -0,0 +1,205 @@
+// Copyright 2020 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! # Minimal CBOR encoding and decoding
+//!
+//! Only what is needed for `COSE_Key` structures: integers, byte strings and maps, written with
+//! the deterministic encoding of RFC 8949. Other items can be skipped when decoding but not read.
+//! Indefinite-length items are rejected.
+
+use crate::types::status::{Error, Result};
+use core::convert::TryFrom;
+use log::error;
+
+pub(crate) const UNSIGNED: u8 = 0;
+pub(crate) const NEGATIVE: u8 = 1;
+pub(crate) const BYTES: u8 = 2;
+pub(crate) const TEXT: u8 = 3;
+pub(crate) const ARRAY: u8 = 4;
+pub(crate) const MAP: u8 = 5;
+pub(crate) const TAG: u8 = 6;
+pub(crate) const SIMPLE: u8 = 7;
+
+/// Deepest nesting of arrays, maps and tags that `Reader::skip` goes through
+const MAX_DEPTH: usize = 16;
+
+/// Append the head of an item, in its shortest form
+pub(crate) fn write_head(out: &mut Vec<u8>, major: u8, value: u64) {
+    let major = major << 5;
+    if value < 24 {
+        out.push(major | value as u8);
+    } else if let Ok(value) = u8::try_from(value) {
+        out.extend_from_slice(&[major | 24, value]);
+    } else if let Ok(value) = u16::try_from(value) {
+        out.push(major | 25);
+        out.extend_from_slice(&value.to_be_bytes());
+    } else if let Ok(value) = u32::try_from(value) {
+        out.push(major | 26);
+        out.extend_from_slice(&value.to_be_bytes());
+    } else {
+        out.push(major | 27);
+        out.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+/// Append an integer
+pub(crate) fn write_int(out: &mut Vec<u8>, value: i64) {
+    if value < 0 {
+        // -1 - value cannot overflow for a negative value.
+        write_head(out, NEGATIVE, (-1 - value) as u64);
+    } else {
+        write_head(out, UNSIGNED, value as u64);
+    }
+}
+
+/// Append a byte string
+pub(crate) fn write_bytes(out: &mut Vec<u8>, value: &[u8]) {
+    write_head(out, BYTES, value.len() as u64);
+    out.extend_from_slice(value);
+}
+
+/// Reads CBOR items from the front of a buffer
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Reader { data }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Major type of the next item, without consuming it
+    pub(crate) fn peek_major(&self) -> Result<u8> {
+        self.data
+            .first()
+            .map(|initial| initial >> 5)
+            .ok_or_else(truncated)
+    }
+
+    /// Read the head of a map and return its number of entries
+    pub(crate) fn map(&mut self) -> Result<usize> {
+        let entries = self.expect(MAP)?;
+        usize::try_from(entries).map_err(|_| truncated())
+    }
+
+    /// Read the head of an array and return its number of items
+    pub(crate) fn array(&mut self) -> Result<usize> {
+        let items = self.expect(ARRAY)?;
+        usize::try_from(items).map_err(|_| truncated())
+    }
+
+    /// Read an integer, which must fit in an `i64`
+    pub(crate) fn int(&mut self) -> Result<i64> {
+        let (major, value) = self.head()?;
+        let value = i64::try_from(value).map_err(|_| {
+            error!("CBOR integer out of range.");
+            Error::InvalidArgument
+        })?;
+        match major {
+            UNSIGNED => Ok(value),
+            NEGATIVE => Ok(-1 - value),
+            _ => {
+                error!("Expected a CBOR integer, found major type {}.", major);
+                Err(Error::InvalidArgument)
+            }
+        }
+    }
+
+    /// Read a byte string
+    pub(crate) fn bytes(&mut self) -> Result<&'a [u8]> {
+        let len = self.expect(BYTES)?;
+        self.take(len)
+    }
+
+    /// Skip the next item, with everything it contains
+    pub(crate) fn skip(&mut self) -> Result<()> {
+        self.skip_nested(0)
+    }
+
+    fn skip_nested(&mut self, depth: usize) -> Result<()> {
+        if depth > MAX_DEPTH {
+            error!("CBOR items are nested too deeply.");
+            return Err(Error::InvalidArgument);
+        }
+        let (major, value) = self.head()?;
+        match major {
+            BYTES | TEXT => {
+                let _ = self.take(value)?;
+            }
+            ARRAY => {
+                for _ in 0..value {
+                    self.skip_nested(depth + 1)?;
+                }
+            }
+            MAP => {
+                for _ in 0..value {
+                    self.skip_nested(depth + 1)?;
+                    self.skip_nested(depth + 1)?;
+                }
+            }
+            TAG => self.skip_nested(depth + 1)?,
+            // Integers and simple values are only a head.
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<u64> {
+        let (major, value) = self.head()?;
+        if major != expected {
+            error!("Expected CBOR major type {}, found {}.", expected, major);
+            return Err(Error::InvalidArgument);
+        }
+        Ok(value)
+    }
+
+    /// Read the head of the next item: its major type and argument
+    fn head(&mut self) -> Result<(u8, u64)> {
+        let initial = self.take(1)?[0];
+        let major = initial >> 5;
+        let value = match initial & 0x1f {
+            info @ 0..=23 => u64::from(info),
+            24 => u64::from(self.take(1)?[0]),
+            25 => {
+                let mut value = [0; 2];
+                value.copy_from_slice(self.take(2)?);
+                u64::from(u16::from_be_bytes(value))
+            }
+            26 => {
+                let mut value = [0; 4];
+                value.copy_from_slice(self.take(4)?);
+                u64::from(u32::from_be_bytes(value))
+            }
+            27 => {
+                let mut value = [0; 8];
+                value.copy_from_slice(self.take(8)?);
+                u64::from_be_bytes(value)
+            }
+            info => {
+                error!("Unsupported CBOR additional information {}.", info);
+                return Err(Error::InvalidArgument);
+            }
+        };
+        Ok((major, value))
+    }
+
+    fn take(&mut self, len: u64) -> Result<&'a [u8]> {
+        let len = usize::try_from(len).map_err(|_| truncated())?;
+        if len > self.data.len() {
+            return Err(truncated());
+        }
+        let (taken, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(taken)
+    }
+}
+
+fn truncated() -> Error {
+    error!("Truncated CBOR item.");
+    Error::InvalidArgument
+}
@@ -0,0 +1,370 @@
+// Copyright 2020 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! # Minimal DER and PEM decoding
+//!
+//! Only what is needed to unwrap the standard key containers (PKCS#8, SubjectPublicKeyInfo) into
+//! the formats expected by the PSA Crypto API. Nothing here allocates.
+
+use crate::types::key::EccFamily;
+#[cfg(feature = "std")]
+use crate::types::key::Type;
+use crate::types::status::{Error, Result};
+use log::error;
+
+pub(crate) const INTEGER: u8 = 0x02;
+pub(crate) const BIT_STRING: u8 = 0x03;
+pub(crate) const OCTET_STRING: u8 = 0x04;
+#[cfg(feature = "std")]
+pub(crate) const NULL: u8 = 0x05;
+pub(crate) const OBJECT_IDENTIFIER: u8 = 0x06;
+pub(crate) const SEQUENCE: u8 = 0x30;
+
+/// rsaEncryption (1.2.840.113549.1.1.1)
+pub(crate) const OID_RSA_ENCRYPTION: &[u8] =
+    &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+/// id-ecPublicKey (1.2.840.10045.2.1)
+pub(crate) const OID_EC_PUBLIC_KEY: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+/// id-X25519 (1.3.101.110)
+pub(crate) const OID_X25519: &[u8] = &[0x2b, 0x65, 0x6e];
+/// id-X448 (1.3.101.111)
+pub(crate) const OID_X448: &[u8] = &[0x2b, 0x65, 0x6f];
+
+/// Named curves which can be used with id-ecPublicKey, with their PSA family and size
+const NAMED_CURVES: [(&[u8], EccFamily, usize); 9] = [
+    // secp192r1 (1.2.840.10045.3.1.1)
+    (
+        &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x01],
+        EccFamily::SecpR1,
+        192,
+    ),
+    // secp224r1 (1.3.132.0.33)
+    (&[0x2b, 0x81, 0x04, 0x00, 0x21], EccFamily::SecpR1, 224),
+    // secp256r1 (1.2.840.10045.3.1.7)
+    (
+        &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07],
+        EccFamily::SecpR1,
+        256,
+    ),
+    // secp384r1 (1.3.132.0.34)
+    (&[0x2b, 0x81, 0x04, 0x00, 0x22], EccFamily::SecpR1, 384),
+    // secp521r1 (1.3.132.0.35)
+    (&[0x2b, 0x81, 0x04, 0x00, 0x23], EccFamily::SecpR1, 521),
+    // secp256k1 (1.3.132.0.10)
+    (&[0x2b, 0x81, 0x04, 0x00, 0x0a], EccFamily::SecpK1, 256),
+    // brainpoolP256r1 (1.3.36.3.3.2.8.1.1.7)
+    (
+        &[0x2b, 0x24, 0x03, 0x03, 0x02, 0x08, 0x01, 0x01, 0x07],
+        EccFamily::BrainpoolPR1,
+        256,
+    ),
+    // brainpoolP384r1 (1.3.36.3.3.2.8.1.1.11)
+    (
+        &[0x2b, 0x24, 0x03, 0x03, 0x02, 0x08, 0x01, 0x01, 0x0b],
+        EccFamily::BrainpoolPR1,
+        384,
+    ),
+    // brainpoolP512r1 (1.3.36.3.3.2.8.1.1.13)
+    (
+        &[0x2b, 0x24, 0x03, 0x03, 0x02, 0x08, 0x01, 0x01, 0x0d],
+        EccFamily::BrainpoolPR1,
+        512,
+    ),
+];
+
+/// Find the named curve OID of a PSA curve family and size
+#[cfg(feature = "std")]
+fn named_curve_oid(family: EccFamily, bits: usize) -> Result<&'static [u8]> {
+    NAMED_CURVES
+        .iter()
+        .find(|(_, curve_family, curve_bits)| *curve_family == family && *curve_bits == bits)
+        .map(|(oid, _, _)| *oid)
+        .ok_or_else(|| {
+            error!("No named curve for this curve family and size.");
+            Error::NotSupported
+        })
+}
+
+/// Find the PSA curve family and size of a named curve OID
+pub(crate) fn named_curve(oid: &[u8]) -> Result<(EccFamily, usize)> {
+    NAMED_CURVES
+        .iter()
+        .find(|(curve_oid, _, _)| *curve_oid == oid)
+        .map(|(_, family, bits)| (*family, *bits))
+        .ok_or_else(|| {
+            error!("Unsupported named curve.");
+            Error::NotSupported
+        })
+}
+
+/// Sequential reader over DER encoded elements
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Reader { data }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub(crate) fn peek_tag(&self) -> Option<u8> {
+        self.data.first().copied()
+    }
+
+    /// Read the next element, returning its tag and contents
+    pub(crate) fn read_any(&mut self) -> Result<(u8, &'a [u8])> {
+        let (&tag, rest) = self.data.split_first().ok_or(Error::InvalidArgument)?;
+        let (&first, mut rest) = rest.split_first().ok_or(Error::InvalidArgument)?;
+        let len = if first < 0x80 {
+            usize::from(first)
+        } else {
+            let len_bytes = usize::from(first & 0x7f);
+            // Indefinite lengths are not DER, and nothing we parse is over 16 MiB.
+            if len_bytes == 0 || len_bytes > 3 || rest.len() < len_bytes {
+                return Err(Error::InvalidArgument);
+            }
+            let (len_bytes, after) = rest.split_at(len_bytes);
+            rest = after;
+            len_bytes
+                .iter()
+                .fold(0, |len, &byte| (len << 8) | usize::from(byte))
+        };
+        if rest.len() < len {
+            return Err(Error::InvalidArgument);
+        }
+        let (contents, rest) = rest.split_at(len);
+        self.data = rest;
+        Ok((tag, contents))
+    }
+
+    /// Read the next element, which must have the given tag
+    pub(crate) fn read(&mut self, tag: u8) -> Result<&'a [u8]> {
+        match self.read_any()? {
+            (read_tag, contents) if read_tag == tag => Ok(contents),
+            _ => Err(Error::InvalidArgument),
+        }
+    }
+}
+
+/// Whether `data` looks like PEM rather than DER
+pub(crate) fn is_pem(data: &[u8]) -> bool {
+    data.iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .map_or(false, |start| data[start..].starts_with(b"-----BEGIN "))
+}
+
+/// Decode the first PEM block of `pem` into `out`, returning the label and the decoded bytes
+pub(crate) fn pem_decode<'a, 'b>(pem: &'a [u8], out: &'b mut [u8]) -> Result<(&'a [u8], &'b [u8])> {
+    const BEGIN: &[u8] = b"-----BEGIN ";
+    const END: &[u8] = b"-----END ";
+    const DASHES: &[u8] = b"-----";
+
+    let start = find(pem, BEGIN).ok_or(Error::InvalidArgument)? + BEGIN.len();
+    let label_len = find(&pem[start..], DASHES).ok_or(Error::InvalidArgument)?;
+    let label = &pem[start..start + label_len];
+    let body_start = start + label_len + DASHES.len();
+    let body_len = find(&pem[body_start..], END).ok_or(Error::InvalidArgument)?;
+    let body = &pem[body_start..body_start + body_len];
+    let end_label = &pem[body_start + body_len + END.len()..];
+    if !end_label.starts_with(label) || !end_label[label.len()..].starts_with(DASHES) {
+        return Err(Error::InvalidArgument);
+    }
+
+    let mut written = 0;
+    let mut acc: u32 = 0;
+    let mut acc_bits = 0;
+    let mut padding = 0;
+    for &byte in body {
+        if byte.is_ascii_whitespace() {
+            continue;
+        }
+        if byte == b'=' {
+            padding += 1;
+            continue;
+        }
+        if padding > 0 {
+            // Data after the padding
+            return Err(Error::InvalidArgument);
+        }
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return Err(Error::InvalidArgument),
+        };
+        acc = ((acc << 6) | u32::from(value)) & 0xffff;
+        acc_bits += 6;
+        if acc_bits >= 8 {
+            acc_bits -= 8;
+            *out.get_mut(written).ok_or(Error::BufferTooSmall)? = (acc >> acc_bits) as u8;
+            written += 1;
+        }
+    }
+    if padding > 2 {
+        return Err(Error::InvalidArgument);
+    }
+
+    Ok((label, &out[..written]))
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Encode an RSA public key as a PKCS#1 `RSAPublicKey` from the big-endian modulus and public
+/// exponent, returning the encoding written in `out`
+#[cfg(feature = "pkcs11")]
+pub(crate) fn encode_rsa_public_key<'a>(
+    modulus: &[u8],
+    public_exponent: &[u8],
+    out: &'a mut [u8],
+) -> Result<&'a [u8]> {
+    let contents_len = integer_len(modulus) + integer_len(public_exponent);
+    let mut written = write_header(out, SEQUENCE, contents_len)?;
+    written += write_integer(&mut out[written..], modulus)?;
+    written += write_integer(&mut out[written..], public_exponent)?;
+    Ok(&out[..written])
+}
+
+/// Largest difference between the size of a SubjectPublicKeyInfo written by `encode_spki` and the
+/// size of the public key it contains: the SEQUENCE, AlgorithmIdentifier and BIT STRING headers,
+/// the OIDs and the unused bits byte
+#[cfg(feature = "std")]
+pub(crate) const SPKI_MAX_OVERHEAD: usize = 4 + 2 + 11 + 11 + 4 + 1;
+
+/// Encode a public key as a SubjectPublicKeyInfo, returning the encoding written in `out`
+///
+/// `public_key` is in the format of `psa_export_public_key`, which is the one of the
+/// `subjectPublicKey` BIT STRING for all the key types supported here.
+#[cfg(feature = "std")]
+pub(crate) fn encode_spki<'a>(
+    key_type: Type,
+    bits: usize,
+    public_key: &[u8],
+    out: &'a mut [u8],
+) -> Result<&'a [u8]> {
+    let (oid, parameters): (&[u8], Option<(u8, &[u8])>) = match key_type {
+        Type::RsaPublicKey | Type::RsaKeyPair => (OID_RSA_ENCRYPTION, Some((NULL, &[]))),
+        Type::EccPublicKey {
+            curve_family: EccFamily::Montgomery,
+        }
+        | Type::EccKeyPair {
+            curve_family: EccFamily::Montgomery,
+        } => match bits {
+            255 => (OID_X25519, None),
+            448 => (OID_X448, None),
+            _ => return Err(Error::NotSupported),
+        },
+        Type::EccPublicKey { curve_family } | Type::EccKeyPair { curve_family } => (
+            OID_EC_PUBLIC_KEY,
+            Some((OBJECT_IDENTIFIER, named_curve_oid(curve_family, bits)?)),
+        ),
+        _ => {
+            error!("No SubjectPublicKeyInfo encoding for this key type.");
+            return Err(Error::NotSupported);
+        }
+    };
+
+    let algorithm_len = header_len(oid.len())
+        + oid.len()
+        + parameters.map_or(0, |(_, contents)| {
+            header_len(contents.len()) + contents.len()
+        });
+    let bit_string_len = 1 + public_key.len();
+    let contents_len =
+        header_len(algorithm_len) + algorithm_len + header_len(bit_string_len) + bit_string_len;
+
+    let mut written = write_header(out, SEQUENCE, contents_len)?;
+    written += write_header(&mut out[written..], SEQUENCE, algorithm_len)?;
+    written += write_element(&mut out[written..], OBJECT_IDENTIFIER, oid)?;
+    if let Some((tag, contents)) = parameters {
+        written += write_element(&mut out[written..], tag, contents)?;
+    }
+    written += write_header(&mut out[written..], BIT_STRING, bit_string_len)?;
+    // No unused bits
+    out[written] = 0;
+    written += 1;
+    out[written..written + public_key.len()].copy_from_slice(public_key);
+    Ok(&out[..written + public_key.len()])
+}
+
+/// Magnitude of a big-endian unsigned integer without its leading zeros, and whether its DER
+/// encoding needs a leading zero to stay positive
+#[cfg(feature = "pkcs11")]
+fn integer_magnitude(value: &[u8]) -> (&[u8], bool) {
+    let start = value
+        .iter()
+        .position(|&byte| byte != 0)
+        .unwrap_or(value.len());
+    let magnitude = &value[start..];
+    match magnitude.first() {
+        Some(&first) => (magnitude, first & 0x80 != 0),
+        // Zero is encoded as a single zero byte.
+        None => (magnitude, true),
+    }
+}
+
+#[cfg(any(feature = "pkcs11", feature = "std"))]
+fn header_len(contents_len: usize) -> usize {
+    match contents_len {
+        0..=0x7f => 2,
+        0x80..=0xff => 3,
+        0x100..=0xffff => 4,
+        _ => 5,
+    }
+}
+
+#[cfg(feature = "pkcs11")]
+fn integer_len(value: &[u8]) -> usize {
+    let (magnitude, leading_zero) = integer_magnitude(value);
+    let contents_len = magnitude.len() + usize::from(leading_zero);
+    header_len(contents_len) + contents_len
+}
+
+#[cfg(any(feature = "pkcs11", feature = "std"))]
+fn write_header(out: &mut [u8], tag: u8, contents_len: usize) -> Result<usize> {
+    let len = header_len(contents_len);
+    if out.len() < len + contents_len || contents_len > 0xff_ffff {
+        return Err(Error::BufferTooSmall);
+    }
+    out[0] = tag;
+    if len == 2 {
+        out[1] = contents_len as u8;
+    } else {
+        let len_bytes = len - 2;
+        out[1] = 0x80 | len_bytes as u8;
+        for i in 0..len_bytes {
+            out[2 + i] = (contents_len >> (8 * (len_bytes - 1 - i))) as u8;
+        }
+    }
+    Ok(len)
+}
+
+#[cfg(feature = "std")]
+fn write_element(out: &mut [u8], tag: u8, contents: &[u8]) -> Result<usize> {
+    let written = write_header(out, tag, contents.len())?;
+    out[written..written + contents.len()].copy_from_slice(contents);
+    Ok(written + contents.len())
+}
+
+#[cfg(feature = "pkcs11")]
+fn write_integer(out: &mut [u8], value: &[u8]) -> Result<usize> {
+    let (magnitude, leading_zero) = integer_magnitude(value);
+    let contents_len = magnitude.len() + usize::from(leading_zero);
+    let mut written = write_header(out, INTEGER, contents_len)?;
+    if leading_zero {
+        out[written] = 0;
+        written += 1;
+    }
+    out[written..written + magnitude.len()].copy_from_slice(magnitude);
+    Ok(written + magnitude.len())
+}
@@ -45,8 +45,14 @@
 // This one is hard to avoid.
 #![allow(clippy::multiple_crate_versions)]
 
+#[cfg(feature = "cose")]
+mod cbor;
+#[cfg(feature = "operations")]
+mod der;
 #[cfg(feature = "operations")]
 pub mod operations;
+#[cfg(feature = "operations")]
+mod trace;
 pub mod types;
 
 pub use psa_crypto_sys as ffi;
@@ -4,13 +4,73 @@
 //! # Authenticated Encryption with Associated Data (AEAD) operations
 //!
 //! See the PSA Crypto API for the format of the different parameters used in this module.
+//!
+//! Only the single-part functions are available: the multipart AEAD functions of the PSA Crypto
+//! API are not implemented by Mbed TLS 3.0, which `psa-crypto-sys` builds. Data can be
+//! authenticated without being encrypted by passing it as the additional data with an empty
+//! plaintext, the ciphertext then only contains the tag.
+//!
+//! ## Without an allocator
+//!
+//! `encrypt` and `decrypt` work on caller-provided slices and, without the `std` feature, never
+//! allocate. The constants of this module give the sizes of the algorithms with their default
+//! tag length, so that the buffers can be arrays on the stack:
+//!
+//! ```
+//! use psa_crypto::operations::{aead, key_management};
+//! use psa_crypto::types::algorithm::{Aead, AeadWithDefaultLengthTag};
+//! use psa_crypto::types::key::{Attributes, Type, Lifetime, Policy, UsageFlags};
+//! # let mut usage_flags: UsageFlags = Default::default();
+//! # usage_flags.set_encrypt().set_decrypt();
+//! # let attributes = Attributes {
+//! #     key_type: Type::Aes,
+//! #     bits: 128,
+//! #     lifetime: Lifetime::Volatile,
+//! #     policy: Policy {
+//! #         usage_flags,
+//! #         permitted_algorithms: Aead::AeadWithDefaultLengthTag(AeadWithDefaultLengthTag::Gcm)
+//! #             .into(),
+//! #     },
+//! # };
+//! const MESSAGE_LENGTH: usize = 16;
+//! let alg = Aead::AeadWithDefaultLengthTag(AeadWithDefaultLengthTag::Gcm);
+//! psa_crypto::init().unwrap();
+//! let my_key = key_management::generate(attributes, None).unwrap();
+//!
+//! let nonce = [0x42; aead::GCM_NONCE_LENGTH];
+//! let mut ciphertext = [0; MESSAGE_LENGTH + aead::GCM_TAG_LENGTH];
+//! aead::encrypt(my_key, alg, &nonce, &[], &[0x5a; MESSAGE_LENGTH], &mut ciphertext).unwrap();
+//! let mut plaintext = [0; MESSAGE_LENGTH];
+//! aead::decrypt(my_key, alg, &nonce, &[], &ciphertext, &mut plaintext).unwrap();
+//! ```
 
 use crate::initialized;
+use crate::trace;
 use crate::types::algorithm::Aead;
 use crate::types::key::Id;
-use crate::types::status::{Result, Status};
+use crate::types::status::{none_if_not_supported, Result, Status};
+#[cfg(not(feature = "std"))]
+use zeroize::Zeroize;
+#[cfg(feature = "std")]
+use zeroize::Zeroizing;
+
+/// Tag length of GCM with the default tag length
+pub const GCM_TAG_LENGTH: usize = 16;
+/// Default nonce length of GCM
+pub const GCM_NONCE_LENGTH: usize = 12;
+/// Tag length of CCM with the default tag length
+pub const CCM_TAG_LENGTH: usize = 16;
+/// Default nonce length of CCM
+pub const CCM_NONCE_LENGTH: usize = 13;
+/// Tag length of ChaCha20-Poly1305
+pub const CHACHA20_POLY1305_TAG_LENGTH: usize = 16;
+/// Nonce length of ChaCha20-Poly1305
+pub const CHACHA20_POLY1305_NONCE_LENGTH: usize = 12;
 
 /// Process an authenticated encryption operation.
+///
+/// `nonce` is used as given: no nonce is generated, so the output is fully determined by the key
+/// and the inputs. The caller must never use the same nonce twice with the same key.
 /// # Example
 ///
 /// ```
@@ -51,9 +111,10 @@ pub fn encrypt(
     ciphertext: &mut [u8],
 ) -> Result<usize> {
     initialized()?;
+    let span = trace::span!("aead_encrypt", Some(key_id), aead_alg);
 
     let mut ciphertext_size = 0;
-    Status::from(unsafe {
+    let result = Status::from(unsafe {
         psa_crypto_sys::psa_aead_encrypt(
             key_id.0,
             aead_alg.into(),
@@ -68,8 +129,10 @@ pub fn encrypt(
             &mut ciphertext_size,
         )
     })
-    .to_result()?;
-    Ok(ciphertext_size)
+    .to_result()
+    .map(|()| ciphertext_size);
+    span.result(&result);
+    result
 }
 
 /// Process an authenticated decryption operation.
@@ -104,6 +167,18 @@ pub fn encrypt(
 /// let length = aead::decrypt(my_key, alg, &NONCE, &ADDITIONAL_DATA, &INPUT_DATA, &mut output_buffer).unwrap();
 /// output_buffer.resize(length, 0);
 /// ```
+///
+/// # Failed verification
+///
+/// No plaintext is ever left in `plaintext` when the tag does not verify and
+/// `Error::InvalidSignature` is returned. Mbed TLS decrypts into the output buffer before
+/// checking the tag, and clears the buffer afterwards if the check fails. To not rely on this:
+///
+/// * with the `std` feature, the message is decrypted into a scratch buffer which is only copied
+///   to `plaintext` once the tag is verified, and erased in all cases. `plaintext` is left
+///   unmodified on failure.
+/// * without it, there is no allocator for the scratch buffer, so this is the one case where
+///   `plaintext` is modified on failure: it is cleared, and then contains only zeros.
 pub fn decrypt(
     key_id: Id,
     aead_alg: Aead,
@@ -111,12 +186,98 @@ pub fn decrypt(
     additional_data: &[u8],
     ciphertext: &[u8],
     plaintext: &mut [u8],
+) -> Result<usize> {
+    #[cfg(feature = "std")]
+    {
+        // The plaintext is never longer than the ciphertext.
+        let mut scratch = Zeroizing::new(vec![0; plaintext.len().min(ciphertext.len())]);
+        let plaintext_size = decrypt_into(
+            key_id,
+            aead_alg,
+            nonce,
+            additional_data,
+            ciphertext,
+            &mut scratch,
+        )?;
+        plaintext[..plaintext_size].copy_from_slice(&scratch[..plaintext_size]);
+        Ok(plaintext_size)
+    }
+
+    #[cfg(not(feature = "std"))]
+    {
+        decrypt_into(
+            key_id,
+            aead_alg,
+            nonce,
+            additional_data,
+            ciphertext,
+            plaintext,
+        )
+        .map_err(|error| {
+            plaintext.zeroize();
+            error
+        })
+    }
+}
+
+/// Encrypt and authenticate a message if the implementation supports `aead_alg`
+///
+/// Same as `encrypt`, except that `Ok(None)` is returned instead of `Error::NotSupported`, so that
+/// the caller can fall back to another algorithm. Other errors are returned as they are.
+pub fn encrypt_or_none(
+    key_id: Id,
+    aead_alg: Aead,
+    nonce: &[u8],
+    additional_data: &[u8],
+    plaintext: &[u8],
+    ciphertext: &mut [u8],
+) -> Result<Option<usize>> {
+    none_if_not_supported(encrypt(
+        key_id,
+        aead_alg,
+        nonce,
+        additional_data,
+        plaintext,
+        ciphertext,
+    ))
+}
+
+/// Decrypt and verify a message if the implementation supports `aead_alg`
+///
+/// Same as `decrypt`, except that `Ok(None)` is returned instead of `Error::NotSupported`. A tag
+/// which does not verify is still an error.
+pub fn decrypt_or_none(
+    key_id: Id,
+    aead_alg: Aead,
+    nonce: &[u8],
+    additional_data: &[u8],
+    ciphertext: &[u8],
+    plaintext: &mut [u8],
+) -> Result<Option<usize>> {
+    none_if_not_supported(decrypt(
+        key_id,
+        aead_alg,
+        nonce,
+        additional_data,
+        ciphertext,
+        plaintext,
+    ))
+}
+
+fn decrypt_into(
+    key_id: Id,
+    aead_alg: Aead,
+    nonce: &[u8],
+    additional_data: &[u8],
+    ciphertext: &[u8],
+    plaintext: &mut [u8],
 ) -> Result<usize> {
     initialized()?;
+    let span = trace::span!("aead_decrypt", Some(key_id), aead_alg);
 
     let mut plaintext_size = 0;
 
-    Status::from(unsafe {
+    let result = Status::from(unsafe {
         psa_crypto_sys::psa_aead_decrypt(
             key_id.0,
             aead_alg.into(),
@@ -131,6 +292,8 @@ pub fn decrypt(
             &mut plaintext_size,
         )
     })
-    .to_result()?;
-    Ok(plaintext_size)
+    .to_result()
+    .map(|()| plaintext_size);
+    span.result(&result);
+    result
 }
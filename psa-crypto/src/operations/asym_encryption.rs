@@ -7,8 +7,8 @@
 
 use crate::initialized;
 use crate::types::algorithm::AsymmetricEncryption;
-use crate::types::key::Id;
-use crate::types::status::{Result, Status};
+use crate::types::key::{Attributes, Id};
+use crate::types::status::{Error, Result, Status};
 
 /// Encrypt a short message with a key pair or public key
 ///
@@ -86,6 +86,22 @@ pub fn encrypt(
 ///
 /// The decrypted message is written in `plaintext`. The function returns the number of bytes written.
 ///
+/// # PKCS#1 v1.5 and padding oracles
+///
+/// `RsaPkcs1v15Crypt` decryption is only safe if a caller cannot tell whether a failure was caused
+/// by bad padding or by anything else that depends on the content of the ciphertext. To avoid
+/// introducing such an oracle on top of what the backend already provides, this function:
+///
+/// * checks that `plaintext` is at least `asymmetric_decrypt_output_size` bytes long before
+///   decrypting anything, so that `Error::BufferTooSmall` only ever depends on the key and not on
+///   the length of the recovered message;
+/// * reports every failure that depends on the ciphertext (bad padding, a recovered message longer
+///   than `plaintext`, a ciphertext out of range for the key) as `Error::InvalidPadding`.
+///
+/// The timing guarantees are those of the backend. New designs should use `RsaOaep` instead, and
+/// protocols which must keep PKCS#1 v1.5 (such as TLS 1.2 RSA key exchange) should carry on with a
+/// random value when decryption fails rather than acting on the error.
+///
 /// # Example
 ///
 /// ```
@@ -140,13 +156,20 @@ pub fn decrypt(
 ) -> Result<usize> {
     initialized()?;
 
+    let pkcs1v15 = alg == AsymmetricEncryption::RsaPkcs1v15Crypt;
+    if pkcs1v15
+        && plaintext.len() < Attributes::from_key_id(key_id)?.asymmetric_decrypt_output_size(alg)?
+    {
+        return Err(Error::BufferTooSmall);
+    }
+
     let mut output_length = 0;
     let (salt_ptr, salt_len) = match salt {
         Some(salt) => (salt.as_ptr(), salt.len()),
         None => (core::ptr::null(), 0),
     };
 
-    Status::from(unsafe {
+    let status = Status::from(unsafe {
         psa_crypto_sys::psa_asymmetric_decrypt(
             key_id.0,
             alg.into(),
@@ -159,6 +182,15 @@ pub fn decrypt(
             &mut output_length,
         )
     })
-    .to_result()?;
-    Ok(output_length)
+    .to_result();
+
+    match status {
+        Ok(()) => Ok(output_length),
+        Err(Error::InvalidPadding) | Err(Error::BufferTooSmall) | Err(Error::InvalidArgument)
+            if pkcs1v15 =>
+        {
+            Err(Error::InvalidPadding)
+        }
+        Err(error) => Err(error),
+    }
 }
@@ -6,9 +6,24 @@
 //! See the PSA Crypto API for the format of the different parameters used in this module.
 
 use crate::initialized;
+#[cfg(feature = "std")]
+use crate::operations::hash::{hash_finish, hash_setup, hash_update};
+#[cfg(feature = "std")]
+use crate::operations::key_management::export_public;
+use crate::trace;
 use crate::types::algorithm::AsymmetricSignature;
+#[cfg(feature = "std")]
+use crate::types::algorithm::{Hash, SignHash};
+#[cfg(feature = "std")]
+use crate::types::key::Attributes;
 use crate::types::key::Id;
+#[cfg(feature = "std")]
+use crate::types::operation::{AbortGuard, HashOperation};
+#[cfg(feature = "std")]
+use crate::types::status::Error;
 use crate::types::status::{Result, Status};
+#[cfg(feature = "std")]
+use log::error;
 
 /// Sign an already-calculated hash with a private key
 ///
@@ -59,10 +74,11 @@ pub fn sign_hash(
     signature: &mut [u8],
 ) -> Result<usize> {
     initialized()?;
+    let span = trace::span!("sign_hash", Some(key), alg);
 
     let mut signature_length = 0;
 
-    Status::from(unsafe {
+    let result = Status::from(unsafe {
         psa_crypto_sys::psa_sign_hash(
             key.0,
             alg.into(),
@@ -73,8 +89,10 @@ pub fn sign_hash(
             &mut signature_length,
         )
     })
-    .to_result()?;
-    Ok(signature_length)
+    .to_result()
+    .map(|()| signature_length);
+    span.result(&result);
+    result
 }
 
 /// Verify the signature of a hash or short message using a public key
@@ -132,3 +150,270 @@ pub fn verify_hash(key: Id, alg: AsymmetricSignature, hash: &[u8], signature: &[
     })
     .to_result()
 }
+
+/// Sign a message with a private key
+///
+/// The message is hashed with the hash algorithm of `alg` before being signed, so `alg` must
+/// specify a hash algorithm. The signature is written in `signature`. The function returns the
+/// number of bytes written.
+///
+/// # Example
+///
+/// ```
+/// # use psa_crypto::operations::key_management::generate;
+/// # use psa_crypto::operations::asym_signature::sign_message;
+/// # use psa_crypto::types::key::{Attributes, Type, Lifetime, Policy, UsageFlags};
+/// # use psa_crypto::types::algorithm::{AsymmetricSignature, Hash};
+/// # let mut usage_flags: UsageFlags = Default::default();
+/// # usage_flags.set_sign_hash().set_verify_hash();
+/// # let mut attributes = Attributes {
+/// #     key_type: Type::RsaKeyPair,
+/// #     bits: 1024,
+/// #     lifetime: Lifetime::Volatile,
+/// #     policy: Policy {
+/// #         usage_flags,
+/// #         permitted_algorithms: AsymmetricSignature::RsaPkcs1v15Sign {
+/// #             hash_alg: Hash::Sha256.into(),
+/// #         }.into(),
+/// #     },
+/// # };
+/// psa_crypto::init().unwrap();
+/// let my_key = generate(attributes, None).unwrap();
+/// let alg = AsymmetricSignature::RsaPkcs1v15Sign {
+///     hash_alg: Hash::Sha256.into(),
+/// };
+/// let mut signature = vec![0; attributes.sign_output_size(alg).unwrap()];
+/// let size = sign_message(my_key, alg, b"message to sign", &mut signature).unwrap();
+/// signature.resize(size, 0);
+/// ```
+pub fn sign_message(
+    key: Id,
+    alg: AsymmetricSignature,
+    message: &[u8],
+    signature: &mut [u8],
+) -> Result<usize> {
+    initialized()?;
+    let span = trace::span!("sign_message", Some(key), alg);
+
+    let mut signature_length = 0;
+
+    let result = Status::from(unsafe {
+        psa_crypto_sys::psa_sign_message(
+            key.0,
+            alg.into(),
+            message.as_ptr(),
+            message.len(),
+            signature.as_mut_ptr(),
+            signature.len(),
+            &mut signature_length,
+        )
+    })
+    .to_result()
+    .map(|()| signature_length);
+    span.result(&result);
+    result
+}
+
+/// Verify the signature of a message using a public key
+///
+/// The message is hashed with the hash algorithm of `alg` before the signature is checked. The
+/// hash is never taken from the signature: a signature made with another hash algorithm is
+/// rejected with `Error::InvalidSignature`, even if the key policy allows both, and `SignHash::Any`
+/// is rejected with `Error::InvalidArgument`.
+pub fn verify_message(
+    key: Id,
+    alg: AsymmetricSignature,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<()> {
+    initialized()?;
+
+    Status::from(unsafe {
+        psa_crypto_sys::psa_verify_message(
+            key.0,
+            alg.into(),
+            message.as_ptr(),
+            message.len(),
+            signature.as_ptr(),
+            signature.len(),
+        )
+    })
+    .to_result()
+}
+
+/// Verify the signature of a message read from `reader` using a public key
+///
+/// The message is hashed in chunks as it is read, so it never needs to be held in memory. This
+/// accepts the signatures produced by `sign_message` with the same algorithm. `alg` must specify
+/// a hash algorithm: `SignHash::Any` is rejected with `Error::InvalidArgument`.
+///
+/// Errors returned by the reader are reported as `Error::InsufficientData`.
+///
+/// # Example
+///
+/// ```
+/// # use psa_crypto::operations::key_management::generate;
+/// # use psa_crypto::operations::asym_signature::{sign_message, verify_message_streaming};
+/// # use psa_crypto::types::key::{Attributes, Type, Lifetime, Policy, UsageFlags};
+/// # use psa_crypto::types::algorithm::{AsymmetricSignature, Hash};
+/// # let mut usage_flags: UsageFlags = Default::default();
+/// # usage_flags.set_sign_hash().set_verify_hash();
+/// # let mut attributes = Attributes {
+/// #     key_type: Type::RsaKeyPair,
+/// #     bits: 1024,
+/// #     lifetime: Lifetime::Volatile,
+/// #     policy: Policy {
+/// #         usage_flags,
+/// #         permitted_algorithms: AsymmetricSignature::RsaPkcs1v15Sign {
+/// #             hash_alg: Hash::Sha256.into(),
+/// #         }.into(),
+/// #     },
+/// # };
+/// psa_crypto::init().unwrap();
+/// let my_key = generate(attributes, None).unwrap();
+/// let alg = AsymmetricSignature::RsaPkcs1v15Sign {
+///     hash_alg: Hash::Sha256.into(),
+/// };
+/// let message = vec![0x5a; 100_000];
+/// let mut signature = vec![0; attributes.sign_output_size(alg).unwrap()];
+/// let size = sign_message(my_key, alg, &message, &mut signature).unwrap();
+/// signature.resize(size, 0);
+///
+/// verify_message_streaming(my_key, alg, &message[..], &signature).unwrap();
+/// ```
+#[cfg(feature = "std")]
+pub fn verify_message_streaming<R: std::io::Read>(
+    key: Id,
+    alg: AsymmetricSignature,
+    mut reader: R,
+    signature: &[u8],
+) -> Result<()> {
+    initialized()?;
+
+    let hash_alg = match alg.hash() {
+        Some(SignHash::Specific(hash_alg)) => hash_alg,
+        _ => {
+            error!("Streaming verification needs a signature algorithm with a specific hash.");
+            return Err(Error::InvalidArgument);
+        }
+    };
+
+    let mut operation = AbortGuard::<HashOperation>::default();
+    hash_setup(&mut operation, hash_alg)?;
+    let (hash, hash_length) = hash_reader(&mut operation, hash_alg, &mut reader)?;
+
+    verify_hash(key, alg, &hash[..hash_length], signature)
+}
+
+/// Verify the signatures of several signers over the same hash, requiring at least `threshold`
+/// valid ones
+///
+/// `signatures[i]` is verified with `key_ids[i]`, with `verify_hash`. The function returns the
+/// indices of the valid signatures, in increasing order, if there are at least `threshold` of
+/// them, and `Error::InvalidSignature` otherwise. Other errors of `verify_hash`, like a key not
+/// allowing `alg`, are returned as they are.
+///
+/// The lists must have the same length, `threshold` must be between 1 and that length and no two
+/// keys may have the same public key, so that a signer cannot be counted twice, even under two
+/// IDs. If not, `Error::InvalidArgument` is returned. The public keys are exported for this check.
+///
+/// # Example
+///
+/// ```
+/// use psa_crypto::operations::{asym_signature, key_management};
+/// use psa_crypto::types::algorithm::{AsymmetricSignature, Hash};
+/// use psa_crypto::types::key::{Attributes, EccFamily, Type, Lifetime, Policy, UsageFlags};
+/// let alg = AsymmetricSignature::Ecdsa { hash_alg: Hash::Sha256.into() };
+/// # let mut usage_flags: UsageFlags = Default::default();
+/// # usage_flags.set_sign_hash().set_verify_hash();
+/// # let attributes = Attributes {
+/// #     key_type: Type::EccKeyPair { curve_family: EccFamily::SecpR1 },
+/// #     bits: 256,
+/// #     lifetime: Lifetime::Volatile,
+/// #     policy: Policy { usage_flags, permitted_algorithms: alg.into() },
+/// # };
+/// # const HASH: [u8; 32] = [0x42; 32];
+/// psa_crypto::init().unwrap();
+/// let keys = [
+///     key_management::generate(attributes, None).unwrap(),
+///     key_management::generate(attributes, None).unwrap(),
+/// ];
+/// let mut signatures = [[0; 64]; 2];
+/// for (key, signature) in keys.iter().zip(signatures.iter_mut()) {
+///     asym_signature::sign_hash(*key, alg, &HASH, signature).unwrap();
+/// }
+///
+/// let valid =
+///     asym_signature::verify_multi(&keys, alg, &HASH, &[&signatures[0], &signatures[1]], 2)
+///         .unwrap();
+/// assert_eq!(valid, [0, 1]);
+/// ```
+#[cfg(feature = "std")]
+pub fn verify_multi(
+    key_ids: &[Id],
+    alg: AsymmetricSignature,
+    hash: &[u8],
+    signatures: &[&[u8]],
+    threshold: usize,
+) -> Result<Vec<usize>> {
+    if key_ids.len() != signatures.len() || threshold == 0 || threshold > key_ids.len() {
+        error!("Invalid signer list or threshold.");
+        return Err(Error::InvalidArgument);
+    }
+    // The same signer can have several IDs, for example if its public key was imported twice.
+    let mut public_keys: Vec<Vec<u8>> = Vec::with_capacity(key_ids.len());
+    for key in key_ids {
+        let mut public_key =
+            vec![0; Attributes::from_key_id(*key)?.export_public_key_output_size()?];
+        let length = export_public(*key, &mut public_key)?;
+        public_key.truncate(length);
+        if public_keys.contains(&public_key) {
+            error!("A signer key appears twice.");
+            return Err(Error::InvalidArgument);
+        }
+        public_keys.push(public_key);
+    }
+
+    let mut valid = Vec::with_capacity(key_ids.len());
+    for (i, (key, signature)) in key_ids.iter().zip(signatures.iter()).enumerate() {
+        match verify_hash(*key, alg, hash, signature) {
+            Ok(()) => valid.push(i),
+            Err(Error::InvalidSignature) => (),
+            Err(error) => return Err(error),
+        }
+    }
+    if valid.len() < threshold {
+        error!(
+            "Only {} valid signatures, {} are needed.",
+            valid.len(),
+            threshold
+        );
+        return Err(Error::InvalidSignature);
+    }
+    Ok(valid)
+}
+
+#[cfg(feature = "std")]
+fn hash_reader<R: std::io::Read>(
+    operation: &mut HashOperation,
+    hash_alg: Hash,
+    reader: &mut R,
+) -> Result<([u8; 64], usize)> {
+    let mut chunk = [0; 4096];
+    loop {
+        let read = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => {
+                error!("Reading the message to verify failed: {}", e);
+                return Err(Error::InsufficientData);
+            }
+        };
+        hash_update(operation, &chunk[..read])?;
+    }
+
+    let mut hash = [0; 64];
+    let hash_length = hash_finish(operation, &mut hash[..hash_alg.hash_length()])?;
+    Ok((hash, hash_length))
+}
@@ -4,9 +4,10 @@
 //! # Unauthenticated Ciphers operations
 
 use crate::initialized;
+use crate::operations::other::generate_random;
 use crate::types::algorithm::Cipher;
-use crate::types::key::Id;
-use crate::types::status::{Result, Status};
+use crate::types::key::{Attributes, Id, Type};
+use crate::types::status::{Error, Result, Status};
 
 fn crypt(
     encrypt: bool,
@@ -34,10 +35,13 @@ fn crypt(
     let mut output_length_finish = 0;
 
     let status = {
-        Status::from(unsafe {
-            psa_crypto_sys::psa_cipher_set_iv(&mut operation, iv.as_ptr(), iv.len())
-        })
-        .to_result()?;
+        // ECB does not take an IV and the backend rejects any attempt to set one.
+        if alg != Cipher::EcbNoPadding {
+            Status::from(unsafe {
+                psa_crypto_sys::psa_cipher_set_iv(&mut operation, iv.as_ptr(), iv.len())
+            })
+            .to_result()?;
+        }
 
         Status::from(unsafe {
             psa_crypto_sys::psa_cipher_update(
@@ -78,6 +82,11 @@ fn crypt(
 ///
 /// The encrypted message is written in `ciphertext`. The function returns the number of bytes written.
 ///
+/// The IV is chosen by the caller and is not written in `ciphertext`: unlike `psa_cipher_encrypt`
+/// of the PSA Crypto API, the output is the ciphertext only. Use `encrypt_detached_iv` to have a
+/// random IV generated, or `encrypt_with_iv_prefix` for the `IV || ciphertext` format of
+/// `psa_cipher_encrypt`.
+///
 /// # Example
 ///
 /// ```
@@ -120,6 +129,9 @@ pub fn encrypt(
 ///
 /// The decrypted message is written in `plaintext`. The function returns the number of bytes written.
 ///
+/// The IV is passed separately from the ciphertext, as written by `encrypt` or
+/// `encrypt_detached_iv`. Use `decrypt_with_iv_prefix` for a ciphertext starting with its IV.
+///
 /// # Example
 ///
 /// ```
@@ -157,3 +169,162 @@ pub fn decrypt(
 ) -> Result<usize> {
     crypt(false, key_id, alg, ciphertext, iv, plaintext)
 }
+
+/// Encrypt a short message with a key and a random IV
+///
+/// The IV, with the default length of the algorithm, is written in `iv` and the encrypted message
+/// in `ciphertext`. The function returns the lengths of the IV and of the encrypted message. The
+/// message can be decrypted with `decrypt` and the IV. No IV is generated for
+/// `Cipher::EcbNoPadding`.
+///
+/// # Example
+///
+/// ```
+/// # use psa_crypto::operations::cipher::{decrypt, encrypt_detached_iv};
+/// # use psa_crypto::operations::key_management::generate;
+/// # use psa_crypto::types::algorithm::Cipher;
+/// # use psa_crypto::types::key::{Attributes, Type, Lifetime, Policy, UsageFlags};
+/// #
+/// # let mut usage_flags: UsageFlags = Default::default();
+/// # usage_flags.set_encrypt().set_decrypt();
+/// # let mut attributes = Attributes {
+/// #     key_type: Type::Aes,
+/// #     bits: 128,
+/// #     lifetime: Lifetime::Volatile,
+/// #     policy: Policy {
+/// #         usage_flags,
+/// #         permitted_algorithms: Cipher::CbcPkcs7.into(),
+/// #     },
+/// # };
+/// psa_crypto::init().unwrap();
+/// let my_key = generate(attributes, None).unwrap();
+/// let alg = Cipher::CbcPkcs7;
+/// let mut iv = vec![0; attributes.cipher_iv_length(alg).unwrap()];
+/// let mut ciphertext = vec![0; 16];
+///
+/// let (_, size) = encrypt_detached_iv(my_key, alg, b"message", &mut iv, &mut ciphertext).unwrap();
+/// let mut plaintext = vec![0; 16];
+/// let size = decrypt(my_key, alg, &ciphertext[..size], &iv, &mut plaintext).unwrap();
+/// assert_eq!(&plaintext[..size], b"message");
+/// ```
+pub fn encrypt_detached_iv(
+    key_id: Id,
+    alg: Cipher,
+    plaintext: &[u8],
+    iv: &mut [u8],
+    ciphertext: &mut [u8],
+) -> Result<(usize, usize)> {
+    let iv_length = Attributes::from_key_id(key_id)?.cipher_iv_length(alg)?;
+    let iv = iv.get_mut(..iv_length).ok_or(Error::BufferTooSmall)?;
+    generate_random(iv)?;
+    let ciphertext_length = crypt(true, key_id, alg, plaintext, iv, ciphertext)?;
+    Ok((iv_length, ciphertext_length))
+}
+
+/// Encrypt a short message with a key and a random IV, writing the IV before the ciphertext
+///
+/// This is `psa_cipher_encrypt` of the PSA Crypto API: `output` receives the IV followed by the
+/// encrypted message, and the function returns the number of bytes written. A sufficient size for
+/// `output` is given by `Attributes::cipher_encrypt_output_size`.
+pub fn encrypt_with_iv_prefix(
+    key_id: Id,
+    alg: Cipher,
+    plaintext: &[u8],
+    output: &mut [u8],
+) -> Result<usize> {
+    initialized()?;
+
+    let mut output_length = 0;
+    Status::from(unsafe {
+        psa_crypto_sys::psa_cipher_encrypt(
+            key_id.0,
+            alg.into(),
+            plaintext.as_ptr(),
+            plaintext.len(),
+            output.as_mut_ptr(),
+            output.len(),
+            &mut output_length,
+        )
+    })
+    .to_result()?;
+    Ok(output_length)
+}
+
+/// Decrypt a short message starting with its IV, as written by `encrypt_with_iv_prefix`
+///
+/// This is `psa_cipher_decrypt` of the PSA Crypto API. The decrypted message is written in
+/// `plaintext`. The function returns the number of bytes written.
+pub fn decrypt_with_iv_prefix(
+    key_id: Id,
+    alg: Cipher,
+    input: &[u8],
+    plaintext: &mut [u8],
+) -> Result<usize> {
+    initialized()?;
+
+    let mut output_length = 0;
+    Status::from(unsafe {
+        psa_crypto_sys::psa_cipher_decrypt(
+            key_id.0,
+            alg.into(),
+            input.as_ptr(),
+            input.len(),
+            plaintext.as_mut_ptr(),
+            plaintext.len(),
+            &mut output_length,
+        )
+    })
+    .to_result()?;
+    Ok(output_length)
+}
+
+/// Compute the key check value (KCV) of an AES key
+///
+/// The KCV is the first three bytes of the encryption of an all-zero block with the key in ECB
+/// mode. It lets provisioning tooling check that the expected key was imported without revealing
+/// the key itself. The key must allow `encrypt` with `Cipher::EcbNoPadding`.
+///
+/// # Example
+///
+/// ```
+/// # use psa_crypto::operations::cipher::key_check_value;
+/// # use psa_crypto::operations::key_management::import;
+/// # use psa_crypto::types::algorithm::Cipher;
+/// # use psa_crypto::types::key::{Attributes, Type, Lifetime, Policy, UsageFlags};
+/// #
+/// # let mut usage_flags: UsageFlags = Default::default();
+/// # usage_flags.set_encrypt();
+/// # let mut attributes = Attributes {
+/// #     key_type: Type::Aes,
+/// #     bits: 128,
+/// #     lifetime: Lifetime::Volatile,
+/// #     policy: Policy {
+/// #         usage_flags,
+/// #         permitted_algorithms: Cipher::EcbNoPadding.into(),
+/// #     },
+/// # };
+/// psa_crypto::init().unwrap();
+/// let my_key = import(attributes, None, &[0; 16]).unwrap();
+///
+/// assert_eq!(key_check_value(my_key).unwrap(), [0x66, 0xe9, 0x4b]);
+/// ```
+pub fn key_check_value(key_id: Id) -> Result<[u8; 3]> {
+    if Attributes::from_key_id(key_id)?.key_type != Type::Aes {
+        return Err(Error::NotSupported);
+    }
+
+    let mut block = [0; 16];
+    let size = crypt(
+        true,
+        key_id,
+        Cipher::EcbNoPadding,
+        &[0; 16],
+        &[],
+        &mut block,
+    )?;
+    if size != block.len() {
+        return Err(Error::GenericError);
+    }
+
+    Ok([block[0], block[1], block[2]])
+}
@@ -0,0 +1,426 @@
+// Copyright 2020 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! # COSE keys
+//!
+//! Conversion between PSA keys and the `COSE_Key` structure of RFC 9052, for EC2 keys on the
+//! NIST P-256, P-384 and P-521 curves and for symmetric keys. A PSA key permits a single
+//! algorithm, so the keys converted always have an `alg` parameter, one of:
+//!
+//! | COSE algorithm             | PSA algorithm                        | Key                    |
+//! |----------------------------|--------------------------------------|------------------------|
+//! | ES256, ES384, ES512        | ECDSA with SHA-256, SHA-384, SHA-512 | EC2                    |
+//! | HMAC 256/256, 384/384, ... | HMAC with SHA-256, SHA-384, SHA-512  | Symmetric, HMAC        |
+//! | A128GCM, A192GCM, A256GCM  | GCM                                  | Symmetric, AES         |
+//! | ChaCha20/Poly1305          | ChaCha20-Poly1305                    | Symmetric, ChaCha20    |
+//!
+//! Other key types, curves and algorithms are rejected with `Error::NotSupported`.
+
+use crate::cbor::{self, Reader};
+use crate::operations::key_management::{destroy, export, export_public, import};
+use crate::types::algorithm::{
+    Aead, AeadWithDefaultLengthTag, Algorithm, AsymmetricSignature, FullLengthMac, Hash, Mac,
+    SignHash,
+};
+use crate::types::key::{Attributes, EccFamily, Id, Lifetime, Policy, Type, UsageFlags};
+use crate::types::status::{Error, Result};
+use log::error;
+use zeroize::Zeroizing;
+
+const KTY: i64 = 1;
+const ALG: i64 = 3;
+const KEY_OPS: i64 = 4;
+const EC2_CRV: i64 = -1;
+const EC2_X: i64 = -2;
+const EC2_Y: i64 = -3;
+const EC2_D: i64 = -4;
+const SYMMETRIC_K: i64 = -1;
+
+const KTY_EC2: i64 = 2;
+const KTY_SYMMETRIC: i64 = 4;
+
+/// Curves of EC2 keys, as their COSE identifier and their size in bits
+const CURVES: [(i64, usize); 3] = [(1, 256), (2, 384), (3, 521)];
+
+/// COSE algorithms and their PSA algorithm, with the type of symmetric keys (none for EC2 keys)
+/// and the size of the key when the algorithm fixes it
+const ALGORITHMS: [(i64, Algorithm, Option<Type>, usize); 10] = [
+    (-7, ecdsa(Hash::Sha256), None, 0),
+    (-35, ecdsa(Hash::Sha384), None, 0),
+    (-36, ecdsa(Hash::Sha512), None, 0),
+    (5, hmac(Hash::Sha256), Some(Type::Hmac), 0),
+    (6, hmac(Hash::Sha384), Some(Type::Hmac), 0),
+    (7, hmac(Hash::Sha512), Some(Type::Hmac), 0),
+    (1, aead(AeadWithDefaultLengthTag::Gcm), Some(Type::Aes), 128),
+    (2, aead(AeadWithDefaultLengthTag::Gcm), Some(Type::Aes), 192),
+    (3, aead(AeadWithDefaultLengthTag::Gcm), Some(Type::Aes), 256),
+    (
+        24,
+        aead(AeadWithDefaultLengthTag::Chacha20Poly1305),
+        Some(Type::Chacha20),
+        256,
+    ),
+];
+
+const fn ecdsa(hash_alg: Hash) -> Algorithm {
+    Algorithm::AsymmetricSignature(AsymmetricSignature::Ecdsa {
+        hash_alg: SignHash::Specific(hash_alg),
+    })
+}
+
+const fn hmac(hash_alg: Hash) -> Algorithm {
+    Algorithm::Mac(Mac::FullLength(FullLengthMac::Hmac { hash_alg }))
+}
+
+const fn aead(alg: AeadWithDefaultLengthTag) -> Algorithm {
+    Algorithm::Aead(Aead::AeadWithDefaultLengthTag(alg))
+}
+
+/// Export a key as a `COSE_Key`
+///
+/// EC2 keys are exported as public keys, even for key pairs. Symmetric keys are exported with
+/// their key material, so they must allow `export` and the returned buffer must be handled as a
+/// secret. The `alg` parameter is the permitted algorithm of the key, which must be one of the
+/// algorithms of the module documentation.
+///
+/// # Example
+///
+/// ```
+/// use psa_crypto::operations::{cose, key_management};
+/// use psa_crypto::types::algorithm::{AsymmetricSignature, Hash};
+/// use psa_crypto::types::key::{Attributes, EccFamily, Lifetime, Policy, Type, UsageFlags};
+/// # let mut usage_flags: UsageFlags = Default::default();
+/// # usage_flags.set_sign_hash().set_verify_hash();
+/// let attributes = Attributes {
+///     key_type: Type::EccKeyPair { curve_family: EccFamily::SecpR1 },
+///     bits: 256,
+///     lifetime: Lifetime::Volatile,
+///     policy: Policy {
+///         usage_flags,
+///         permitted_algorithms: AsymmetricSignature::Ecdsa { hash_alg: Hash::Sha256.into() }
+///             .into(),
+///     },
+/// };
+/// psa_crypto::init().unwrap();
+/// let key = key_management::generate(attributes, None).unwrap();
+/// let cose_key = cose::export_cose_key(key).unwrap();
+/// let public_key = cose::import_cose_key(&cose_key).unwrap();
+/// ```
+pub fn export_cose_key(key_id: Id) -> Result<Vec<u8>> {
+    let attributes = Attributes::from_key_id(key_id)?;
+    let alg = cose_algorithm(attributes)?;
+    let mut cose_key = Vec::new();
+    match attributes.key_type {
+        Type::EccKeyPair {
+            curve_family: EccFamily::SecpR1,
+        }
+        | Type::EccPublicKey {
+            curve_family: EccFamily::SecpR1,
+        } => {
+            let crv = CURVES
+                .iter()
+                .find(|(_, bits)| *bits == attributes.bits)
+                .map(|(crv, _)| *crv)
+                .ok_or(Error::NotSupported)?;
+            let mut point = vec![0; attributes.export_public_key_output_size()?];
+            let point_length = export_public(key_id, &mut point)?;
+            // The PSA format is the uncompressed point: 0x04 || x || y.
+            let coordinate_length = coordinate_length(attributes.bits);
+            if point_length != 1 + 2 * coordinate_length || point[0] != 0x04 {
+                error!("Unexpected format of the exported public key.");
+                return Err(Error::CorruptionDetected);
+            }
+            let (x, y) = point[1..point_length].split_at(coordinate_length);
+
+            cbor::write_head(&mut cose_key, cbor::MAP, 5);
+            cbor::write_int(&mut cose_key, KTY);
+            cbor::write_int(&mut cose_key, KTY_EC2);
+            cbor::write_int(&mut cose_key, ALG);
+            cbor::write_int(&mut cose_key, alg);
+            cbor::write_int(&mut cose_key, EC2_CRV);
+            cbor::write_int(&mut cose_key, crv);
+            cbor::write_int(&mut cose_key, EC2_X);
+            cbor::write_bytes(&mut cose_key, x);
+            cbor::write_int(&mut cose_key, EC2_Y);
+            cbor::write_bytes(&mut cose_key, y);
+        }
+        Type::Hmac | Type::Aes | Type::Chacha20 => {
+            let mut key = Zeroizing::new(vec![0; attributes.export_key_output_size()?]);
+            let key_length = export(key_id, &mut key)?;
+
+            cbor::write_head(&mut cose_key, cbor::MAP, 3);
+            cbor::write_int(&mut cose_key, KTY);
+            cbor::write_int(&mut cose_key, KTY_SYMMETRIC);
+            cbor::write_int(&mut cose_key, ALG);
+            cbor::write_int(&mut cose_key, alg);
+            cbor::write_int(&mut cose_key, SYMMETRIC_K);
+            cbor::write_bytes(&mut cose_key, &key[..key_length]);
+        }
+        _ => {
+            error!(
+                "Key type {:?} cannot be exported as a COSE key.",
+                attributes.key_type
+            );
+            return Err(Error::NotSupported);
+        }
+    }
+
+    Ok(cose_key)
+}
+
+/// Import a `COSE_Key` as a volatile key
+///
+/// The `kty` and `alg` parameters are required, and so are `crv`, `x` and `y` for EC2 public
+/// keys, `crv` and `d` for EC2 key pairs and `k` for symmetric keys. The `x` and `y` parameters of
+/// an EC2 key pair are optional, but must match its public key when present. EC2 keys with a
+/// compressed point are not supported. The key permits the algorithm given by `alg`, with the usages that
+/// this algorithm allows for the key. If the `key_ops` parameter is present, the usages are
+/// restricted to the operations it contains. Other parameters, such as `kid`, are ignored.
+///
+/// Malformed COSE keys, and keys that do not match their algorithm, are rejected with
+/// `Error::InvalidArgument`.
+pub fn import_cose_key(cbor: &[u8]) -> Result<Id> {
+    let parameters = Parameters::parse(cbor)?;
+    let kty = parameters.int(KTY)?.ok_or_else(|| missing("kty"))?;
+    let alg_id = parameters.int(ALG)?.ok_or_else(|| missing("alg"))?;
+    let (_, alg, symmetric_type, alg_bits) = *ALGORITHMS
+        .iter()
+        .find(|(id, ..)| *id == alg_id)
+        .ok_or_else(|| {
+            error!("COSE algorithm {} is not supported.", alg_id);
+            Error::NotSupported
+        })?;
+
+    let mut usage_flags: UsageFlags = Default::default();
+    // Coordinates given with the private key of an EC2 key pair
+    let mut public_key = (None, None);
+    let (key_type, bits, data) = match (kty, symmetric_type) {
+        (KTY_EC2, None) => {
+            let crv = parameters.int(EC2_CRV)?.ok_or_else(|| missing("crv"))?;
+            let bits = CURVES
+                .iter()
+                .find(|(id, _)| *id == crv)
+                .map(|(_, bits)| *bits)
+                .ok_or_else(|| {
+                    error!("COSE curve {} is not supported.", crv);
+                    Error::NotSupported
+                })?;
+            let coordinate_length = coordinate_length(bits);
+            if let Some(Ok(cbor::SIMPLE)) = parameters.find(EC2_Y).map(|y| y.peek_major()) {
+                error!("Compressed EC2 points are not supported.");
+                return Err(Error::NotSupported);
+            }
+            let x = parameters.bytes(EC2_X)?;
+            let y = parameters.bytes(EC2_Y)?;
+            if [x, y]
+                .iter()
+                .flatten()
+                .any(|coordinate| coordinate.len() != coordinate_length)
+            {
+                error!("The public key does not match the curve.");
+                return Err(Error::InvalidArgument);
+            }
+            if let Some(d) = parameters.bytes(EC2_D)? {
+                if d.len() != coordinate_length {
+                    error!("The private key does not match the curve.");
+                    return Err(Error::InvalidArgument);
+                }
+                let _ = usage_flags
+                    .set_sign_hash()
+                    .set_sign_message()
+                    .set_verify_hash()
+                    .set_verify_message();
+                let key_type = Type::EccKeyPair {
+                    curve_family: EccFamily::SecpR1,
+                };
+                public_key = (x, y);
+                (key_type, bits, Zeroizing::new(d.to_vec()))
+            } else {
+                let x = x.ok_or_else(|| missing("x"))?;
+                let y = y.ok_or_else(|| missing("y"))?;
+                let _ = usage_flags.set_verify_hash().set_verify_message();
+                let mut point = Vec::with_capacity(1 + 2 * coordinate_length);
+                point.push(0x04);
+                point.extend_from_slice(x);
+                point.extend_from_slice(y);
+                let key_type = Type::EccPublicKey {
+                    curve_family: EccFamily::SecpR1,
+                };
+                (key_type, bits, Zeroizing::new(point))
+            }
+        }
+        (KTY_SYMMETRIC, Some(key_type)) => {
+            let k = parameters.bytes(SYMMETRIC_K)?.ok_or_else(|| missing("k"))?;
+            let bits = k.len() * 8;
+            if alg_bits != 0 && bits != alg_bits {
+                error!("The key does not have the size required by its algorithm.");
+                return Err(Error::InvalidArgument);
+            }
+            if let Algorithm::Mac(_) = alg {
+                let _ = usage_flags
+                    .set_sign_hash()
+                    .set_sign_message()
+                    .set_verify_hash()
+                    .set_verify_message();
+            } else {
+                let _ = usage_flags.set_encrypt().set_decrypt();
+            }
+            (key_type, bits, Zeroizing::new(k.to_vec()))
+        }
+        (KTY_EC2, Some(_)) | (KTY_SYMMETRIC, None) => {
+            error!(
+                "COSE algorithm {} cannot be used with key type {}.",
+                alg_id, kty
+            );
+            return Err(Error::InvalidArgument);
+        }
+        _ => {
+            error!("COSE key type {} is not supported.", kty);
+            return Err(Error::NotSupported);
+        }
+    };
+
+    if let Some(key_ops) = parameters.key_ops()? {
+        let usage: psa_crypto_sys::psa_key_usage_t = usage_flags.into();
+        usage_flags = (usage & psa_crypto_sys::psa_key_usage_t::from(key_ops)).into();
+    }
+
+    let attributes = Attributes {
+        key_type,
+        bits,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: alg,
+        },
+    };
+    let key_id = import(attributes, None, &data)?;
+    if let Err(error) = check_public_key(key_id, attributes, public_key) {
+        // The key has just been imported, so nothing else uses it.
+        let _ = unsafe { destroy(key_id) };
+        return Err(error);
+    }
+    Ok(key_id)
+}
+
+/// Check the coordinates given with the private key of an EC2 key pair against its public key
+fn check_public_key(
+    key_id: Id,
+    attributes: Attributes,
+    (x, y): (Option<&[u8]>, Option<&[u8]>),
+) -> Result<()> {
+    if x.is_none() && y.is_none() {
+        return Ok(());
+    }
+    let mut point = vec![0; attributes.export_public_key_output_size()?];
+    let point_length = export_public(key_id, &mut point)?;
+    let coordinate_length = coordinate_length(attributes.bits);
+    if point_length != 1 + 2 * coordinate_length {
+        error!("Unexpected format of the exported public key.");
+        return Err(Error::CorruptionDetected);
+    }
+    let (actual_x, actual_y) = point[1..point_length].split_at(coordinate_length);
+    if x.map_or(false, |x| x != actual_x) || y.map_or(false, |y| y != actual_y) {
+        error!("The public key does not match the private key.");
+        return Err(Error::InvalidArgument);
+    }
+    Ok(())
+}
+
+/// COSE identifier of the permitted algorithm of a key
+fn cose_algorithm(attributes: Attributes) -> Result<i64> {
+    ALGORITHMS
+        .iter()
+        .find(|(_, alg, symmetric_type, bits)| {
+            *alg == attributes.policy.permitted_algorithms
+                && (symmetric_type.is_none() || *symmetric_type == Some(attributes.key_type))
+                && (*bits == 0 || *bits == attributes.bits)
+        })
+        .map(|(id, ..)| *id)
+        .ok_or_else(|| {
+            error!(
+                "Algorithm {:?} has no COSE identifier.",
+                attributes.policy.permitted_algorithms
+            );
+            Error::NotSupported
+        })
+}
+
+/// Length of the coordinates and of the private keys on a curve of `bits` bits
+fn coordinate_length(bits: usize) -> usize {
+    (bits + 7) / 8
+}
+
+fn missing(parameter: &str) -> Error {
+    error!("The COSE key has no {} parameter.", parameter);
+    Error::InvalidArgument
+}
+
+/// The parameters of a `COSE_Key` with an integer label, and their undecoded value
+#[derive(Debug)]
+struct Parameters<'a>(Vec<(i64, Reader<'a>)>);
+
+impl<'a> Parameters<'a> {
+    fn parse(cbor: &'a [u8]) -> Result<Self> {
+        let mut reader = Reader::new(cbor);
+        let entries = reader.map()?;
+        let mut parameters = Vec::new();
+        for _ in 0..entries {
+            // Text labels are only used by private parameters, which are ignored.
+            if reader.peek_major()? == cbor::TEXT {
+                reader.skip()?;
+                reader.skip()?;
+                continue;
+            }
+            let label = reader.int()?;
+            if parameters.iter().any(|(other, _)| *other == label) {
+                error!("COSE key parameter {} is duplicated.", label);
+                return Err(Error::InvalidArgument);
+            }
+            parameters.push((label, reader));
+            reader.skip()?;
+        }
+        if !reader.is_empty() {
+            error!("Unexpected data after the COSE key.");
+            return Err(Error::InvalidArgument);
+        }
+        Ok(Parameters(parameters))
+    }
+
+    fn find(&self, label: i64) -> Option<Reader<'a>> {
+        self.0
+            .iter()
+            .find(|(other, _)| *other == label)
+            .map(|(_, value)| *value)
+    }
+
+    fn int(&self, label: i64) -> Result<Option<i64>> {
+        self.find(label).map(|mut value| value.int()).transpose()
+    }
+
+    fn bytes(&self, label: i64) -> Result<Option<&'a [u8]>> {
+        self.find(label).map(|mut value| value.bytes()).transpose()
+    }
+
+    /// Usages allowed by the `key_ops` parameter
+    fn key_ops(&self) -> Result<Option<UsageFlags>> {
+        let mut value = match self.find(KEY_OPS) {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        let mut usage_flags: UsageFlags = Default::default();
+        for _ in 0..value.array()? {
+            // Operations which have no PSA usage, such as key wrapping, are ignored.
+            let _ = match value.int()? {
+                1 => usage_flags.set_sign_hash().set_sign_message(),
+                2 => usage_flags.set_verify_hash().set_verify_message(),
+                3 => usage_flags.set_encrypt(),
+                4 => usage_flags.set_decrypt(),
+                9 => usage_flags.set_sign_hash().set_sign_message(),
+                10 => usage_flags.set_verify_hash().set_verify_message(),
+                _ => &mut usage_flags,
+            };
+        }
+        Ok(Some(usage_flags))
+    }
+}
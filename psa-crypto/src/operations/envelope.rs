@@ -0,0 +1,166 @@
+// Copyright 2020 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! # Signed and encrypted envelopes
+//!
+//! An envelope carries a message signed by its sender and then encrypted with an AEAD key shared
+//! with its recipient. Version 1 of the envelope is laid out as:
+//!
+//! ```text
+//! version (1 byte, 0x01) || nonce || AEAD ciphertext || AEAD tag
+//! ```
+//!
+//! The nonce is random, with the default nonce length of the AEAD algorithm. The version byte is
+//! the additional data of the AEAD operation. The AEAD plaintext is:
+//!
+//! ```text
+//! message || signature || signature length (16-bit big-endian)
+//! ```
+//!
+//! where the signature is calculated over the message with `sign_message`.
+//!
+//! The signature is not bound to the AEAD key, so a recipient could take the message and its
+//! signature out of the envelope and seal them again for someone else. If this matters, the
+//! message should say who it is meant for.
+
+use crate::operations::aead;
+use crate::operations::asym_signature::{sign_message, verify_message};
+use crate::operations::other::generate_random;
+use crate::types::algorithm::{Aead, AsymmetricSignature};
+use crate::types::key::{Attributes, Id};
+use crate::types::status::{Error, Result};
+use core::convert::TryFrom;
+use log::error;
+use zeroize::Zeroizing;
+
+/// Version of the envelopes created by `sign_then_seal`
+pub const ENVELOPE_VERSION: u8 = 1;
+
+const SIGNATURE_LENGTH_SIZE: usize = 2;
+
+/// Sign a message and encrypt it together with its signature
+///
+/// `signing_key` must allow `sign_message` with `sig_alg` and `aead_key` must allow `encrypt`
+/// with `aead_alg`. See the module documentation for the format of the envelope returned.
+///
+/// # Example
+///
+/// ```
+/// use psa_crypto::operations::{envelope, key_management};
+/// use psa_crypto::types::algorithm::{Aead, AeadWithDefaultLengthTag, AsymmetricSignature, Hash};
+/// use psa_crypto::types::key::{Attributes, EccFamily, Type, Lifetime, Policy, UsageFlags};
+/// let sig_alg = AsymmetricSignature::Ecdsa { hash_alg: Hash::Sha256.into() };
+/// let aead_alg = Aead::AeadWithDefaultLengthTag(AeadWithDefaultLengthTag::Gcm);
+/// # let mut usage_flags: UsageFlags = Default::default();
+/// # usage_flags.set_sign_hash().set_verify_hash();
+/// # let signing_attributes = Attributes {
+/// #     key_type: Type::EccKeyPair { curve_family: EccFamily::SecpR1 },
+/// #     bits: 256,
+/// #     lifetime: Lifetime::Volatile,
+/// #     policy: Policy { usage_flags, permitted_algorithms: sig_alg.into() },
+/// # };
+/// # let mut usage_flags: UsageFlags = Default::default();
+/// # usage_flags.set_encrypt().set_decrypt();
+/// # let aead_attributes = Attributes {
+/// #     key_type: Type::Aes,
+/// #     bits: 256,
+/// #     lifetime: Lifetime::Volatile,
+/// #     policy: Policy { usage_flags, permitted_algorithms: aead_alg.into() },
+/// # };
+/// psa_crypto::init().unwrap();
+/// let signing_key = key_management::generate(signing_attributes, None).unwrap();
+/// let aead_key = key_management::generate(aead_attributes, None).unwrap();
+///
+/// let sealed =
+///     envelope::sign_then_seal(signing_key, sig_alg, aead_key, aead_alg, b"message").unwrap();
+/// let message =
+///     envelope::unseal_then_verify(signing_key, sig_alg, aead_key, aead_alg, &sealed).unwrap();
+/// assert_eq!(message, b"message");
+/// ```
+pub fn sign_then_seal(
+    signing_key: Id,
+    sig_alg: AsymmetricSignature,
+    aead_key: Id,
+    aead_alg: Aead,
+    plaintext: &[u8],
+) -> Result<Vec<u8>> {
+    let mut signature = vec![0; Attributes::from_key_id(signing_key)?.sign_output_size(sig_alg)?];
+    let signature_length = sign_message(signing_key, sig_alg, plaintext, &mut signature)?;
+    signature.truncate(signature_length);
+    let encoded_length = u16::try_from(signature_length).map_err(|_| {
+        error!("The signature is too long for the envelope.");
+        Error::NotSupported
+    })?;
+
+    let mut contents = Zeroizing::new(Vec::with_capacity(
+        plaintext.len() + signature_length + SIGNATURE_LENGTH_SIZE,
+    ));
+    contents.extend_from_slice(plaintext);
+    contents.extend_from_slice(&signature);
+    contents.extend_from_slice(&encoded_length.to_be_bytes());
+
+    let aead_attributes = Attributes::from_key_id(aead_key)?;
+    let header_length = 1 + aead_attributes.aead_nonce_length(aead_alg)?;
+    let mut envelope =
+        vec![
+            0;
+            header_length + aead_attributes.aead_encrypt_output_size(aead_alg, contents.len())?
+        ];
+    envelope[0] = ENVELOPE_VERSION;
+    let (header, body) = envelope.split_at_mut(header_length);
+    generate_random(&mut header[1..])?;
+    let (version, nonce) = header.split_at(1);
+    let body_length = aead::encrypt(aead_key, aead_alg, nonce, version, &contents, body)?;
+    envelope.truncate(header_length + body_length);
+
+    Ok(envelope)
+}
+
+/// Decrypt an envelope created by `sign_then_seal` and verify the signature of its message
+///
+/// The envelope is authenticated by the AEAD algorithm first: a modified envelope is rejected with
+/// `Error::InvalidSignature` before any signature verification. `verifying_key` must allow
+/// `verify_message` with `sig_alg` and `aead_key` must allow `decrypt` with `aead_alg`. Envelopes
+/// with an unknown version are rejected with `Error::NotSupported`.
+pub fn unseal_then_verify(
+    verifying_key: Id,
+    sig_alg: AsymmetricSignature,
+    aead_key: Id,
+    aead_alg: Aead,
+    envelope: &[u8],
+) -> Result<Vec<u8>> {
+    let version = *envelope.first().ok_or(Error::InvalidArgument)?;
+    if version != ENVELOPE_VERSION {
+        error!("Unknown envelope version {}.", version);
+        return Err(Error::NotSupported);
+    }
+
+    let aead_attributes = Attributes::from_key_id(aead_key)?;
+    let header_length = 1 + aead_attributes.aead_nonce_length(aead_alg)?;
+    if envelope.len() < header_length {
+        return Err(Error::InvalidArgument);
+    }
+    let (header, body) = envelope.split_at(header_length);
+    let (version, nonce) = header.split_at(1);
+    let mut contents = Zeroizing::new(vec![
+        0;
+        aead_attributes
+            .aead_decrypt_output_size(aead_alg, body.len())?
+    ]);
+    let contents_length = aead::decrypt(aead_key, aead_alg, nonce, version, body, &mut contents)?;
+    let contents = &contents[..contents_length];
+
+    // The contents are authenticated, so they can only be malformed if the sender made them so.
+    if contents.len() < SIGNATURE_LENGTH_SIZE {
+        return Err(Error::InvalidArgument);
+    }
+    let (signed, encoded_length) = contents.split_at(contents.len() - SIGNATURE_LENGTH_SIZE);
+    let signature_length = usize::from(u16::from_be_bytes([encoded_length[0], encoded_length[1]]));
+    if signed.len() < signature_length {
+        return Err(Error::InvalidArgument);
+    }
+    let (plaintext, signature) = signed.split_at(signed.len() - signature_length);
+    verify_message(verifying_key, sig_alg, plaintext, signature)?;
+
+    Ok(plaintext.to_vec())
+}
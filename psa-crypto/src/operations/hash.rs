@@ -7,6 +7,7 @@
 
 use crate::initialized;
 use crate::types::algorithm::Hash;
+use crate::types::operation::{AbortGuard, HashOperation, MultipartOperation};
 use crate::types::status::{Result, Status};
 
 /// Calculate hash of a message
@@ -76,3 +77,192 @@ pub fn hash_compare(hash_alg: Hash, input: &[u8], hash_to_compare: &[u8]) -> Res
     })
     .to_result()
 }
+
+/// Set up a multipart hash operation
+///
+/// The data to hash is then passed with `hash_update`, and the operation is completed with
+/// `hash_finish` or `hash_verify`.
+///
+/// # Example
+///
+/// ```
+/// use psa_crypto::operations::hash::{hash_finish, hash_setup, hash_update};
+/// use psa_crypto::types::algorithm::Hash;
+/// use psa_crypto::types::operation::HashOperation;
+///
+/// psa_crypto::init().unwrap();
+/// let hash_alg = Hash::Sha256;
+/// let mut operation = HashOperation::default();
+/// hash_setup(&mut operation, hash_alg).unwrap();
+/// hash_update(&mut operation, b"first part, ").unwrap();
+/// hash_update(&mut operation, b"second part").unwrap();
+/// let mut hash = vec![0; hash_alg.hash_length()];
+/// let size = hash_finish(&mut operation, &mut hash).unwrap();
+/// ```
+pub fn hash_setup(operation: &mut HashOperation, hash_alg: Hash) -> Result<()> {
+    initialized()?;
+
+    Status::from(unsafe {
+        psa_crypto_sys::psa_hash_setup(operation.as_mut_ptr(), hash_alg.into())
+    })
+    .to_result()?;
+    operation.set_live(true);
+    Ok(())
+}
+
+/// Add a message fragment to a multipart hash operation
+pub fn hash_update(operation: &mut HashOperation, input: &[u8]) -> Result<()> {
+    initialized()?;
+
+    Status::from(unsafe {
+        psa_crypto_sys::psa_hash_update(operation.as_mut_ptr(), input.as_ptr(), input.len())
+    })
+    .to_result()
+}
+
+/// Finish the calculation of the hash of a message
+///
+/// The hash is written in `hash`. The function returns the number of bytes written.
+pub fn hash_finish(operation: &mut HashOperation, hash: &mut [u8]) -> Result<usize> {
+    initialized()?;
+
+    let mut hash_length = 0;
+
+    let status = Status::from(unsafe {
+        psa_crypto_sys::psa_hash_finish(
+            operation.as_mut_ptr(),
+            hash.as_mut_ptr(),
+            hash.len(),
+            &mut hash_length,
+        )
+    })
+    .to_result();
+    // The operation is aborted when it fails to finish.
+    operation.set_live(false);
+    status?;
+    Ok(hash_length)
+}
+
+/// Finish the calculation of the hash of a message and compare it with a reference value
+pub fn hash_verify(operation: &mut HashOperation, hash_to_compare: &[u8]) -> Result<()> {
+    initialized()?;
+
+    let status = Status::from(unsafe {
+        psa_crypto_sys::psa_hash_verify(
+            operation.as_mut_ptr(),
+            hash_to_compare.as_ptr(),
+            hash_to_compare.len(),
+        )
+    })
+    .to_result();
+    // The operation is aborted when it fails to finish.
+    operation.set_live(false);
+    status
+}
+
+/// Copy the state of a multipart hash operation into another one
+///
+/// `target` must not be set up. Both operations can then be continued independently, which gives
+/// the hash of a prefix of a message without starting over for the rest of it.
+pub fn hash_clone(source: &HashOperation, target: &mut HashOperation) -> Result<()> {
+    initialized()?;
+
+    Status::from(unsafe { psa_crypto_sys::psa_hash_clone(&source.0, target.as_mut_ptr()) })
+        .to_result()?;
+    target.set_live(true);
+    Ok(())
+}
+
+/// Running hash of a transcript, whose intermediate values can be read at any point
+///
+/// This is the transcript hash of protocols like TLS 1.3, which needs the hash of the messages
+/// exchanged so far at several points of the handshake.
+///
+/// # Example
+///
+/// ```
+/// use psa_crypto::operations::hash::TranscriptHash;
+/// use psa_crypto::types::algorithm::Hash;
+///
+/// psa_crypto::init().unwrap();
+/// let mut transcript = TranscriptHash::new(Hash::Sha256).unwrap();
+/// let mut hash = [0; 32];
+/// transcript.update(b"ClientHello").unwrap();
+/// let size = transcript.snapshot(&mut hash).unwrap();
+/// transcript.update(b"ServerHello").unwrap();
+/// let size = transcript.finish(&mut hash).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct TranscriptHash {
+    operation: HashOperation,
+    hash_alg: Hash,
+}
+
+impl TranscriptHash {
+    /// Start a transcript hash with the given algorithm
+    pub fn new(hash_alg: Hash) -> Result<Self> {
+        let mut operation = HashOperation::default();
+        hash_setup(&mut operation, hash_alg)?;
+        Ok(TranscriptHash {
+            operation,
+            hash_alg,
+        })
+    }
+
+    /// Algorithm of the transcript hash
+    pub fn algorithm(&self) -> Hash {
+        self.hash_alg
+    }
+
+    /// Add data to the transcript
+    pub fn update(&mut self, data: &[u8]) -> Result<()> {
+        hash_update(&mut self.operation, data)
+    }
+
+    /// Hash of the data added so far, the transcript can be continued afterwards
+    ///
+    /// The hash is written in `hash`. The function returns the number of bytes written.
+    pub fn snapshot(&self, hash: &mut [u8]) -> Result<usize> {
+        let mut copy = AbortGuard::<HashOperation>::default();
+        hash_clone(&self.operation, &mut copy)?;
+        hash_finish(&mut copy, hash)
+    }
+
+    /// Hash of the data added so far, returned in a vector
+    ///
+    /// Same as `snapshot`, with a vector the size of the hash.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use psa_crypto::operations::hash::TranscriptHash;
+    /// use psa_crypto::types::algorithm::Hash;
+    ///
+    /// psa_crypto::init().unwrap();
+    /// let mut transcript = TranscriptHash::new(Hash::Sha256).unwrap();
+    /// transcript.update(b"ClientHello").unwrap();
+    /// let hash = transcript.snapshot_vec().unwrap();
+    /// assert_eq!(hash.len(), 32);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn snapshot_vec(&self) -> Result<Vec<u8>> {
+        let mut hash = vec![0; self.algorithm().hash_length()];
+        let length = self.snapshot(&mut hash)?;
+        hash.truncate(length);
+        Ok(hash)
+    }
+
+    /// Hash of the whole transcript
+    ///
+    /// The hash is written in `hash`. The function returns the number of bytes written.
+    pub fn finish(mut self, hash: &mut [u8]) -> Result<usize> {
+        hash_finish(&mut self.operation, hash)
+    }
+}
+
+impl Drop for TranscriptHash {
+    fn drop(&mut self) {
+        // Nothing to release if the transcript was finished.
+        let _ = self.operation.abort();
+    }
+}
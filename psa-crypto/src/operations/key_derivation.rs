@@ -4,11 +4,16 @@
 //! # Key Derivation operations
 
 use crate::initialized;
-use crate::types::key::Attributes;
+use crate::operations::key_management::destroy;
+use crate::types::algorithm::{Algorithm, KeyDerivation};
 use crate::types::key::Id;
-use crate::types::key_derivation::Operation;
+use crate::types::key::{new_key_attributes, Attributes};
+use crate::types::key_derivation::{Input, InputSecret, Inputs, Operation};
 use crate::types::status::{Error, Result, Status};
-use core::convert::{TryFrom, TryInto};
+use core::convert::TryInto;
+use log::error;
+#[cfg(feature = "std")]
+use zeroize::Zeroizing;
 
 /// This function calculates output bytes from a key derivation algorithm and uses those bytes to generate a key deterministically.
 /// The key's location, usage policy, type and size are taken from attributes.
@@ -66,10 +71,7 @@ use core::convert::{TryFrom, TryInto};
 pub fn output_key(operation: Operation, attributes: Attributes, id: Option<u32>) -> Result<Id> {
     initialized()?;
 
-    let mut key_attributes = psa_crypto_sys::psa_key_attributes_t::try_from(attributes)?;
-    if let Some(id) = id {
-        unsafe { psa_crypto_sys::psa_set_key_id(&mut key_attributes, id) };
-    }
+    let key_attributes = new_key_attributes(attributes, id)?;
     let mut id_for_new_key = 0;
 
     let mut op: psa_crypto_sys::psa_key_derivation_operation_t = operation.try_into()?;
@@ -84,3 +86,174 @@ pub fn output_key(operation: Operation, attributes: Attributes, id: Option<u32>)
     key_deriv_res?; // All other error can now return after abort
     Ok(Id(id_for_new_key))
 }
+
+/// Calculate `bits` bits of output of a key derivation algorithm, to be used as key material
+///
+/// `bits` must be a non-zero multiple of 8, otherwise `Error::InvalidArgument` is returned. The
+/// function returns exactly `bits / 8` bytes, in a buffer which is zeroized when dropped. If the
+/// operation has less than `bits / 8` bytes of capacity left, `Error::InsufficientData` is
+/// returned.
+///
+/// # Example
+///
+/// ```
+/// use psa_crypto::operations::{key_derivation, key_management};
+/// use psa_crypto::types::algorithm::{Hash, KeyDerivation};
+/// use psa_crypto::types::key::{Attributes, Type, Lifetime, Policy, UsageFlags};
+/// use psa_crypto::types::key_derivation::{Input, InputSecret, Inputs, Operation};
+/// # let mut usage_flags: UsageFlags = Default::default();
+/// # usage_flags.set_derive();
+/// # let attributes = Attributes {
+/// #     key_type: Type::Derive,
+/// #     bits: 0,
+/// #     lifetime: Lifetime::Volatile,
+/// #     policy: Policy {
+/// #         usage_flags,
+/// #         permitted_algorithms: KeyDerivation::Hkdf { hash_alg: Hash::Sha256 }.into(),
+/// #     },
+/// # };
+/// psa_crypto::init().unwrap();
+/// let my_key = key_management::import(attributes, None, &[0x42; 32]).unwrap();
+/// let operation = Operation {
+///     inputs: Inputs::Hkdf {
+///         hash_alg: Hash::Sha256,
+///         salt: None,
+///         secret: InputSecret::Input(Input::Key(my_key)),
+///         info: Input::Bytes(b"session key"),
+///     },
+///     capacity: None,
+/// };
+/// let key_material = key_derivation::output_key_bytes(operation, 256).unwrap();
+/// assert_eq!(key_material.len(), 32);
+/// ```
+#[cfg(feature = "std")]
+pub fn output_key_bytes(operation: Operation, bits: usize) -> Result<Zeroizing<Vec<u8>>> {
+    initialized()?;
+
+    if bits == 0 || bits % 8 != 0 {
+        error!("Key material must be a whole number of bytes.");
+        return Err(Error::InvalidArgument);
+    }
+    let mut output = Zeroizing::new(vec![0; bits / 8]);
+
+    let mut op: psa_crypto_sys::psa_key_derivation_operation_t = operation.try_into()?;
+    let output_res = Status::from(unsafe {
+        psa_crypto_sys::psa_key_derivation_output_bytes(&mut op, output.as_mut_ptr(), output.len())
+    })
+    .to_result();
+    Operation::abort(op)?;
+    output_res?;
+    Ok(output)
+}
+
+/// HKDF info used by `derive_encryption_keys` for the data encryption key
+pub const ENC_KEY_INFO: &[u8] = b"psa-crypto data encryption key";
+/// HKDF info used by `derive_encryption_keys` for the IV generation key
+pub const IV_KEY_INFO: &[u8] = b"psa-crypto IV generation key";
+
+/// Independent keys for the encryption of data and the generation of its IVs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncryptionKeys {
+    /// Key encrypting the data
+    pub enc_key: Id,
+    /// Key of the PRF generating the IVs
+    pub iv_key: Id,
+}
+
+/// Derive a data encryption key and an IV generation key from a master key
+///
+/// Both keys are derived with HKDF from `master`, with the same salt and with `ENC_KEY_INFO` and
+/// `IV_KEY_INFO` as info. The different info makes them independent: knowing one of them tells
+/// nothing about the other, or about the master key. Deriving again from the same master key and
+/// salt gives the same keys.
+///
+/// `master` must allow `derive` with `KeyDerivation::Hkdf`, whose hash is used. The type, size and
+/// policy of the keys are taken from `enc_attributes` and `iv_attributes`. The IV key is typically
+/// an HMAC key, used as a PRF over a message counter. If the IV key cannot be derived, the
+/// encryption key is destroyed before returning the error.
+///
+/// # Example
+///
+/// ```
+/// use psa_crypto::operations::{key_derivation, key_management};
+/// use psa_crypto::types::algorithm::{
+///     Aead, AeadWithDefaultLengthTag, FullLengthMac, Hash, KeyDerivation, Mac,
+/// };
+/// use psa_crypto::types::key::{Attributes, Type, Lifetime, Policy, UsageFlags};
+/// # let mut usage_flags: UsageFlags = Default::default();
+/// # usage_flags.set_derive();
+/// # let master_attributes = Attributes {
+/// #     key_type: Type::Derive,
+/// #     bits: 0,
+/// #     lifetime: Lifetime::Volatile,
+/// #     policy: Policy {
+/// #         usage_flags,
+/// #         permitted_algorithms: KeyDerivation::Hkdf { hash_alg: Hash::Sha256 }.into(),
+/// #     },
+/// # };
+/// # let mut usage_flags: UsageFlags = Default::default();
+/// # usage_flags.set_encrypt().set_decrypt();
+/// let enc_attributes = Attributes {
+///     key_type: Type::Aes,
+///     bits: 256,
+///     lifetime: Lifetime::Volatile,
+///     policy: Policy {
+///         usage_flags,
+///         permitted_algorithms: Aead::AeadWithDefaultLengthTag(AeadWithDefaultLengthTag::Gcm)
+///             .into(),
+///     },
+/// };
+/// # let mut usage_flags: UsageFlags = Default::default();
+/// # usage_flags.set_sign_hash();
+/// let iv_attributes = Attributes {
+///     key_type: Type::Hmac,
+///     bits: 256,
+///     lifetime: Lifetime::Volatile,
+///     policy: Policy {
+///         usage_flags,
+///         permitted_algorithms: Mac::FullLength(FullLengthMac::Hmac { hash_alg: Hash::Sha256 })
+///             .into(),
+///     },
+/// };
+/// psa_crypto::init().unwrap();
+/// let master = key_management::import(master_attributes, None, &[0x42; 32]).unwrap();
+/// let keys =
+///     key_derivation::derive_encryption_keys(master, None, enc_attributes, iv_attributes)
+///         .unwrap();
+/// ```
+pub fn derive_encryption_keys(
+    master: Id,
+    salt: Option<&[u8]>,
+    enc_attributes: Attributes,
+    iv_attributes: Attributes,
+) -> Result<EncryptionKeys> {
+    let hash_alg = match Attributes::from_key_id(master)?.policy.permitted_algorithms {
+        Algorithm::KeyDerivation(KeyDerivation::Hkdf { hash_alg }) => hash_alg,
+        _ => {
+            error!("The master key must allow HKDF.");
+            return Err(Error::InvalidArgument);
+        }
+    };
+    let derive = |info, attributes| {
+        let operation = Operation {
+            inputs: Inputs::Hkdf {
+                hash_alg,
+                salt: salt.map(Input::Bytes),
+                secret: InputSecret::Input(Input::Key(master)),
+                info: Input::Bytes(info),
+            },
+            capacity: None,
+        };
+        output_key(operation, attributes, None)
+    };
+
+    let enc_key = derive(ENC_KEY_INFO, enc_attributes)?;
+    match derive(IV_KEY_INFO, iv_attributes) {
+        Ok(iv_key) => Ok(EncryptionKeys { enc_key, iv_key }),
+        Err(error) => {
+            // Safe because the key was just created and not handed out.
+            let _ = unsafe { destroy(enc_key) };
+            Err(error)
+        }
+    }
+}
@@ -0,0 +1,155 @@
+// Copyright 2020 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! # Versioned persistent keys
+//!
+//! A `KeyRing` stores successive versions of a key as persistent keys with consecutive IDs:
+//! version `n` has the ID `base + n`. New operations use the current version while data
+//! protected with an older version can still be processed with that version, as long as it is
+//! within the retention window. Versions older than that are destroyed when the ring is rotated.
+
+use crate::operations::key_management;
+use crate::types::key::{Attributes, Id};
+use crate::types::status::{Error, Result};
+use log::error;
+
+/// A set of persistent keys, each one being a version of the same key
+///
+/// # Example
+///
+/// ```
+/// # use psa_crypto::operations::key_management;
+/// use psa_crypto::operations::key_ring::KeyRing;
+/// use psa_crypto::types::algorithm::{FullLengthMac, Hash, Mac};
+/// use psa_crypto::types::key::{Attributes, Type, Lifetime, Policy, UsageFlags};
+///
+/// # let mut usage_flags: UsageFlags = Default::default();
+/// # usage_flags.set_sign_hash().set_verify_hash();
+/// let attributes = Attributes {
+///     key_type: Type::Hmac,
+///     bits: 256,
+///     lifetime: Lifetime::Persistent,
+///     policy: Policy {
+///         usage_flags,
+///         permitted_algorithms: Mac::FullLength(FullLengthMac::Hmac {
+///             hash_alg: Hash::Sha256,
+///         })
+///         .into(),
+///     },
+/// };
+///
+/// psa_crypto::init().unwrap();
+/// // Keep the current version and the one before it.
+/// let mut ring = KeyRing::create(attributes, 0x1000, 1).unwrap();
+/// let first = ring.current();
+/// // Safe because the destroyed versions are not in use.
+/// unsafe { ring.rotate() }.unwrap();
+/// assert_eq!(ring.version(), 1);
+/// assert_eq!(ring.get(0).unwrap(), first);
+/// # unsafe { key_management::destroy(ring.get(0).unwrap()) }.unwrap();
+/// # unsafe { key_management::destroy(ring.current()) }.unwrap();
+/// ```
+// Copies could rotate independently, generating the same version twice.
+#[allow(missing_copy_implementations)]
+#[derive(Debug)]
+pub struct KeyRing {
+    attributes: Attributes,
+    base: u32,
+    version: u32,
+    retention: u32,
+    current: Id,
+}
+
+impl KeyRing {
+    /// Create a key ring, generating its version 0 with the ID `base`
+    ///
+    /// `retention` is the number of versions kept in addition to the current one.
+    pub fn create(attributes: Attributes, base: u32, retention: u32) -> Result<Self> {
+        let current = key_management::generate(attributes, Some(base))?;
+        Ok(KeyRing {
+            attributes,
+            base,
+            version: 0,
+            retention,
+            current,
+        })
+    }
+
+    /// Open an existing key ring whose current version is `version`
+    ///
+    /// The key of the current version must exist. The attributes of the following versions are
+    /// taken from it.
+    pub fn open(base: u32, version: u32, retention: u32) -> Result<Self> {
+        let current = Id::from_persistent_key_id(version_id(base, version)?)?;
+        Ok(KeyRing {
+            attributes: Attributes::from_key_id(current)?,
+            base,
+            version,
+            retention,
+            current,
+        })
+    }
+
+    /// Version of the key to use for new operations
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Key to use for new operations
+    pub fn current(&self) -> Id {
+        self.current
+    }
+
+    /// Key of an older or current version
+    ///
+    /// Returns `Error::DoesNotExist` if the version is newer than the current one or has been
+    /// dropped out of the retention window.
+    pub fn get(&self, version: u32) -> Result<Id> {
+        if version > self.version || self.version - version > self.retention {
+            error!("Key version {} is not in the key ring.", version);
+            return Err(Error::DoesNotExist);
+        }
+        Id::from_persistent_key_id(version_id(self.base, version)?)
+    }
+
+    /// Generate the next version of the key and make it the current one
+    ///
+    /// The version which falls out of the retention window is destroyed. If the generation fails,
+    /// the key ring is left unchanged. If destroying the expired version fails, the error is
+    /// returned but the key ring has already been rotated.
+    ///
+    /// # Safety
+    ///
+    /// Same as `key_management::destroy`: the caller must make sure that the destroyed version is
+    /// not used by another thread.
+    pub unsafe fn rotate(&mut self) -> Result<Id> {
+        let version = self.version.checked_add(1).ok_or_else(|| {
+            error!("The key ring is out of versions.");
+            Error::InsufficientStorage
+        })?;
+        let current =
+            key_management::generate(self.attributes, Some(version_id(self.base, version)?))?;
+        self.version = version;
+        self.current = current;
+
+        if let Some(expired) = version
+            .checked_sub(self.retention)
+            .and_then(|v| v.checked_sub(1))
+        {
+            match key_management::destroy(Id(version_id(self.base, expired)?)) {
+                // It might already have been destroyed, for example with a smaller retention.
+                Ok(()) | Err(Error::DoesNotExist) | Err(Error::InvalidHandle) => (),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(current)
+    }
+}
+
+fn version_id(base: u32, version: u32) -> Result<u32> {
+    base.checked_add(version).ok_or_else(|| {
+        error!("Key version {} does not have a valid key ID.", version);
+        Error::InvalidArgument
+    })
+}
@@ -2,17 +2,40 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! # Message Authentication Code (MAC) operations
+//!
+//! ## KMAC and cSHAKE
+//!
+//! Version 1.0 of the PSA Crypto API defines no KMAC or cSHAKE algorithm, and Mbed TLS, the
+//! backend of this crate, implements no Keccak-based function, so `Mac` has no variant with a
+//! customization string. Protocols which only need domain separation can put a fixed,
+//! unambiguous prefix in front of the message with HMAC, as `VersionedMac` does with its header.
+//! Protocols which require KMAC itself cannot be implemented with this crate.
 
 use crate::initialized;
-use crate::types::key::Id;
-use crate::types::algorithm::Mac;
-use crate::types::status::{Result, Status};
-use crate::types::operation::MacOperation;
-
+use crate::operations::key_derivation;
+use crate::operations::key_management::destroy;
+use crate::trace;
+use crate::types::algorithm::{Algorithm, Cipher, FullLengthMac, Hash, KeyDerivation, Mac};
+use crate::types::key::{Attributes, Id, Lifetime, Policy, Type, UsageFlags};
+use crate::types::key_derivation::{Input, InputSecret, Inputs, Operation};
+use crate::types::operation::{AbortGuard, MacOperation};
+use crate::types::status::{none_if_not_supported, Error, Result, Status};
+use core::{fmt, mem};
+use log::error;
+#[cfg(feature = "std")]
+use std::borrow::Cow;
+use subtle::ConstantTimeEq;
+use zeroize::Zeroize;
 
 /// Calculate the message authentication code (MAC) of a message
 /// The key must allow `sign_message`
 ///
+/// On success, the MAC is written at the start of `mac` and its length is returned: only
+/// `mac[..length]` is meaningful. The rest of the buffer, and the whole buffer when an error such
+/// as `Error::BufferTooSmall` is returned, has unspecified contents. A buffer reused across calls
+/// can therefore hold bytes of an earlier, longer MAC after its end, and must only be read up to
+/// the length returned by the last successful call.
+///
 /// # Example
 ///
 /// ```
@@ -23,16 +46,15 @@ use crate::types::operation::MacOperation;
 /// #     0x69, 0x3E, 0xDB, 0x1B, 0x22, 0x79, 0x03, 0xF4, 0xC0, 0xBF, 0xD6, 0x91, 0x76, 0x37, 0x84, 0xA2,
 /// #     0x94, 0x8E, 0x92, 0x50, 0x35, 0xC2, 0x8C, 0x5C, 0x3C, 0xCA, 0xFE, 0x18, 0xE8, 0x81, 0x37, 0x78,
 /// # ];
+/// # let mut usage_flags: UsageFlags = Default::default();
+/// # usage_flags.set_sign_hash();
 /// # let mut attributes = Attributes {
-/// #     key_type: Type::RsaKeyPair,
-/// #     bits: 1024,
+/// #     key_type: Type::Hmac,
+/// #     bits: 256,
 /// #     lifetime: Lifetime::Volatile,
 /// #     policy: Policy {
-/// #         usage_flags: UsageFlags {
-/// #             sign_message: true,
-/// #             ..Default::default()
-/// #         },
-/// #         permitted_algorithms: FullLengthMac::Hmac{hash_alg: Hash::Sha256}.into(),
+/// #         usage_flags,
+/// #         permitted_algorithms: Mac::FullLength(FullLengthMac::Hmac{hash_alg: Hash::Sha256}).into(),
 /// #     },
 /// # };
 /// #
@@ -48,15 +70,21 @@ use crate::types::operation::MacOperation;
 ///                      &mut mac).unwrap();
 /// mac.resize(size, 0);
 /// ```
-pub fn compute_mac(key_id: Id, mac_alg: Mac, input_message: &[u8], mac: &mut [u8]) -> Result<usize> {
+pub fn compute_mac(
+    key_id: Id,
+    mac_alg: Mac,
+    input_message: &[u8],
+    mac: &mut [u8],
+) -> Result<usize> {
     // Check if PSA Crypto is initialized
     initialized()?;
+    let span = trace::span!("compute_mac", Some(key_id), mac_alg);
     /* At the moment (July 2025), support only CMAC */
-    
+
     let mut output_length = 0;
     let key_handle = key_id.0;
 
-    let mac_compute_res = Status::from(unsafe {
+    let result = Status::from(unsafe {
         psa_crypto_sys::psa_mac_compute(
             key_handle,
             mac_alg.into(),
@@ -65,15 +93,36 @@ pub fn compute_mac(key_id: Id, mac_alg: Mac, input_message: &[u8], mac: &mut [u8
             mac.as_mut_ptr(),
             mac.len(),
             &mut output_length,
-        )}
-    ).to_result();
-    mac_compute_res?;
-    Ok(output_length)
+        )
+    })
+    .to_result()
+    .map(|()| output_length);
+    span.result(&result);
+    result
+}
+
+/// Calculate the MAC of a message if the implementation supports `mac_alg`
+///
+/// Same as `compute_mac`, except that `Ok(None)` is returned instead of `Error::NotSupported`, so
+/// that the caller can fall back to another algorithm. Other errors are returned as they are.
+pub fn compute_mac_or_none(
+    key_id: Id,
+    mac_alg: Mac,
+    input_message: &[u8],
+    mac: &mut [u8],
+) -> Result<Option<usize>> {
+    none_if_not_supported(compute_mac(key_id, mac_alg, input_message, mac))
 }
 
 /// Calculate the message authentication code (MAC) of a message and compare it with a reference value
 /// The key must allow `sign_message`
 ///
+/// The implementation compares as many bytes as the MAC of `mac_alg` has, and rejects an
+/// `expected_mac` of another length. To verify a MAC truncated to `n` bytes, `mac_alg` must be a
+/// `Mac::Truncated` algorithm with a `mac_length` of `n`. An `expected_mac` shorter than the MAC
+/// of a full-length algorithm is rejected with `Error::InvalidArgument`, as it would be if a prefix
+/// were compared.
+///
 /// # Example
 ///
 /// ```
@@ -84,15 +133,14 @@ pub fn compute_mac(key_id: Id, mac_alg: Mac, input_message: &[u8], mac: &mut [u8
 /// #     0x69, 0x3E, 0xDB, 0x1B, 0x22, 0x79, 0x03, 0xF4, 0xC0, 0xBF, 0xD6, 0x91, 0x76, 0x37, 0x84, 0xA2,
 /// #     0x94, 0x8E, 0x92, 0x50, 0x35, 0xC2, 0x8C, 0x5C, 0x3C, 0xCA, 0xFE, 0x18, 0xE8, 0x81, 0x37, 0x78,
 /// # ];
+/// # let mut usage_flags: UsageFlags = Default::default();
+/// # usage_flags.set_sign_hash().set_verify_hash();
 /// # let mut attributes = Attributes {
-/// #     key_type: Type::RsaKeyPair,
-/// #     bits: 1024,
+/// #     key_type: Type::Hmac,
+/// #     bits: 256,
 /// #     lifetime: Lifetime::Volatile,
 /// #     policy: Policy {
-/// #         usage_flags: UsageFlags {
-/// #             sign_message: true,
-/// #             ..Default::default()
-/// #         },
+/// #         usage_flags,
 /// #         permitted_algorithms: Mac::FullLength(FullLengthMac::Hmac{hash_alg: Hash::Sha256}).into(),
 /// #     },
 /// # };
@@ -108,11 +156,30 @@ pub fn compute_mac(key_id: Id, mac_alg: Mac, input_message: &[u8], mac: &mut [u8
 ///                      &MESSAGE,
 ///                      &mut mac).unwrap();
 /// mac.resize(size, 0);
-/// assert!(verify_mac(my_key, mac_alg, &MESSAGE, &mac));
+/// verify_mac(my_key, mac_alg, &MESSAGE, &mac).unwrap();
 /// ```
-pub fn verify_mac(key_id: Id, mac_alg: Mac, input_message: &[u8], expected_mac: &[u8]) -> Result<()> {
+pub fn verify_mac(
+    key_id: Id,
+    mac_alg: Mac,
+    input_message: &[u8],
+    expected_mac: &[u8],
+) -> Result<()> {
     initialized()?;
 
+    if let Mac::FullLength(full_length_alg) = mac_alg {
+        let mac_length = match full_length_alg {
+            FullLengthMac::Hmac { hash_alg } => hash_alg.hash_length(),
+            // The MAC is a block of the cipher of the key.
+            FullLengthMac::CbcMac | FullLengthMac::Cmac => {
+                Attributes::from_key_id(key_id)?.mac_length(mac_alg)?
+            }
+        };
+        if expected_mac.len() < mac_length {
+            error!("The MAC is shorter than the MAC of the full-length algorithm.");
+            return Err(Error::InvalidArgument);
+        }
+    }
+
     let key_handle = key_id.0;
 
     let mac_verify_res = Status::from(unsafe {
@@ -123,61 +190,941 @@ pub fn verify_mac(key_id: Id, mac_alg: Mac, input_message: &[u8], expected_mac:
             input_message.len(),
             expected_mac.as_ptr(),
             expected_mac.len(),
-        )}
-    ).to_result();
+        )
+    })
+    .to_result();
     mac_verify_res?;
     Ok(())
 }
 
+/// Verify the MAC of a message if the implementation supports `mac_alg`
+///
+/// Same as `verify_mac`, except that `Ok(None)` is returned instead of `Error::NotSupported`. A
+/// MAC which does not match is still an error.
+pub fn verify_mac_or_none(
+    key_id: Id,
+    mac_alg: Mac,
+    input_message: &[u8],
+    expected_mac: &[u8],
+) -> Result<Option<()>> {
+    none_if_not_supported(verify_mac(key_id, mac_alg, input_message, expected_mac))
+}
+
+/// Compare two MACs, or other secrets, in constant time
+///
+/// Prefer `verify_mac` and `mac_verify_finish`, which compare the MAC inside the implementation.
+/// This is for values which have already been calculated, for example MACs received in a protocol
+/// and MACs computed earlier. The time taken depends on the length of the values, which is not
+/// secret, but not on their contents or on where they differ. Values of different lengths are not
+/// equal.
+///
+/// # Example
+///
+/// ```
+/// use psa_crypto::operations::mac::secure_compare;
+/// assert!(secure_compare(&[0x42; 64], &[0x42; 64]));
+/// assert!(!secure_compare(&[0x42; 64], &[0x24; 64]));
+/// ```
+pub fn secure_compare(a: &[u8], b: &[u8]) -> bool {
+    // `subtle` ORs the differences of all the bytes together before looking at the result, so
+    // there is no branch on the contents that could end the loop early. The result is
+    // only turned into a `bool` through an optimization barrier, which stops the compiler from
+    // turning the loop back into a comparison that returns at the first difference.
+    a.ct_eq(b).into()
+}
+
 /// Setup MAC Operation, in some cryptography application, one key have a quite long lifetime that
 /// the key will be reused for every message. However, the operation like CMAC need AES
 /// key expansion, and it is expensive, thus this setup operation can do the key expansion
 /// and the subsequence operation can just reused the expanded round keys.
-pub fn mac_sign_setup(operation : &mut MacOperation, key_id: Id, mac_alg: Mac) -> Result<()>{
+pub fn mac_sign_setup(operation: &mut MacOperation, key_id: Id, mac_alg: Mac) -> Result<()> {
     initialized()?;
 
     let key_handle = key_id.0;
     let mac_init_status = Status::from(unsafe {
-        psa_crypto_sys:: psa_mac_sign_setup(
-            operation.as_mut_ptr(),
-            key_handle,
-            mac_alg.into()
-        )
-    }).to_result();
+        psa_crypto_sys::psa_mac_sign_setup(operation.as_mut_ptr(), key_handle, mac_alg.into())
+    })
+    .to_result();
     mac_init_status?;
+    operation.set_algorithm(Some((mac_alg, key_id)));
     Ok(())
 }
 
 /// Function to feed data to MAC operation, in this function we do not need keyID
 /// because it is embedded inside the MacOperation
-pub fn mac_update(operation : &mut MacOperation, input : &[u8]) -> Result<()> {
+pub fn mac_update(operation: &mut MacOperation, input: &[u8]) -> Result<()> {
     initialized()?;
 
-    let mac_update_status = Status::from(unsafe{
-        psa_crypto_sys:: psa_mac_update(
-            operation.as_mut_ptr(),
-            input.as_ptr(),
-            input.len()
-        )
-    }).to_result();
+    let mac_update_status = Status::from(unsafe {
+        psa_crypto_sys::psa_mac_update(operation.as_mut_ptr(), input.as_ptr(), input.len())
+    })
+    .to_result();
     mac_update_status?;
     Ok(())
 }
 
-/// Function to indicate the end of the MAC compute operation in the multi-part MAC 
+/// Function to indicate the end of the MAC compute operation in the multi-part MAC
 /// calculation
-pub fn mac_sign_finish(operation : &mut MacOperation, output: &mut [u8]) -> Result<usize> {
+pub fn mac_sign_finish(operation: &mut MacOperation, output: &mut [u8]) -> Result<usize> {
     initialized()?;
     let mut output_length = 0;
-    
-    let mac_finish_status = Status :: from (unsafe{
-        psa_crypto_sys:: psa_mac_sign_finish(
+
+    let mac_finish_status = Status::from(unsafe {
+        psa_crypto_sys::psa_mac_sign_finish(
             operation.as_mut_ptr(),
             output.as_mut_ptr(),
             output.len(),
-            &mut output_length
+            &mut output_length,
         )
-    }).to_result();
+    })
+    .to_result();
+    // The operation is aborted when it fails to finish.
+    operation.set_algorithm(None);
     mac_finish_status?;
     Ok(output_length)
-}
\ No newline at end of file
+}
+
+/// Finish a multipart MAC calculation, returning the MAC
+///
+/// Same as `mac_sign_finish`, with an output buffer sized from the algorithm and key that the
+/// operation was set up with, by `mac_sign_setup`. The attributes of the key are read here, so
+/// the key must not have been destroyed since. `Error::BadState` is returned if the operation is
+/// not set up.
+///
+/// # Example
+///
+/// ```
+/// use psa_crypto::operations::{key_management, mac};
+/// use psa_crypto::types::algorithm::{FullLengthMac, Hash, Mac};
+/// use psa_crypto::types::key::{Attributes, Type, Lifetime, Policy, UsageFlags};
+/// use psa_crypto::types::operation::MacOperation;
+/// let mac_alg = Mac::FullLength(FullLengthMac::Hmac { hash_alg: Hash::Sha256 });
+/// # let mut usage_flags: UsageFlags = Default::default();
+/// # usage_flags.set_sign_hash();
+/// # let attributes = Attributes {
+/// #     key_type: Type::Hmac,
+/// #     bits: 256,
+/// #     lifetime: Lifetime::Volatile,
+/// #     policy: Policy { usage_flags, permitted_algorithms: mac_alg.into() },
+/// # };
+/// psa_crypto::init().unwrap();
+/// let key = key_management::generate(attributes, None).unwrap();
+/// let mut operation = MacOperation::default();
+/// mac::mac_sign_setup(&mut operation, key, mac_alg).unwrap();
+/// mac::mac_update(&mut operation, b"message").unwrap();
+/// let mac = mac::mac_sign_finish_vec(&mut operation).unwrap();
+/// assert_eq!(mac.len(), 32);
+/// ```
+#[cfg(feature = "std")]
+pub fn mac_sign_finish_vec(operation: &mut MacOperation) -> Result<Vec<u8>> {
+    let (mac_alg, key_id) = operation.algorithm_and_key().ok_or_else(|| {
+        error!("The MAC operation is not set up.");
+        Error::BadState
+    })?;
+    let mut mac = vec![0; Attributes::from_key_id(key_id)?.mac_length(mac_alg)?];
+    let length = mac_sign_finish(operation, &mut mac)?;
+    mac.truncate(length);
+    Ok(mac)
+}
+
+/// Set up a multipart MAC verification operation
+///
+/// The message is then passed with `mac_update`, and the MAC is checked with `mac_verify_finish`.
+pub fn mac_verify_setup(operation: &mut MacOperation, key_id: Id, mac_alg: Mac) -> Result<()> {
+    initialized()?;
+
+    Status::from(unsafe {
+        psa_crypto_sys::psa_mac_verify_setup(operation.as_mut_ptr(), key_id.0, mac_alg.into())
+    })
+    .to_result()?;
+    operation.set_algorithm(Some((mac_alg, key_id)));
+    Ok(())
+}
+
+/// Finish a multipart MAC verification operation, comparing the MAC of the message with
+/// `expected_mac`
+pub fn mac_verify_finish(operation: &mut MacOperation, expected_mac: &[u8]) -> Result<()> {
+    initialized()?;
+
+    let status = Status::from(unsafe {
+        psa_crypto_sys::psa_mac_verify_finish(
+            operation.as_mut_ptr(),
+            expected_mac.as_ptr(),
+            expected_mac.len(),
+        )
+    })
+    .to_result();
+    // The operation is aborted when it fails to finish.
+    operation.set_algorithm(None);
+    status
+}
+
+/// Calculate the MAC of a message pulled chunk by chunk from `next`
+///
+/// `next` is called until it returns `Ok(None)`, each chunk it returns is added to the message.
+/// This suits sources which do not implement `std::io::Read`, like generators. If `next` returns
+/// an error, the operation is aborted and the error returned. The function returns the MAC.
+///
+/// # Example
+///
+/// ```
+/// use psa_crypto::operations::{key_management, mac::compute_mac_pull};
+/// use psa_crypto::types::algorithm::{Hash, Mac, FullLengthMac};
+/// use psa_crypto::types::key::{Attributes, Type, Lifetime, Policy, UsageFlags};
+/// # let mut usage_flags: UsageFlags = Default::default();
+/// # usage_flags.set_sign_hash();
+/// # let attributes = Attributes {
+/// #     key_type: Type::Hmac,
+/// #     bits: 256,
+/// #     lifetime: Lifetime::Volatile,
+/// #     policy: Policy {
+/// #         usage_flags,
+/// #         permitted_algorithms: Mac::FullLength(FullLengthMac::Hmac { hash_alg: Hash::Sha256 }).into(),
+/// #     },
+/// # };
+/// psa_crypto::init().unwrap();
+/// let my_key = key_management::generate(attributes, None).unwrap();
+/// let mac_alg = Mac::FullLength(FullLengthMac::Hmac { hash_alg: Hash::Sha256 });
+///
+/// let mut chunks = (0..4u8).map(|i| vec![i; 100]);
+/// let mac = compute_mac_pull(my_key, mac_alg, || Ok(chunks.next())).unwrap();
+/// assert_eq!(mac.len(), 32);
+/// ```
+#[cfg(feature = "std")]
+pub fn compute_mac_pull(
+    key_id: Id,
+    mac_alg: Mac,
+    mut next: impl FnMut() -> Result<Option<Vec<u8>>>,
+) -> Result<Vec<u8>> {
+    let mut mac = vec![0; Attributes::from_key_id(key_id)?.mac_length(mac_alg)?];
+    let mut operation = AbortGuard::<MacOperation>::default();
+    mac_sign_setup(&mut operation, key_id, mac_alg)?;
+    while let Some(chunk) = next()? {
+        mac_update(&mut operation, &chunk)?;
+    }
+    let mac_length = mac_sign_finish(&mut operation, &mut mac)?;
+    mac.truncate(mac_length);
+    Ok(mac)
+}
+
+/// A value with a canonical byte encoding over which its MAC is calculated
+///
+/// The encoding is the responsibility of the implementer: two values which must be told apart
+/// need different encodings, and the encoding must not change between the sender and the
+/// receiver. Byte containers implementing `AsRef<[u8]>` are their own encoding.
+#[cfg(feature = "std")]
+pub trait Maccable {
+    /// The canonical encoding of the value
+    fn mac_bytes(&self) -> Cow<'_, [u8]>;
+}
+
+#[cfg(feature = "std")]
+impl<T: AsRef<[u8]> + ?Sized> Maccable for T {
+    fn mac_bytes(&self) -> Cow<'_, [u8]> {
+        Cow::Borrowed(self.as_ref())
+    }
+}
+
+/// Calculate the MAC of the canonical encoding of `value`
+///
+/// The key must allow `sign_message`. The function returns the MAC.
+///
+/// # Example
+///
+/// ```
+/// use psa_crypto::operations::{key_management, mac};
+/// use psa_crypto::operations::mac::Maccable;
+/// use psa_crypto::types::algorithm::{FullLengthMac, Hash, Mac};
+/// use psa_crypto::types::key::{Attributes, Type, Lifetime, Policy, UsageFlags};
+/// use std::borrow::Cow;
+/// # let mut usage_flags: UsageFlags = Default::default();
+/// # usage_flags.set_sign_hash();
+/// # let attributes = Attributes {
+/// #     key_type: Type::Hmac,
+/// #     bits: 256,
+/// #     lifetime: Lifetime::Volatile,
+/// #     policy: Policy {
+/// #         usage_flags,
+/// #         permitted_algorithms: Mac::FullLength(FullLengthMac::Hmac { hash_alg: Hash::Sha256 }).into(),
+/// #     },
+/// # };
+/// struct Transfer {
+///     amount: u64,
+///     account: u32,
+/// }
+///
+/// impl Maccable for Transfer {
+///     fn mac_bytes(&self) -> Cow<'_, [u8]> {
+///         let mut bytes = self.amount.to_be_bytes().to_vec();
+///         bytes.extend_from_slice(&self.account.to_be_bytes());
+///         Cow::Owned(bytes)
+///     }
+/// }
+///
+/// psa_crypto::init().unwrap();
+/// let my_key = key_management::generate(attributes, None).unwrap();
+/// let mac_alg = Mac::FullLength(FullLengthMac::Hmac { hash_alg: Hash::Sha256 });
+/// let transfer = Transfer { amount: 100, account: 7 };
+/// let mac = mac::compute_mac_of(my_key, mac_alg, &transfer).unwrap();
+/// assert_eq!(mac.len(), 32);
+/// ```
+#[cfg(feature = "std")]
+pub fn compute_mac_of<T: Maccable + ?Sized>(
+    key_id: Id,
+    mac_alg: Mac,
+    value: &T,
+) -> Result<Vec<u8>> {
+    let mut mac = vec![0; Attributes::from_key_id(key_id)?.mac_length(mac_alg)?];
+    let mac_length = compute_mac(key_id, mac_alg, &value.mac_bytes(), &mut mac)?;
+    mac.truncate(mac_length);
+    Ok(mac)
+}
+
+/// MAC of a sequence of records, each one bound to its position in the sequence
+///
+/// The MAC of each record is calculated over the record's sequence number and length, both
+/// encoded as 64-bit big-endian integers, followed by the record itself. The sequence number is
+/// incremented after each record signed or verified successfully, so a record which is replayed,
+/// dropped or reordered fails verification on the receiving side. A receiver which gets the
+/// sequence number of each record from elsewhere, for example from a transport which can reorder
+/// records, checks them with `verify_at` instead.
+///
+/// # Example
+///
+/// ```
+/// use psa_crypto::operations::{key_management, mac::SequencedMac};
+/// use psa_crypto::types::algorithm::{FullLengthMac, Hash, Mac};
+/// use psa_crypto::types::key::{Attributes, Type, Lifetime, Policy, UsageFlags};
+/// let mac_alg = Mac::FullLength(FullLengthMac::Hmac { hash_alg: Hash::Sha256 });
+/// # let mut usage_flags: UsageFlags = Default::default();
+/// # usage_flags.set_sign_hash().set_verify_hash();
+/// # let attributes = Attributes {
+/// #     key_type: Type::Hmac,
+/// #     bits: 256,
+/// #     lifetime: Lifetime::Volatile,
+/// #     policy: Policy {
+/// #         usage_flags,
+/// #         permitted_algorithms: mac_alg.into(),
+/// #     },
+/// # };
+/// psa_crypto::init().unwrap();
+/// let my_key = key_management::generate(attributes, None).unwrap();
+/// let mut sender = SequencedMac::new(my_key, mac_alg, 0);
+/// let mut receiver = SequencedMac::new(my_key, mac_alg, 0);
+///
+/// let mut mac = [0; 32];
+/// for record in [&b"first record"[..], &b"second record"[..]].iter() {
+///     let size = sender.sign(record, &mut mac).unwrap();
+///     receiver.verify(record, &mac[..size]).unwrap();
+/// }
+/// ```
+// Neither Copy nor Clone: a copy would sign records with sequence numbers already used by the
+// original.
+#[allow(missing_copy_implementations)]
+#[derive(Debug)]
+pub struct SequencedMac {
+    key_id: Id,
+    mac_alg: Mac,
+    sequence: u64,
+}
+
+impl SequencedMac {
+    /// Create a sequence whose first record has the sequence number `first`
+    pub fn new(key_id: Id, mac_alg: Mac, first: u64) -> Self {
+        SequencedMac {
+            key_id,
+            mac_alg,
+            sequence: first,
+        }
+    }
+
+    /// Sequence number of the next record to sign or verify
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Calculate the MAC of the next record
+    ///
+    /// The MAC is written in `mac`. The function returns the number of bytes written. The sequence
+    /// number is only incremented if the MAC was calculated.
+    pub fn sign(&mut self, record: &[u8], mac: &mut [u8]) -> Result<usize> {
+        let next = self.next()?;
+        let mut operation = AbortGuard::<MacOperation>::default();
+        mac_sign_setup(&mut operation, self.key_id, self.mac_alg)?;
+        Self::update(&mut operation, self.sequence, record)?;
+        let size = mac_sign_finish(&mut operation, mac)?;
+        self.sequence = next;
+        Ok(size)
+    }
+
+    /// Verify the MAC of the next record
+    ///
+    /// The record is expected to have the current sequence number. The sequence number is only
+    /// incremented if the MAC is valid.
+    pub fn verify(&mut self, record: &[u8], mac: &[u8]) -> Result<()> {
+        let next = self.next()?;
+        self.verify_at(self.sequence, record, mac)?;
+        self.sequence = next;
+        Ok(())
+    }
+
+    /// Verify the MAC of the record with the sequence number `sequence`
+    ///
+    /// The sequence number of this `SequencedMac` is neither used nor changed, so records can be
+    /// checked in any order. Rejecting a record whose sequence number was already seen is then up
+    /// to the caller.
+    pub fn verify_at(&self, sequence: u64, record: &[u8], mac: &[u8]) -> Result<()> {
+        let mut operation = AbortGuard::<MacOperation>::default();
+        mac_verify_setup(&mut operation, self.key_id, self.mac_alg)?;
+        Self::update(&mut operation, sequence, record)?;
+        mac_verify_finish(&mut operation, mac)
+    }
+
+    fn next(&self) -> Result<u64> {
+        self.sequence.checked_add(1).ok_or_else(|| {
+            error!("The MAC sequence number is exhausted.");
+            Error::BadState
+        })
+    }
+
+    fn update(operation: &mut MacOperation, sequence: u64, record: &[u8]) -> Result<()> {
+        mac_update(operation, &sequence.to_be_bytes())?;
+        mac_update(operation, &(record.len() as u64).to_be_bytes())?;
+        mac_update(operation, record)
+    }
+}
+
+/// MAC of records of a versioned format, bound to the version of the format
+///
+/// The MAC of each record is calculated over a header followed by the record. The header is the
+/// version byte followed by `0x00` for records without a type, or by `0x01` and the record type
+/// byte for typed records. A MAC calculated for one version, or one record type, then fails the
+/// verification with any other, so changing the format version invalidates all the old MACs.
+///
+/// # Example
+///
+/// ```
+/// use psa_crypto::operations::{key_management, mac::VersionedMac};
+/// use psa_crypto::types::algorithm::{FullLengthMac, Hash, Mac};
+/// use psa_crypto::types::key::{Attributes, Type, Lifetime, Policy, UsageFlags};
+/// use psa_crypto::types::status::Error;
+/// let mac_alg = Mac::FullLength(FullLengthMac::Hmac { hash_alg: Hash::Sha256 });
+/// # let mut usage_flags: UsageFlags = Default::default();
+/// # usage_flags.set_sign_hash().set_verify_hash();
+/// # let attributes = Attributes {
+/// #     key_type: Type::Hmac,
+/// #     bits: 256,
+/// #     lifetime: Lifetime::Volatile,
+/// #     policy: Policy {
+/// #         usage_flags,
+/// #         permitted_algorithms: mac_alg.into(),
+/// #     },
+/// # };
+/// psa_crypto::init().unwrap();
+/// let my_key = key_management::generate(attributes, None).unwrap();
+/// let v1 = VersionedMac::new(my_key, mac_alg, 1);
+/// let v2 = VersionedMac::new(my_key, mac_alg, 2);
+///
+/// let mut mac = [0; 32];
+/// let size = v1.compute(b"log record", &mut mac).unwrap();
+/// v1.verify(b"log record", &mac[..size]).unwrap();
+/// assert_eq!(v2.verify(b"log record", &mac[..size]), Err(Error::InvalidSignature));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct VersionedMac {
+    key_id: Id,
+    mac_alg: Mac,
+    version: u8,
+    record_type: Option<u8>,
+}
+
+impl VersionedMac {
+    /// MAC of the records of version `version` of a format
+    pub fn new(key_id: Id, mac_alg: Mac, version: u8) -> Self {
+        VersionedMac {
+            key_id,
+            mac_alg,
+            version,
+            record_type: None,
+        }
+    }
+
+    /// MAC of the records of type `record_type` of version `version` of a format
+    pub fn with_record_type(key_id: Id, mac_alg: Mac, version: u8, record_type: u8) -> Self {
+        VersionedMac {
+            key_id,
+            mac_alg,
+            version,
+            record_type: Some(record_type),
+        }
+    }
+
+    /// Version of the format
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Type of the records, if they have one
+    pub fn record_type(&self) -> Option<u8> {
+        self.record_type
+    }
+
+    /// Calculate the MAC of a record
+    ///
+    /// The MAC is written in `mac`. The function returns the number of bytes written.
+    pub fn compute(&self, record: &[u8], mac: &mut [u8]) -> Result<usize> {
+        let mut operation = AbortGuard::<MacOperation>::default();
+        mac_sign_setup(&mut operation, self.key_id, self.mac_alg)?;
+        self.update(&mut operation, record)?;
+        mac_sign_finish(&mut operation, mac)
+    }
+
+    /// Verify the MAC of a record
+    pub fn verify(&self, record: &[u8], mac: &[u8]) -> Result<()> {
+        let mut operation = AbortGuard::<MacOperation>::default();
+        mac_verify_setup(&mut operation, self.key_id, self.mac_alg)?;
+        self.update(&mut operation, record)?;
+        mac_verify_finish(&mut operation, mac)
+    }
+
+    fn update(&self, operation: &mut MacOperation, record: &[u8]) -> Result<()> {
+        match self.record_type {
+            None => mac_update(operation, &[self.version, 0x00])?,
+            Some(record_type) => mac_update(operation, &[self.version, 0x01, record_type])?,
+        }
+        mac_update(operation, record)
+    }
+}
+
+/// State of a MAC key ratchet
+///
+/// Every message is authenticated with its own one-time HMAC key, derived with HKDF from the
+/// current chain key and the message counter. The chain key is then replaced by the next one,
+/// also derived with HKDF, and destroyed, so that the keys of past messages cannot be recovered
+/// from the current state. For the message with counter `n`, the HKDF info is `n` encoded as a
+/// 64-bit big-endian integer followed by a byte set to 0 for the message key and to 1 for the next
+/// chain key. There is no salt.
+///
+/// The chain key must be a `Type::Derive` key allowing `derive` with `KeyDerivation::Hkdf`. The
+/// message keys have the size of the output of the HMAC hash. All the keys derived are volatile.
+/// The current chain key is destroyed when the ratchet is dropped.
+///
+/// # Example
+///
+/// ```
+/// use psa_crypto::operations::{key_management, mac};
+/// use psa_crypto::operations::mac::MacRatchet;
+/// use psa_crypto::types::algorithm::{FullLengthMac, Hash, KeyDerivation, Mac};
+/// use psa_crypto::types::key::{Attributes, Type, Lifetime, Policy, UsageFlags};
+/// let mac_alg = Mac::FullLength(FullLengthMac::Hmac { hash_alg: Hash::Sha256 });
+/// # let mut usage_flags: UsageFlags = Default::default();
+/// # usage_flags.set_derive();
+/// # let attributes = Attributes {
+/// #     key_type: Type::Derive,
+/// #     bits: 256,
+/// #     lifetime: Lifetime::Volatile,
+/// #     policy: Policy {
+/// #         usage_flags,
+/// #         permitted_algorithms: KeyDerivation::Hkdf { hash_alg: Hash::Sha256 }.into(),
+/// #     },
+/// # };
+/// psa_crypto::init().unwrap();
+/// let sender_key = key_management::import(attributes, None, &[0x42; 32]).unwrap();
+/// let receiver_key = key_management::import(attributes, None, &[0x42; 32]).unwrap();
+/// let mut sender = MacRatchet::new(sender_key, mac_alg, 0).unwrap();
+/// let mut receiver = MacRatchet::new(receiver_key, mac_alg, 0).unwrap();
+///
+/// let mut mac = [0; 32];
+/// // Safe because the chain keys are only used by the ratchets.
+/// unsafe {
+///     let size = mac::ratchet_mac(&mut sender, b"first message", &mut mac).unwrap();
+///     mac::ratchet_verify_mac(&mut receiver, b"first message", &mac[..size]).unwrap();
+/// }
+/// assert_eq!(sender.counter(), 1);
+/// ```
+// Copying the state would let both copies destroy the same chain key.
+#[allow(missing_copy_implementations)]
+#[derive(Debug)]
+pub struct MacRatchet {
+    chain_key: Id,
+    chain_attributes: Attributes,
+    hash_alg: Hash,
+    mac_alg: Mac,
+    message_key_attributes: Attributes,
+    counter: u64,
+}
+
+const MESSAGE_KEY_LABEL: u8 = 0;
+const CHAIN_KEY_LABEL: u8 = 1;
+
+impl MacRatchet {
+    /// Start a ratchet from `chain_key`, the next message having the counter `counter`
+    ///
+    /// `mac_alg` must be an HMAC algorithm. The ratchet owns `chain_key` from now on: it is
+    /// destroyed when the ratchet advances or is dropped, or before returning if the ratchet
+    /// cannot be started.
+    pub fn new(chain_key: Id, mac_alg: Mac, counter: u64) -> Result<Self> {
+        let chain_key = OwnedKey(chain_key);
+        let mac_hash_alg = match mac_alg {
+            Mac::FullLength(FullLengthMac::Hmac { hash_alg })
+            | Mac::Truncated {
+                mac_alg: FullLengthMac::Hmac { hash_alg },
+                ..
+            } => hash_alg,
+            _ => {
+                error!("The MAC ratchet only supports HMAC.");
+                return Err(Error::NotSupported);
+            }
+        };
+        let mut chain_attributes = Attributes::from_key_id(chain_key.0)?;
+        let hash_alg = match chain_attributes.policy.permitted_algorithms {
+            Algorithm::KeyDerivation(KeyDerivation::Hkdf { hash_alg }) => hash_alg,
+            _ => {
+                error!("The chain key of a MAC ratchet must allow HKDF.");
+                return Err(Error::InvalidArgument);
+            }
+        };
+        chain_attributes.lifetime = Lifetime::Volatile;
+
+        let mut usage_flags: UsageFlags = Default::default();
+        let _ = usage_flags.set_sign_hash().set_verify_hash();
+        let message_key_attributes = Attributes {
+            key_type: Type::Hmac,
+            bits: mac_hash_alg.hash_length() * 8,
+            lifetime: Lifetime::Volatile,
+            policy: Policy {
+                usage_flags,
+                permitted_algorithms: mac_alg.into(),
+            },
+        };
+
+        Ok(MacRatchet {
+            chain_key: chain_key.into_id(),
+            chain_attributes,
+            hash_alg,
+            mac_alg,
+            message_key_attributes,
+            counter,
+        })
+    }
+
+    /// Counter of the next message to authenticate or verify
+    pub fn counter(&self) -> u64 {
+        self.counter
+    }
+
+    fn derive(&self, label: u8, attributes: Attributes) -> Result<Id> {
+        let mut info = [0; 9];
+        info[..8].copy_from_slice(&self.counter.to_be_bytes());
+        info[8] = label;
+        let operation = Operation {
+            inputs: Inputs::Hkdf {
+                hash_alg: self.hash_alg,
+                salt: None,
+                secret: InputSecret::Input(Input::Key(self.chain_key)),
+                info: Input::Bytes(&info),
+            },
+            capacity: None,
+        };
+        key_derivation::output_key(operation, attributes, None)
+    }
+
+    /// Run `f` with the key of the current message, advancing the ratchet if it succeeds
+    unsafe fn advance<T>(&mut self, f: impl FnOnce(Id) -> Result<T>) -> Result<T> {
+        let next_counter = self.counter.checked_add(1).ok_or_else(|| {
+            error!("The MAC ratchet counter is exhausted.");
+            Error::BadState
+        })?;
+        let output = {
+            let message_key =
+                OwnedKey(self.derive(MESSAGE_KEY_LABEL, self.message_key_attributes)?);
+            f(message_key.0)?
+        };
+        let next_chain_key = self.derive(CHAIN_KEY_LABEL, self.chain_attributes)?;
+        let previous_chain_key = mem::replace(&mut self.chain_key, next_chain_key);
+        self.counter = next_counter;
+        destroy(previous_chain_key)?;
+        Ok(output)
+    }
+}
+
+impl Drop for MacRatchet {
+    fn drop(&mut self) {
+        // Safe because the chain key is owned by the ratchet. Errors cannot be reported from here.
+        let _ = unsafe { destroy(self.chain_key) };
+    }
+}
+
+/// A key owned by this module, destroyed when dropped
+struct OwnedKey(Id);
+
+impl OwnedKey {
+    /// Give up the ownership of the key, which is not destroyed
+    fn into_id(self) -> Id {
+        let id = self.0;
+        mem::forget(self);
+        id
+    }
+}
+
+impl Drop for OwnedKey {
+    fn drop(&mut self) {
+        // Safe because the key ID is not used outside of this module. Errors cannot be reported
+        // from here.
+        let _ = unsafe { destroy(self.0) };
+    }
+}
+
+/// Calculate the MAC of the next message of a ratchet and advance the ratchet
+///
+/// The MAC is written in `mac`. The function returns the number of bytes written. The one-time key
+/// of the message is destroyed whether the MAC could be calculated or not. The ratchet only
+/// advances if the MAC was calculated. If destroying the previous chain key fails, the error is
+/// returned but the ratchet has already advanced.
+///
+/// # Safety
+///
+/// Same as `key_management::destroy`: the caller must make sure that the current chain key is not
+/// used by another thread.
+pub unsafe fn ratchet_mac(
+    ratchet: &mut MacRatchet,
+    message: &[u8],
+    mac: &mut [u8],
+) -> Result<usize> {
+    let mac_alg = ratchet.mac_alg;
+    ratchet.advance(|key| compute_mac(key, mac_alg, message, mac))
+}
+
+/// Verify the MAC of the next message of a ratchet and advance the ratchet
+///
+/// The ratchet only advances if the MAC is valid, so that a forged message does not desynchronise
+/// the verifier from the sender. Otherwise, this behaves like `ratchet_mac`.
+///
+/// # Safety
+///
+/// Same as `key_management::destroy`: the caller must make sure that the current chain key is not
+/// used by another thread.
+pub unsafe fn ratchet_verify_mac(
+    ratchet: &mut MacRatchet,
+    message: &[u8],
+    mac: &[u8],
+) -> Result<()> {
+    let mac_alg = ratchet.mac_alg;
+    ratchet.advance(|key| verify_mac(key, mac_alg, message, mac))
+}
+
+const AES_BLOCK_SIZE: usize = 16;
+
+/// AES-CMAC (RFC 4493) calculation with the subkeys derived once per key
+///
+/// `compute_mac` with `FullLengthMac::Cmac` derives the K1 and K2 subkeys again for every message.
+/// This context derives them once when it is created and keeps them for all the messages
+/// authenticated with it, running the CMAC chaining itself on top of single block AES-ECB
+/// encryptions. The output is the same as the one of `compute_mac` with `FullLengthMac::Cmac`.
+///
+/// The key must be an AES key allowing `encrypt` with `Cipher::EcbNoPadding`. As anyone able to
+/// encrypt with such a key can compute its CMAC values, the key should only be used through this
+/// context. The subkeys are erased from memory when the context is dropped.
+///
+/// The backend still schedules the AES key on every message, so the saving is the subkey
+/// derivation only: one block encryption per message. With Mbed TLS this makes messages of a few
+/// blocks about a third faster than `compute_mac`, while for messages of a kilobyte or more the
+/// call per block costs about as much as it saves. `cmac_context_speed` in the MAC tests measures
+/// both on the target. Prefer `compute_mac` unless most messages are short.
+///
+/// # Example
+///
+/// ```
+/// use psa_crypto::operations::{key_management, mac::CmacContext};
+/// use psa_crypto::types::algorithm::Cipher;
+/// use psa_crypto::types::key::{Attributes, Type, Lifetime, Policy, UsageFlags};
+/// # const KEY_DATA: [u8; 16] = [0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c];
+/// let mut usage_flags: UsageFlags = Default::default();
+/// usage_flags.set_encrypt();
+/// let attributes = Attributes {
+///     key_type: Type::Aes,
+///     bits: 128,
+///     lifetime: Lifetime::Volatile,
+///     policy: Policy {
+///         usage_flags,
+///         permitted_algorithms: Cipher::EcbNoPadding.into(),
+///     },
+/// };
+/// psa_crypto::init().unwrap();
+/// let my_key = key_management::import(attributes, None, &KEY_DATA).unwrap();
+/// let context = CmacContext::new(my_key).unwrap();
+///
+/// let mut mac = [0; 16];
+/// for message in [&b"first message"[..], &b"second message"[..]].iter() {
+///     let size = context.compute(message, &mut mac).unwrap();
+/// }
+/// ```
+pub struct CmacContext {
+    key_id: Id,
+    k1: [u8; AES_BLOCK_SIZE],
+    k2: [u8; AES_BLOCK_SIZE],
+}
+
+impl CmacContext {
+    /// Derive and cache the CMAC subkeys of an AES key
+    pub fn new(key_id: Id) -> Result<Self> {
+        if Attributes::from_key_id(key_id)?.key_type != Type::Aes {
+            error!("CMAC subkey caching is only supported with AES keys.");
+            return Err(Error::NotSupported);
+        }
+
+        // L = AES(K, 0^128), which is the chain of a single zero block.
+        let mut context = CmacContext {
+            key_id,
+            k1: [0; AES_BLOCK_SIZE],
+            k2: [0; AES_BLOCK_SIZE],
+        };
+        cmac_chain(key_id, &[], &[0; AES_BLOCK_SIZE], &mut context.k1)?;
+        double(&mut context.k1);
+        context.k2 = context.k1;
+        double(&mut context.k2);
+
+        Ok(context)
+    }
+
+    /// Calculate the CMAC of a message
+    ///
+    /// The MAC is written in `mac`, which must be at least 16 bytes long. The function returns the
+    /// number of bytes written.
+    pub fn compute(&self, message: &[u8], mac: &mut [u8]) -> Result<usize> {
+        if mac.len() < AES_BLOCK_SIZE {
+            error!("The MAC buffer is too small for AES-CMAC.");
+            return Err(Error::BufferTooSmall);
+        }
+
+        // The last block is XORed with K1 when it is complete, or padded and XORed with K2.
+        let last_length = match message.len() % AES_BLOCK_SIZE {
+            0 if !message.is_empty() => AES_BLOCK_SIZE,
+            partial => partial,
+        };
+        let (blocks, last) = message.split_at(message.len() - last_length);
+        let mut last_block = [0; AES_BLOCK_SIZE];
+        last_block[..last_length].copy_from_slice(last);
+        let subkey = if last_length == AES_BLOCK_SIZE {
+            &self.k1
+        } else {
+            last_block[last_length] = 0x80;
+            &self.k2
+        };
+        last_block
+            .iter_mut()
+            .zip(subkey.iter())
+            .for_each(|(byte, key_byte)| *byte ^= key_byte);
+
+        let mut tag = [0; AES_BLOCK_SIZE];
+        let result = cmac_chain(self.key_id, blocks, &last_block, &mut tag);
+        last_block.zeroize();
+        result?;
+        mac[..AES_BLOCK_SIZE].copy_from_slice(&tag);
+        tag.zeroize();
+
+        Ok(AES_BLOCK_SIZE)
+    }
+}
+
+impl fmt::Debug for CmacContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CmacContext")
+            .field("key_id", &self.key_id)
+            .finish()
+    }
+}
+
+impl Drop for CmacContext {
+    fn drop(&mut self) {
+        self.k1.zeroize();
+        self.k2.zeroize();
+    }
+}
+
+/// Multiply a block by x in GF(2^128), as used to derive the CMAC subkeys
+fn double(block: &mut [u8; AES_BLOCK_SIZE]) {
+    let msb = block[0] >> 7;
+    for i in 0..AES_BLOCK_SIZE - 1 {
+        block[i] = (block[i] << 1) | (block[i + 1] >> 7);
+    }
+    block[AES_BLOCK_SIZE - 1] = (block[AES_BLOCK_SIZE - 1] << 1) ^ (0x87 * msb);
+}
+
+/// Chain `blocks` followed by `last_block` through AES-ECB, one block at a time, keeping the
+/// last ciphertext block only
+///
+/// Each block is XORed with the previous ciphertext block before being encrypted, starting from a
+/// zero block. `blocks` must be a whole number of blocks.
+fn cmac_chain(
+    key_id: Id,
+    blocks: &[u8],
+    last_block: &[u8; AES_BLOCK_SIZE],
+    tag: &mut [u8; AES_BLOCK_SIZE],
+) -> Result<()> {
+    initialized()?;
+
+    let mut operation: psa_crypto_sys::psa_cipher_operation_t =
+        unsafe { psa_crypto_sys::psa_cipher_operation_init() };
+
+    Status::from(unsafe {
+        psa_crypto_sys::psa_cipher_encrypt_setup(
+            &mut operation,
+            key_id.0,
+            Cipher::EcbNoPadding.into(),
+        )
+    })
+    .to_result()?;
+
+    // The intermediate blocks are secret as well, as they are what the tag is computed from.
+    let mut input = [0; AES_BLOCK_SIZE];
+    let status = cmac_chain_run(&mut operation, blocks, last_block, tag, &mut input);
+    input.zeroize();
+
+    if status.is_err() {
+        tag.zeroize();
+        let _ = Status::from(unsafe { psa_crypto_sys::psa_cipher_abort(&mut operation) });
+    }
+    status
+}
+
+fn cmac_chain_run(
+    operation: &mut psa_crypto_sys::psa_cipher_operation_t,
+    blocks: &[u8],
+    last_block: &[u8; AES_BLOCK_SIZE],
+    tag: &mut [u8; AES_BLOCK_SIZE],
+    input: &mut [u8; AES_BLOCK_SIZE],
+) -> Result<()> {
+    // The tag holds the previous ciphertext block.
+    *tag = [0; AES_BLOCK_SIZE];
+    for block in blocks
+        .chunks(AES_BLOCK_SIZE)
+        .chain(core::iter::once(&last_block[..]))
+    {
+        input
+            .iter_mut()
+            .zip(tag.iter().zip(block.iter()))
+            .for_each(|(byte, (previous, message))| *byte = previous ^ message);
+        let mut output_length = 0;
+        Status::from(unsafe {
+            psa_crypto_sys::psa_cipher_update(
+                operation,
+                input.as_ptr(),
+                input.len(),
+                tag.as_mut_ptr(),
+                tag.len(),
+                &mut output_length,
+            )
+        })
+        .to_result()?;
+        if output_length != AES_BLOCK_SIZE {
+            return Err(Error::GenericError);
+        }
+    }
+
+    let mut output_length = 0;
+    Status::from(unsafe {
+        psa_crypto_sys::psa_cipher_finish(operation, input.as_mut_ptr(), 0, &mut output_length)
+    })
+    .to_result()
+}
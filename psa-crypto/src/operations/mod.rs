@@ -2,15 +2,38 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! # PSA Operations
+//!
+//! ## Integer encoding
+//!
+//! The helpers of this module which put integers in a message, a MAC input or a key derivation
+//! input, like `mac::SequencedMac`, `mac::MacRatchet` or the `envelope` format, always encode them
+//! as unsigned big-endian integers of the fixed width given in their documentation. A helper for a
+//! protocol needing little-endian integers would say so in its name, with an `_le` suffix. There
+//! is none at the moment.
+//!
+//! ## Empty inputs
+//!
+//! Empty messages, additional data, plaintexts, salts and output buffers are passed to the C
+//! library as the pointer of the empty slice, which is never null, with a length of zero. They
+//! have the meaning the algorithm gives them: the MAC of an empty message or an AEAD tag over
+//! nothing are computed as for any other input. Optional inputs, like the salt of
+//! `asym_encryption`, are passed as a null pointer with a length of zero when absent. Keys cannot
+//! be empty: importing zero bytes of key material fails with `Error::InvalidArgument`.
 
 pub mod aead;
 pub mod asym_encryption;
 pub mod asym_signature;
 pub mod cipher;
+#[cfg(feature = "cose")]
+pub mod cose;
+#[cfg(feature = "std")]
+pub mod envelope;
+pub mod hash;
 pub mod key_agreement;
 pub mod key_derivation;
 pub mod key_management;
+pub mod key_ring;
 pub mod mac; //Mbed Crypto does not support mac compute or verify yet (as of 16/07/20)
-pub mod hash;
 pub mod message_digest;
 pub mod other;
+pub mod single_use;
@@ -6,7 +6,13 @@
 //! See the PSA Crypto API for the format of the different parameters used in this module.
 
 use crate::initialized;
-use crate::types::status::{Result, Status};
+use crate::types::algorithm::{
+    Aead, AeadWithDefaultLengthTag, Algorithm, AsymmetricSignature, Hash, SignHash,
+};
+use crate::types::status::{Error, Result, Status};
+use core::convert::TryFrom;
+use core::fmt;
+use log::error;
 
 /// Generate a buffer of random bytes.
 ///
@@ -31,3 +37,204 @@ pub fn generate_random(output: &mut [u8]) -> Result<()> {
 
     Ok(())
 }
+
+/// Algorithms compiled into the PSA Crypto implementation
+///
+/// The capabilities come from the configuration the implementation was built with, so they can
+/// be checked before `init` is called, for example to fail early with a clear message when a
+/// test runs against a build without a required primitive. Only hash, AEAD and asymmetric
+/// algorithms are reported.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct BackendCapabilities {
+    _private: (),
+}
+
+/// The algorithms listed by `BackendCapabilities`
+#[allow(deprecated)]
+const HASHES: [Hash; 15] = [
+    Hash::Md2,
+    Hash::Md4,
+    Hash::Md5,
+    Hash::Ripemd160,
+    Hash::Sha1,
+    Hash::Sha224,
+    Hash::Sha256,
+    Hash::Sha384,
+    Hash::Sha512,
+    Hash::Sha512_224,
+    Hash::Sha512_256,
+    Hash::Sha3_224,
+    Hash::Sha3_256,
+    Hash::Sha3_384,
+    Hash::Sha3_512,
+];
+const AEADS: [AeadWithDefaultLengthTag; 3] = [
+    AeadWithDefaultLengthTag::Ccm,
+    AeadWithDefaultLengthTag::Gcm,
+    AeadWithDefaultLengthTag::Chacha20Poly1305,
+];
+const ASYMMETRIC_SIGNATURES: [AsymmetricSignature; 6] = [
+    AsymmetricSignature::RsaPkcs1v15Sign {
+        hash_alg: SignHash::Any,
+    },
+    AsymmetricSignature::RsaPkcs1v15SignRaw,
+    AsymmetricSignature::RsaPss {
+        hash_alg: SignHash::Any,
+    },
+    AsymmetricSignature::EcdsaAny,
+    AsymmetricSignature::Ecdsa {
+        hash_alg: SignHash::Any,
+    },
+    AsymmetricSignature::DeterministicEcdsa {
+        hash_alg: SignHash::Any,
+    },
+];
+
+impl BackendCapabilities {
+    /// Whether an algorithm is compiled in
+    ///
+    /// The hash of a signature or encryption algorithm must be compiled in as well, and AEAD
+    /// algorithms are supported with any tag length. Algorithms other than hash, AEAD or
+    /// asymmetric ones are reported as not supported.
+    pub fn supports(self, alg: impl Into<Algorithm>) -> bool {
+        psa_crypto_sys::psa_algorithm_t::try_from(alg.into())
+            .map_or(false, psa_crypto_sys::PSA_WANT_ALG)
+    }
+
+    /// Check that an algorithm is compiled in, returning `Error::NotSupported` otherwise
+    pub fn require(self, alg: impl Into<Algorithm>) -> Result<()> {
+        let alg = alg.into();
+        if self.supports(alg) {
+            Ok(())
+        } else {
+            error!(
+                "{:?} is not supported by this build of the PSA Crypto implementation.",
+                alg
+            );
+            Err(Error::NotSupported)
+        }
+    }
+
+    /// Hash algorithms compiled in
+    pub fn hashes(self) -> impl Iterator<Item = Hash> + Clone {
+        HASHES
+            .iter()
+            .copied()
+            .filter(move |&alg| self.supports(alg))
+    }
+
+    /// AEAD algorithms compiled in
+    pub fn aeads(self) -> impl Iterator<Item = AeadWithDefaultLengthTag> + Clone {
+        AEADS
+            .iter()
+            .copied()
+            .filter(move |&alg| self.supports(Aead::AeadWithDefaultLengthTag(alg)))
+    }
+
+    /// Asymmetric signature algorithms compiled in, with `SignHash::Any` for the ones taking a
+    /// hash
+    pub fn asymmetric_signatures(self) -> impl Iterator<Item = AsymmetricSignature> + Clone {
+        ASYMMETRIC_SIGNATURES
+            .iter()
+            .copied()
+            .filter(move |&alg| self.supports(alg))
+    }
+}
+
+impl fmt::Debug for BackendCapabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct List<I>(I);
+
+        impl<I: Iterator + Clone> fmt::Debug for List<I>
+        where
+            I::Item: fmt::Debug,
+        {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_list().entries(self.0.clone()).finish()
+            }
+        }
+
+        f.debug_struct("BackendCapabilities")
+            .field("hashes", &List(self.hashes()))
+            .field("aeads", &List(self.aeads()))
+            .field("asymmetric_signatures", &List(self.asymmetric_signatures()))
+            .finish()
+    }
+}
+
+/// Algorithms compiled into the PSA Crypto implementation
+///
+/// # Example
+///
+/// ```
+/// use psa_crypto::operations::other::backend_capabilities;
+/// use psa_crypto::types::algorithm::{AeadWithDefaultLengthTag, Hash};
+///
+/// let capabilities = backend_capabilities();
+/// capabilities.require(Hash::Sha256).unwrap();
+/// println!("{:?}", capabilities);
+/// for aead in capabilities.aeads() {
+///     println!("{:?} is available", aead);
+/// }
+/// ```
+pub fn backend_capabilities() -> BackendCapabilities {
+    BackendCapabilities { _private: () }
+}
+
+/// Run an operation on another thread, giving up waiting for it after `timeout`
+///
+/// This is meant for backends which could hang, like a secure element behind a faulty driver. The
+/// function returns the result of `operation` if it finishes in time, and `Error::Timeout`
+/// otherwise, which operations of the PSA Crypto API never return, so that a timeout cannot be
+/// mistaken for an error of the operation. If `operation` panics, `Error::GenericError` is
+/// returned.
+///
+/// PSA Crypto API calls cannot be cancelled: after a timeout, the operation keeps running in the
+/// background and its result is dropped when it finishes. The inputs are moved into `operation`
+/// so that they outlive the call. The keys it uses must not be destroyed while it could still be
+/// running.
+///
+/// The PSA Crypto core of Mbed TLS 3.0 is not thread safe. Running `operation` on another thread
+/// is fine while the caller waits for it, but after a timeout no other function of this crate may
+/// be called until `operation` has returned, which the caller has to find out by itself, for
+/// example with a channel that `operation` signals before returning.
+///
+/// # Example
+///
+/// ```
+/// use psa_crypto::operations::{hash, other::with_timeout};
+/// use psa_crypto::types::algorithm::Hash;
+/// use std::time::Duration;
+///
+/// psa_crypto::init().unwrap();
+/// let digest = with_timeout(Duration::from_secs(5), || {
+///     let mut digest = [0; 32];
+///     hash::hash_compute(Hash::Sha256, b"message", &mut digest).map(|_| digest)
+/// })
+/// .expect("the hash failed or took too long");
+/// ```
+#[cfg(feature = "std")]
+pub fn with_timeout<T, F>(timeout: std::time::Duration, operation: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    use std::sync::mpsc::{self, RecvTimeoutError};
+
+    let (sender, receiver) = mpsc::channel();
+    let _ = std::thread::spawn(move || {
+        // The receiver is gone if the caller stopped waiting.
+        let _ = sender.send(operation());
+    });
+    match receiver.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => {
+            error!("The operation did not finish in {:?}.", timeout);
+            Err(Error::Timeout)
+        }
+        Err(RecvTimeoutError::Disconnected) => {
+            error!("The operation panicked.");
+            Err(Error::GenericError)
+        }
+    }
+}
@@ -0,0 +1,133 @@
+// Copyright 2020 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! # Single-use keys
+//!
+//! The PSA Crypto API has no way of limiting how many times a key is used. A `SingleUseKey` adds
+//! this on the Rust side: it allows one operation with its key and destroys the key right after.
+
+use crate::operations::{aead, asym_signature, key_management, mac};
+use crate::types::algorithm::{Aead, AsymmetricSignature, Mac};
+use crate::types::key::Id;
+use crate::types::status::{Error, Result};
+use log::error;
+
+/// A key which can be used for a single operation
+///
+/// The key is destroyed after the first operation, whether it succeeded or not, or when the
+/// wrapper is dropped if it was never used. Any further operation returns `Error::BadState`.
+///
+/// # Example
+///
+/// ```
+/// use psa_crypto::operations::{key_management, single_use::SingleUseKey};
+/// use psa_crypto::types::algorithm::{FullLengthMac, Hash, Mac};
+/// use psa_crypto::types::key::{Attributes, Type, Lifetime, Policy, UsageFlags};
+/// use psa_crypto::types::status::Error;
+/// let mac_alg = Mac::FullLength(FullLengthMac::Hmac { hash_alg: Hash::Sha256 });
+/// # let mut usage_flags: UsageFlags = Default::default();
+/// # usage_flags.set_sign_hash();
+/// # let attributes = Attributes {
+/// #     key_type: Type::Hmac,
+/// #     bits: 256,
+/// #     lifetime: Lifetime::Volatile,
+/// #     policy: Policy {
+/// #         usage_flags,
+/// #         permitted_algorithms: mac_alg.into(),
+/// #     },
+/// # };
+/// psa_crypto::init().unwrap();
+/// // Safe because the key is not used anywhere else.
+/// let mut key = unsafe { SingleUseKey::new(key_management::generate(attributes, None).unwrap()) };
+///
+/// let mut token = [0; 32];
+/// key.compute_mac(mac_alg, b"one-time token", &mut token).unwrap();
+/// assert_eq!(key.compute_mac(mac_alg, b"again", &mut token), Err(Error::BadState));
+/// ```
+// Copying the wrapper would allow one use per copy.
+#[allow(missing_copy_implementations)]
+#[derive(Debug)]
+pub struct SingleUseKey {
+    key: Option<Id>,
+}
+
+impl SingleUseKey {
+    /// Take ownership of a key to use it once
+    ///
+    /// # Safety
+    ///
+    /// Same as `key_management::destroy`: the key will be destroyed, so the caller must make sure
+    /// that it is not used anywhere else.
+    pub unsafe fn new(key: Id) -> Self {
+        SingleUseKey { key: Some(key) }
+    }
+
+    /// Whether the key has been used, and destroyed
+    pub fn is_used(&self) -> bool {
+        self.key.is_none()
+    }
+
+    /// Calculate the MAC of a message with the key, see `mac::compute_mac`
+    pub fn compute_mac(&mut self, mac_alg: Mac, input: &[u8], mac: &mut [u8]) -> Result<usize> {
+        self.use_once(|key| mac::compute_mac(key, mac_alg, input, mac))
+    }
+
+    /// Sign a hash with the key, see `asym_signature::sign_hash`
+    pub fn sign_hash(
+        &mut self,
+        alg: AsymmetricSignature,
+        hash: &[u8],
+        signature: &mut [u8],
+    ) -> Result<usize> {
+        self.use_once(|key| asym_signature::sign_hash(key, alg, hash, signature))
+    }
+
+    /// Sign a message with the key, see `asym_signature::sign_message`
+    pub fn sign_message(
+        &mut self,
+        alg: AsymmetricSignature,
+        message: &[u8],
+        signature: &mut [u8],
+    ) -> Result<usize> {
+        self.use_once(|key| asym_signature::sign_message(key, alg, message, signature))
+    }
+
+    /// Encrypt and authenticate a message with the key, see `aead::encrypt`
+    pub fn encrypt(
+        &mut self,
+        aead_alg: Aead,
+        nonce: &[u8],
+        additional_data: &[u8],
+        plaintext: &[u8],
+        ciphertext: &mut [u8],
+    ) -> Result<usize> {
+        self.use_once(|key| {
+            aead::encrypt(key, aead_alg, nonce, additional_data, plaintext, ciphertext)
+        })
+    }
+
+    /// Run `f` with the key and destroy the key
+    ///
+    /// If the operation succeeded but the key could not be destroyed, the error of the destruction
+    /// is returned.
+    fn use_once<T>(&mut self, f: impl FnOnce(Id) -> Result<T>) -> Result<T> {
+        let key = self.key.take().ok_or_else(|| {
+            error!("The single-use key has already been used.");
+            Error::BadState
+        })?;
+        let result = f(key);
+        // Safe because the caller of `new` handed the key over.
+        unsafe { key_management::destroy(key) }?;
+        result
+    }
+}
+
+impl Drop for SingleUseKey {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            // Safe because the caller of `new` handed the key over. Errors cannot be reported
+            // from here.
+            let _ = unsafe { key_management::destroy(key) };
+        }
+    }
+}
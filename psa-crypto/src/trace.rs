@@ -0,0 +1,108 @@
+// Copyright 2020 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! # Operation spans
+//!
+//! With the `tracing` feature, the main operations run in a debug span of the `tracing` crate,
+//! with the target `psa_crypto::operations`. The span is named after the operation and has the
+//! algorithm and the key ID as fields. A debug event with the result status is emitted in the span
+//! when the operation returns. With the `fmt` subscriber of `tracing-subscriber`, this renders as:
+//!
+//! ```text
+//! DEBUG compute_mac{alg=Mac(FullLength(Hmac { hash_alg: Sha256 })) key_id=5}: psa_crypto::operations: status=ok
+//! ```
+//!
+//! Key material, inputs, outputs, MACs and signatures are never part of a span or an event.
+//! Without the feature, nothing is recorded and the calls compile to nothing.
+
+use crate::types::key::Id;
+use crate::types::status::Result;
+
+#[cfg(feature = "tracing")]
+use core::fmt;
+
+/// Enter the span of `$operation`, done with the key `$key` and the algorithm `$alg`
+///
+/// `$operation` must be a string literal. The span is left when the returned `Span` is dropped.
+#[cfg(feature = "tracing")]
+macro_rules! span {
+    ($operation:literal, $key:expr, $alg:expr) => {
+        $crate::trace::Span::enter(
+            tracing::debug_span!(
+                target: "psa_crypto::operations",
+                $operation,
+                alg = ?$crate::types::algorithm::Algorithm::from($alg),
+                key_id = tracing::field::Empty,
+            ),
+            $key,
+        )
+    };
+}
+
+/// Enter the span of `$operation`, done with the key `$key` and the algorithm `$alg`
+#[cfg(not(feature = "tracing"))]
+macro_rules! span {
+    ($operation:literal, $key:expr, $alg:expr) => {{
+        let _: Option<$crate::types::key::Id> = $key;
+        let _ = $alg;
+        $crate::trace::Span
+    }};
+}
+
+pub(crate) use span;
+
+/// The span of an operation, entered until it is dropped
+#[cfg(feature = "tracing")]
+pub(crate) struct Span(tracing::span::EnteredSpan);
+
+/// The span of an operation, entered until it is dropped
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct Span;
+
+#[cfg(feature = "tracing")]
+impl Span {
+    pub(crate) fn enter(span: tracing::Span, key: Option<Id>) -> Self {
+        let span = Span(span.entered());
+        if let Some(key) = key {
+            span.key(key);
+        }
+        span
+    }
+
+    /// Record the key of the operation, when it is only known once the operation is done
+    pub(crate) fn key(&self, key: Id) {
+        let _ = self.0.record("key_id", key.0);
+    }
+
+    /// Emit the event with the result status of the operation
+    pub(crate) fn result<T>(&self, result: &Result<T>) {
+        tracing::debug!(
+            target: "psa_crypto::operations",
+            status = %Status(result.as_ref().err()),
+        );
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+impl Span {
+    /// Record the key of the operation, when it is only known once the operation is done
+    #[inline(always)]
+    pub(crate) fn key(&self, _: Id) {}
+
+    /// Emit the event with the result status of the operation
+    #[inline(always)]
+    pub(crate) fn result<T>(&self, _: &Result<T>) {}
+}
+
+#[cfg(feature = "tracing")]
+struct Status<'a>(Option<&'a crate::types::status::Error>);
+
+#[cfg(feature = "tracing")]
+impl fmt::Display for Status<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(error) => write!(f, "{:?}", error),
+            None => write!(f, "ok"),
+        }
+    }
+}
@@ -562,8 +562,8 @@ impl TryFrom<psa_crypto_sys::psa_algorithm_t> for Algorithm {
             let mac: Mac = alg.try_into()?;
             Ok(mac.into())
         } else if psa_crypto_sys::PSA_ALG_IS_CIPHER(alg) {
-            error!("Cipher algorithms are not supported.");
-            Err(Error::NotSupported)
+            let cipher: Cipher = alg.try_into()?;
+            Ok(cipher.into())
         } else if psa_crypto_sys::PSA_ALG_IS_AEAD(alg) {
             let aead: Aead = alg.try_into()?;
             Ok(aead.into())
@@ -693,14 +693,15 @@ impl TryFrom<psa_crypto_sys::psa_algorithm_t> for AsymmetricSignature {
             Ok(AsymmetricSignature::RsaPss {
                 hash_alg: psa_crypto_sys::PSA_ALG_SIGN_GET_HASH(alg).try_into()?,
             })
-        } else if psa_crypto_sys::PSA_ALG_IS_ECDSA(alg) {
-            Ok(AsymmetricSignature::Ecdsa {
-                hash_alg: psa_crypto_sys::PSA_ALG_SIGN_GET_HASH(alg).try_into()?,
-            })
         } else if psa_crypto_sys::PSA_ALG_IS_DETERMINISTIC_ECDSA(alg) {
+            // Checked first as PSA_ALG_IS_ECDSA is also true for deterministic ECDSA.
             Ok(AsymmetricSignature::DeterministicEcdsa {
                 hash_alg: psa_crypto_sys::PSA_ALG_SIGN_GET_HASH(alg).try_into()?,
             })
+        } else if psa_crypto_sys::PSA_ALG_IS_ECDSA(alg) {
+            Ok(AsymmetricSignature::Ecdsa {
+                hash_alg: psa_crypto_sys::PSA_ALG_SIGN_GET_HASH(alg).try_into()?,
+            })
         } else {
             error!(
                 "Can not find a valid AsymmetricSignature algorithm for {}.",
@@ -767,6 +768,27 @@ impl From<AsymmetricEncryption> for psa_crypto_sys::psa_algorithm_t {
     }
 }
 
+#[cfg(feature = "interface")]
+impl TryFrom<psa_crypto_sys::psa_algorithm_t> for Cipher {
+    type Error = Error;
+    fn try_from(alg: psa_crypto_sys::psa_algorithm_t) -> Result<Self> {
+        match alg {
+            psa_crypto_sys::PSA_ALG_STREAM_CIPHER => Ok(Cipher::StreamCipher),
+            psa_crypto_sys::PSA_ALG_CTR => Ok(Cipher::Ctr),
+            psa_crypto_sys::PSA_ALG_CFB => Ok(Cipher::Cfb),
+            psa_crypto_sys::PSA_ALG_OFB => Ok(Cipher::Ofb),
+            psa_crypto_sys::PSA_ALG_XTS => Ok(Cipher::Xts),
+            psa_crypto_sys::PSA_ALG_ECB_NO_PADDING => Ok(Cipher::EcbNoPadding),
+            psa_crypto_sys::PSA_ALG_CBC_NO_PADDING => Ok(Cipher::CbcNoPadding),
+            psa_crypto_sys::PSA_ALG_CBC_PKCS7 => Ok(Cipher::CbcPkcs7),
+            a => {
+                error!("Can not find a valid Cipher algorithm for {}.", a);
+                Err(Error::InvalidArgument)
+            }
+        }
+    }
+}
+
 #[cfg(feature = "interface")]
 impl From<Cipher> for psa_crypto_sys::psa_algorithm_t {
     fn from(cipher: Cipher) -> Self {
@@ -1064,7 +1086,10 @@ impl TryFrom<psa_crypto_sys::psa_algorithm_t> for KeyDerivation {
 
 #[cfg(test)]
 mod test {
-    use crate::types::algorithm::{Algorithm, AsymmetricSignature, Hash, SignHash};
+    use crate::types::algorithm::{
+        Aead, AeadWithDefaultLengthTag, Algorithm, AsymmetricSignature, Cipher, FullLengthMac,
+        Hash, Mac, SignHash,
+    };
     use core::convert::{TryFrom, TryInto};
 
     #[test]
@@ -1098,6 +1123,10 @@ mod test {
             .try_into()
             .unwrap()
         );
+        assert_eq!(
+            Algorithm::Cipher(Cipher::EcbNoPadding),
+            psa_crypto_sys::PSA_ALG_ECB_NO_PADDING.try_into().unwrap()
+        );
     }
 
     #[test]
@@ -1107,4 +1136,104 @@ mod test {
         let _ = Hash::try_from(psa_crypto_sys::PSA_ALG_ANY_HASH).unwrap_err();
         let _ = Hash::try_from(psa_crypto_sys::PSA_ALG_RSA_PKCS1V15_SIGN_RAW).unwrap_err();
     }
+
+    const HASHES: [Hash; 15] = [
+        Hash::Md2,
+        Hash::Md4,
+        Hash::Md5,
+        Hash::Ripemd160,
+        Hash::Sha1,
+        Hash::Sha224,
+        Hash::Sha256,
+        Hash::Sha384,
+        Hash::Sha512,
+        Hash::Sha512_224,
+        Hash::Sha512_256,
+        Hash::Sha3_224,
+        Hash::Sha3_256,
+        Hash::Sha3_384,
+        Hash::Sha3_512,
+    ];
+
+    #[test]
+    fn hash_round_trip() {
+        for &hash in HASHES.iter() {
+            let alg: psa_crypto_sys::psa_algorithm_t = hash.into();
+            assert_eq!(Hash::try_from(alg).unwrap(), hash);
+        }
+        let _ = Hash::try_from(0xDEAD_BEEF).unwrap_err();
+    }
+
+    #[test]
+    fn mac_round_trip() {
+        let hmacs = HASHES
+            .iter()
+            .map(|&hash_alg| FullLengthMac::Hmac { hash_alg });
+        for full_length_mac in [FullLengthMac::CbcMac, FullLengthMac::Cmac]
+            .iter()
+            .copied()
+            .chain(hmacs)
+        {
+            for &mac in [
+                Mac::FullLength(full_length_mac),
+                Mac::Truncated {
+                    mac_alg: full_length_mac,
+                    mac_length: 4,
+                },
+            ]
+            .iter()
+            {
+                let alg: psa_crypto_sys::psa_algorithm_t = mac.into();
+                assert_eq!(Mac::try_from(alg).unwrap(), mac);
+            }
+        }
+        let _ = Mac::try_from(0xDEAD_BEEF).unwrap_err();
+        let _ = Mac::try_from(psa_crypto_sys::PSA_ALG_SHA_256).unwrap_err();
+    }
+
+    #[test]
+    fn aead_round_trip() {
+        for &aead_alg in [
+            AeadWithDefaultLengthTag::Ccm,
+            AeadWithDefaultLengthTag::Gcm,
+            AeadWithDefaultLengthTag::Chacha20Poly1305,
+        ]
+        .iter()
+        {
+            for &aead in [
+                Aead::AeadWithDefaultLengthTag(aead_alg),
+                Aead::AeadWithShortenedTag {
+                    aead_alg,
+                    tag_length: 8,
+                },
+            ]
+            .iter()
+            {
+                let alg: psa_crypto_sys::psa_algorithm_t = aead.into();
+                assert_eq!(Aead::try_from(alg).unwrap(), aead);
+            }
+        }
+        let _ = Aead::try_from(0xDEAD_BEEF).unwrap_err();
+        let _ = Aead::try_from(psa_crypto_sys::PSA_ALG_CTR).unwrap_err();
+    }
+
+    #[test]
+    fn asymmetric_signature_round_trip() {
+        let sign_hashes = HASHES.iter().map(|&hash| SignHash::Specific(hash));
+        for hash_alg in core::iter::once(SignHash::Any).chain(sign_hashes) {
+            for &asym_sign in [
+                AsymmetricSignature::RsaPkcs1v15Sign { hash_alg },
+                AsymmetricSignature::RsaPkcs1v15SignRaw,
+                AsymmetricSignature::RsaPss { hash_alg },
+                AsymmetricSignature::Ecdsa { hash_alg },
+                AsymmetricSignature::EcdsaAny,
+                AsymmetricSignature::DeterministicEcdsa { hash_alg },
+            ]
+            .iter()
+            {
+                let alg: psa_crypto_sys::psa_algorithm_t = asym_sign.into();
+                assert_eq!(AsymmetricSignature::try_from(alg).unwrap(), asym_sign);
+            }
+        }
+    }
 }
@@ -15,9 +15,12 @@ use crate::types::status::{Error, Result};
 #[cfg(feature = "interface")]
 use core::convert::{TryFrom, TryInto};
 use core::fmt;
+#[cfg(feature = "operations")]
+use core::sync::atomic::{AtomicU32, Ordering};
 use log::error;
 pub use psa_crypto_sys::{self, psa_key_id_t, PSA_KEY_ID_USER_MAX, PSA_KEY_ID_USER_MIN};
 use serde::{Deserialize, Serialize};
+use subtle::{Choice, ConditionallySelectable};
 use zeroize::Zeroize;
 
 /// Native definition of the attributes needed to fully describe
@@ -427,6 +430,54 @@ impl Attributes {
         })
     }
 
+    /// Sufficient buffer size for the output of a cipher update step with `input_len` bytes of input
+    #[cfg(feature = "interface")]
+    pub fn cipher_update_output_size(self, alg: Cipher, input_len: usize) -> Result<usize> {
+        self.compatible_with_alg(alg.into())?;
+        Ok(unsafe {
+            psa_crypto_sys::PSA_CIPHER_UPDATE_OUTPUT_SIZE(
+                self.key_type.try_into()?,
+                alg.into(),
+                input_len,
+            )
+        })
+    }
+
+    /// Sufficient buffer size for the output of a cipher finish step
+    ///
+    /// This is one block for block cipher modes with padding, and zero for the other modes.
+    #[cfg(feature = "interface")]
+    pub fn cipher_finish_output_size(self, alg: Cipher) -> Result<usize> {
+        self.compatible_with_alg(alg.into())?;
+        Ok(unsafe {
+            psa_crypto_sys::PSA_CIPHER_FINISH_OUTPUT_SIZE(self.key_type.try_into()?, alg.into())
+        })
+    }
+
+    /// The length of the IV for a cipher algorithm, zero for algorithms without an IV
+    #[cfg(feature = "interface")]
+    pub fn cipher_iv_length(self, alg: Cipher) -> Result<usize> {
+        self.compatible_with_alg(alg.into())?;
+        Ok(psa_crypto_sys::PSA_CIPHER_IV_LENGTH(
+            self.key_type.try_into()?,
+            alg.into(),
+        ))
+    }
+
+    /// Sufficient buffer size for the output of `cipher::encrypt_with_iv_prefix`, the IV followed
+    /// by the encryption of `input_len` bytes
+    #[cfg(feature = "interface")]
+    pub fn cipher_encrypt_output_size(self, alg: Cipher, input_len: usize) -> Result<usize> {
+        self.compatible_with_alg(alg.into())?;
+        Ok(unsafe {
+            psa_crypto_sys::PSA_CIPHER_ENCRYPT_OUTPUT_SIZE(
+                self.key_type.try_into()?,
+                alg.into(),
+                input_len,
+            )
+        })
+    }
+
     /// Sufficient buffer size for the MAC of the specified algorithm, if compatible
     #[cfg(feature = "interface")]
     pub fn mac_length(self, mac_alg: Mac) -> Result<usize> {
@@ -473,6 +524,16 @@ impl Attributes {
         ))
     }
 
+    /// The default length of a nonce for an AEAD algorithm
+    #[cfg(feature = "interface")]
+    pub fn aead_nonce_length(self, alg: Aead) -> Result<usize> {
+        self.compatible_with_alg(alg.into())?;
+        Ok(psa_crypto_sys::PSA_AEAD_NONCE_LENGTH(
+            self.key_type.try_into()?,
+            alg.into(),
+        ))
+    }
+
     /// Sufficient buffer size for the resulting shared secret from a raw key agreement
     #[cfg(feature = "interface")]
     pub fn raw_key_agreement_output_size(self, alg: RawKeyAgreement) -> Result<usize> {
@@ -497,12 +558,63 @@ pub enum Lifetime {
     Volatile,
     /// A persistent key remains in storage until it is explicitly destroyed or until the
     /// corresponding storage area is wiped.
+    ///
+    /// This is the default persistence in the local storage. Keys created with this lifetime are
+    /// stored in the default location, see `key_management::set_default_location`: outside of the
+    /// local storage, they read back with the lifetime given by `Lifetime::persistent_in`.
     Persistent,
     /// Implementations can offer other storage areas designated by other lifetime values as
     /// implementation-specific extensions.
     Custom(u32),
 }
 
+/// Location of a key: where its material is stored and the operations with it are done
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Zeroize)]
+pub enum KeyLocation {
+    /// The storage of the PSA Crypto implementation itself.
+    LocalStorage,
+    /// The primary secure element of the implementation, if it has one.
+    PrimarySecureElement,
+    /// Implementation-specific locations, as 24-bit values.
+    Custom(u32),
+}
+
+/// Location given to the keys created with `Lifetime::Persistent`, as a `psa_key_location_t`
+#[cfg(feature = "operations")]
+pub(crate) static DEFAULT_LOCATION: AtomicU32 =
+    AtomicU32::new(psa_crypto_sys::PSA_KEY_LOCATION_LOCAL_STORAGE);
+
+impl Lifetime {
+    /// Location of the keys of this lifetime
+    ///
+    /// `Lifetime::Persistent` and volatile keys are in the local storage.
+    #[cfg(feature = "interface")]
+    pub fn location(self) -> KeyLocation {
+        let lifetime: psa_crypto_sys::psa_key_lifetime_t = self.into();
+        KeyLocation::from(lifetime >> 8)
+    }
+
+    /// Lifetime of the keys with the default persistence in `location`
+    ///
+    /// This is `Lifetime::Persistent` for the local storage and a `Custom` lifetime otherwise.
+    /// Locations are 24-bit values: `Error::InvalidArgument` is returned for larger custom
+    /// locations.
+    #[cfg(feature = "interface")]
+    pub fn persistent_in(location: KeyLocation) -> Result<Lifetime> {
+        let location = psa_crypto_sys::psa_key_location_t::from(location);
+        if location > 0x00ff_ffff {
+            error!("Key location {:#x} does not fit in 24 bits.", location);
+            return Err(Error::InvalidArgument);
+        }
+        Ok(Lifetime::from(
+            (location << 8)
+                | psa_crypto_sys::psa_key_lifetime_t::from(
+                    psa_crypto_sys::PSA_KEY_PERSISTENCE_DEFAULT,
+                ),
+        ))
+    }
+}
+
 /// Enumeration of key types supported.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Zeroize)]
 pub enum Type {
@@ -881,6 +993,13 @@ impl Id {
     }
 }
 
+/// Constant-time selection of a key ID, see `key_management::select_key`
+impl ConditionallySelectable for Id {
+    fn conditional_select(a: &Self, b: &Self, choice: Choice) -> Self {
+        Id(psa_key_id_t::conditional_select(&a.0, &b.0, choice))
+    }
+}
+
 #[cfg(feature = "interface")]
 impl TryFrom<Attributes> for psa_crypto_sys::psa_key_attributes_t {
     type Error = Error;
@@ -1028,6 +1147,56 @@ impl From<psa_crypto_sys::psa_key_lifetime_t> for Lifetime {
     }
 }
 
+#[cfg(feature = "interface")]
+impl From<KeyLocation> for psa_crypto_sys::psa_key_location_t {
+    fn from(location: KeyLocation) -> Self {
+        match location {
+            KeyLocation::LocalStorage => psa_crypto_sys::PSA_KEY_LOCATION_LOCAL_STORAGE,
+            KeyLocation::PrimarySecureElement => {
+                psa_crypto_sys::PSA_KEY_LOCATION_PRIMARY_SECURE_ELEMENT
+            }
+            KeyLocation::Custom(value) => value,
+        }
+    }
+}
+
+#[cfg(feature = "interface")]
+impl From<psa_crypto_sys::psa_key_location_t> for KeyLocation {
+    fn from(location: psa_crypto_sys::psa_key_location_t) -> Self {
+        match location {
+            psa_crypto_sys::PSA_KEY_LOCATION_LOCAL_STORAGE => KeyLocation::LocalStorage,
+            psa_crypto_sys::PSA_KEY_LOCATION_PRIMARY_SECURE_ELEMENT => {
+                KeyLocation::PrimarySecureElement
+            }
+            value => KeyLocation::Custom(value),
+        }
+    }
+}
+
+/// Convert the attributes of a key to create, with the persistent ID `id` if any
+///
+/// Keys created with `Lifetime::Persistent`, or with an ID on volatile attributes, are put in the
+/// default location.
+#[cfg(feature = "operations")]
+pub(crate) fn new_key_attributes(
+    attributes: Attributes,
+    id: Option<psa_key_id_t>,
+) -> Result<psa_crypto_sys::psa_key_attributes_t> {
+    let mut key_attributes = psa_crypto_sys::psa_key_attributes_t::try_from(attributes)?;
+    if let Some(id) = id {
+        // This makes volatile attributes `Lifetime::Persistent`.
+        unsafe { psa_crypto_sys::psa_set_key_id(&mut key_attributes, id) };
+    }
+    if attributes.lifetime == Lifetime::Persistent
+        || (attributes.lifetime == Lifetime::Volatile && id.is_some())
+    {
+        let location = KeyLocation::from(DEFAULT_LOCATION.load(Ordering::Relaxed));
+        let lifetime = Lifetime::persistent_in(location)?;
+        unsafe { psa_crypto_sys::psa_set_key_lifetime(&mut key_attributes, lifetime.into()) };
+    }
+    Ok(key_attributes)
+}
+
 #[cfg(feature = "interface")]
 impl From<UsageFlags> for psa_crypto_sys::psa_key_usage_t {
     fn from(flags: UsageFlags) -> Self {
@@ -1224,12 +1393,13 @@ impl TryFrom<psa_crypto_sys::psa_key_type_t> for Type {
 
 #[cfg(test)]
 mod tests {
-    use super::{Attributes, EccFamily, Lifetime, Policy, Type, UsageFlags};
+    use super::{Attributes, EccFamily, Id, Lifetime, Policy, Type, UsageFlags};
     use crate::types::algorithm::{
         Aead, AeadWithDefaultLengthTag, Algorithm, AsymmetricSignature, Cipher, FullLengthMac,
         Hash, Mac, SignHash,
     };
     use core::convert::TryInto;
+    use subtle::{Choice, ConditionallySelectable};
 
     #[test]
     fn usage_flags() {
@@ -1629,4 +1799,18 @@ mod tests {
             attrs.try_into().unwrap()
         );
     }
+
+    #[test]
+    fn conditional_select_id() {
+        let a = Id(0x1234_5678);
+        let b = Id(0x8765_4321);
+        assert_eq!(Id::conditional_select(&a, &b, Choice::from(0)), a);
+        assert_eq!(Id::conditional_select(&a, &b, Choice::from(1)), b);
+
+        let mut id = a;
+        id.conditional_assign(&b, Choice::from(0));
+        assert_eq!(id, a);
+        id.conditional_assign(&b, Choice::from(1));
+        assert_eq!(id, b);
+    }
 }
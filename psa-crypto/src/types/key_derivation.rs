@@ -50,17 +50,24 @@ pub enum Inputs<'a> {
         label: Input<'a>,
     },
     /// TLS-1.2 PSK-to-MasterSecret algorithm.
+    ///
+    /// The premaster secret is formed from the PSK alone, as in RFC 4279, section 2, and the
+    /// master secret is derived from it with the TLS-1.2 PRF. The inputs are fed in the order
+    /// seed, secret, label. There is no "other secret" input in this version of Mbed Crypto, so
+    /// the DHE-PSK and RSA-PSK key exchanges are not supported.
     Tls12PskToMs {
         /// A hash algorithm to use.
         hash_alg: Hash,
-        /// Seed, typically a direct input, can also be a key of type `RawData`.
+        /// Seed, typically a direct input, can also be a key of type `RawData`. For the master
+        /// secret, this is `ClientHello.random + ServerHello.random`.
         seed: Input<'a>,
-        /// Secret, used in the "extract" step. This is typically a key of type `Derive` , or the shared secret
-        /// resulting from a key agreement, using `Input::KeyAgreement`.
+        /// The PSK. This is typically a key of type `Derive` with the derive usage flag and this
+        /// algorithm as permitted algorithm, imported from the PSK bytes.
         /// Must be a key or key agreement input if used with `psa_key_derivation_output_key`.
         /// Must not be larger than `PSA_TLS12_PSK_TO_MS_PSK_MAX_SIZE`.
         secret: InputSecret<'a>,
-        /// Label. Typically a direct input, can also be a key of type `RawData`.
+        /// Label. Typically a direct input, can also be a key of type `RawData`. For the master
+        /// secret, this is `"master secret"`.
         label: Input<'a>,
     },
 }
@@ -6,5 +6,6 @@
 pub mod algorithm;
 pub mod key;
 pub mod key_derivation;
-pub mod status;
+#[cfg(feature = "interface")]
 pub mod operation;
+pub mod status;
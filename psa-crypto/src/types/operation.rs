@@ -1,48 +1,351 @@
-//! implement the operation type for multipart crypto API
-use core::fmt;
-use core::mem::MaybeUninit;
-
-
-#[derive(Debug,Copy,Clone)]
-/// The operation used in multi step crypto API
-pub enum Operation {
-    /// operation for multipart MAC
-    MacOperation,
-    /// Operation for multipart Aead
-    AeadOperation,
-    /// Operation for multipart hashing
-    HashOperation
-}
-
-
-/// The wrapper of the C type for mac operation 
-pub struct MacOperation(pub psa_crypto_sys::psa_mac_operation_t);
-impl Default for MacOperation {
-    fn default() -> Self {
-        unsafe {
-            MacOperation(MaybeUninit::zeroed().assume_init())
-        }
-    }
-}
-
-
-impl fmt::Debug for MacOperation {
-    fn fmt(&self, f: &mut fmt:: Formatter<'_>) -> fmt::Result {
-        write!(f, "MacOperation: (opaque C struct)")
-    }
-}
-
-/// convert from rust type to C type
-impl From<MacOperation> for psa_crypto_sys::psa_mac_operation_t {
-    fn from(mac_oper: MacOperation) -> Self {
-        mac_oper.0
-    }
-}
-
-/// Function ta take the pointer of the inner type of MacOperation (pointer to psa_mac_operation_t)
-impl MacOperation {
-/// Function ta take the pointer of the inner type of MacOperation (pointer to psa_mac_operation_t)
-    pub fn as_mut_ptr(&mut self) -> *mut psa_crypto_sys::psa_mac_operation_t {
-        &mut self.0 as *mut _
-    }
-}
\ No newline at end of file
+// Copyright 2020 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+//! # PSA Multipart Operation types
+//!
+//! The operation structures are opaque to this crate: they are created zeroed, which the PSA
+//! Crypto API defines as a valid initial value, and only ever handed to the backend by pointer.
+//! No field of an operation structure is read or written from Rust, so the contents of its
+//! padding bytes, which the backend is free to change, do not matter.
+//!
+//! With the `live-operations` feature, debug builds count the MAC and hash operations which are
+//! set up and not yet finished or aborted, see `assert_no_live_operations`. Whether an operation
+//! is counted is kept in a private field of the wrapper types, which is zero-sized in release
+//! builds and without the feature. Release builds count nothing.
+//!
+//! Miri cannot run calls into the backend. The tests of this module do not call it and can be run
+//! with `cargo +nightly miri test -p psa-crypto --lib types::operation`.
+
+#[cfg(feature = "operations")]
+use super::status::{Result, Status};
+use crate::types::algorithm::Mac;
+use crate::types::key::Id;
+use core::fmt;
+use core::mem::MaybeUninit;
+#[cfg(feature = "operations")]
+use core::ops::{Deref, DerefMut};
+#[cfg(all(feature = "live-operations", debug_assertions))]
+use core::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(all(feature = "live-operations", debug_assertions))]
+use log::error;
+
+/// The operation used in multi step crypto API
+#[derive(Debug, Copy, Clone)]
+pub enum Operation {
+    /// operation for multipart MAC
+    MacOperation,
+    /// Operation for multipart Aead
+    AeadOperation,
+    /// Operation for multipart hashing
+    HashOperation,
+}
+
+/// Behaviour shared by all the multipart operation contexts
+///
+/// This allows writing code that drives or cleans up an operation without knowing which kind of
+/// operation it is.
+///
+/// # Example
+///
+/// ```
+/// use psa_crypto::types::operation::{MacOperation, MultipartOperation};
+///
+/// fn cancel<O: MultipartOperation>(operation: &mut O) {
+///     operation.abort().unwrap();
+/// }
+///
+/// psa_crypto::init().unwrap();
+/// let mut operation = MacOperation::default();
+/// cancel(&mut operation);
+/// ```
+#[cfg(feature = "operations")]
+pub trait MultipartOperation: Default + fmt::Debug {
+    /// The PSA operation structure wrapped by this type
+    type Raw;
+
+    /// Pointer to the wrapped PSA operation structure, to be passed to the PSA functions
+    fn as_mut_ptr(&mut self) -> *mut Self::Raw;
+
+    /// Abort the operation and release the resources it holds
+    ///
+    /// The operation can be set up again after this. Aborting an operation that was never set up
+    /// or that has already finished succeeds and does nothing.
+    fn abort(&mut self) -> Result<()>;
+}
+
+/// Number of MAC and hash operations set up and not yet finished or aborted
+#[cfg(all(feature = "live-operations", debug_assertions))]
+static LIVE_OPERATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Whether an operation is counted in the live operations
+// Copying it would count the operation twice.
+#[derive(Debug, Default)]
+struct Liveness(#[cfg(all(feature = "live-operations", debug_assertions))] bool);
+
+impl Liveness {
+    #[cfg(feature = "operations")]
+    #[cfg_attr(
+        not(all(feature = "live-operations", debug_assertions)),
+        allow(unused_variables)
+    )]
+    fn set(&mut self, live: bool) {
+        #[cfg(all(feature = "live-operations", debug_assertions))]
+        if live != self.0 {
+            self.0 = live;
+            if live {
+                let _ = LIVE_OPERATIONS.fetch_add(1, Ordering::Relaxed);
+            } else {
+                let _ = LIVE_OPERATIONS.fetch_sub(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// Number of MAC and hash operations which have been set up but not finished or aborted yet
+///
+/// Dropping a live `MacOperation` or `HashOperation` without aborting it leaks the resources of
+/// the operation, so it stays counted. The count is only kept in debug builds with the
+/// `live-operations` feature: this function returns 0 otherwise.
+#[cfg(feature = "operations")]
+pub fn live_operations() -> usize {
+    #[cfg(all(feature = "live-operations", debug_assertions))]
+    return LIVE_OPERATIONS.load(Ordering::Relaxed);
+    #[cfg(not(all(feature = "live-operations", debug_assertions)))]
+    return 0;
+}
+
+/// Check that all the MAC and hash operations set up have been finished or aborted
+///
+/// This is meant to be called at points where no operation should be in progress, like before
+/// the program exits, to find operations that were forgotten. It does nothing unless the
+/// `live-operations` feature is enabled in a debug build.
+///
+/// # Panics
+///
+/// If `live_operations` is not 0. The number of live operations is logged first.
+///
+/// # Example
+///
+/// ```
+/// use psa_crypto::operations::mac::mac_sign_setup;
+/// use psa_crypto::types::operation::{assert_no_live_operations, MacOperation, MultipartOperation};
+/// # use psa_crypto::operations::key_management::generate;
+/// # use psa_crypto::types::algorithm::{Hash, Mac, FullLengthMac};
+/// # use psa_crypto::types::key::{Attributes, Type, Lifetime, Policy, UsageFlags};
+/// # let mac_alg = Mac::FullLength(FullLengthMac::Hmac { hash_alg: Hash::Sha256 });
+/// # let mut usage_flags: UsageFlags = Default::default();
+/// # usage_flags.set_sign_hash();
+/// # let attributes = Attributes {
+/// #     key_type: Type::Hmac,
+/// #     bits: 256,
+/// #     lifetime: Lifetime::Volatile,
+/// #     policy: Policy { usage_flags, permitted_algorithms: mac_alg.into() },
+/// # };
+/// psa_crypto::init().unwrap();
+/// # let key = generate(attributes, None).unwrap();
+/// let mut operation = MacOperation::default();
+/// mac_sign_setup(&mut operation, key, mac_alg).unwrap();
+/// operation.abort().unwrap();
+/// assert_no_live_operations();
+/// ```
+#[cfg(feature = "operations")]
+pub fn assert_no_live_operations() {
+    #[cfg(all(feature = "live-operations", debug_assertions))]
+    {
+        let live = live_operations();
+        if live != 0 {
+            error!("{} operation(s) were neither finished nor aborted.", live);
+            panic!("{} live operation(s)", live);
+        }
+    }
+}
+
+/// Owner of an operation which aborts it when dropped
+///
+/// Helpers driving an operation to completion keep it in a guard so that returning early with `?`
+/// does not leave the operation set up. Aborting an operation which has already finished does
+/// nothing, so the guard can also be dropped on success.
+#[cfg(feature = "operations")]
+#[derive(Debug, Default)]
+pub(crate) struct AbortGuard<O: MultipartOperation>(O);
+
+#[cfg(feature = "operations")]
+impl<O: MultipartOperation> Deref for AbortGuard<O> {
+    type Target = O;
+
+    fn deref(&self) -> &O {
+        &self.0
+    }
+}
+
+#[cfg(feature = "operations")]
+impl<O: MultipartOperation> DerefMut for AbortGuard<O> {
+    fn deref_mut(&mut self) -> &mut O {
+        &mut self.0
+    }
+}
+
+#[cfg(feature = "operations")]
+impl<O: MultipartOperation> Drop for AbortGuard<O> {
+    fn drop(&mut self) {
+        // Errors cannot be reported from here, and there is nothing else to release.
+        let _ = self.0.abort();
+    }
+}
+
+/// The wrapper of the C type for mac operation
+///
+/// Besides the C structure, the wrapper keeps the algorithm and the key of the operation while it
+/// is set up, see `MacOperation::algorithm`.
+pub struct MacOperation(
+    pub psa_crypto_sys::psa_mac_operation_t,
+    Option<(Mac, Id)>,
+    Liveness,
+);
+
+impl Default for MacOperation {
+    fn default() -> Self {
+        // All zeros is a valid value for every field of the C type: integers, raw pointers, and
+        // function pointers which bindgen wraps in `Option`.
+        MacOperation(
+            unsafe { MaybeUninit::zeroed().assume_init() },
+            None,
+            Default::default(),
+        )
+    }
+}
+
+impl fmt::Debug for MacOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MacOperation: (opaque C struct)")
+    }
+}
+
+/// convert from rust type to C type
+impl From<MacOperation> for psa_crypto_sys::psa_mac_operation_t {
+    fn from(mac_oper: MacOperation) -> Self {
+        mac_oper.0
+    }
+}
+
+impl MacOperation {
+    /// Function ta take the pointer of the inner type of MacOperation (pointer to psa_mac_operation_t)
+    pub fn as_mut_ptr(&mut self) -> *mut psa_crypto_sys::psa_mac_operation_t {
+        &mut self.0 as *mut _
+    }
+
+    /// Algorithm of the operation, if the operation is set up
+    ///
+    /// It is recorded by `mac_sign_setup` and `mac_verify_setup`, and forgotten when the operation
+    /// is finished or aborted.
+    pub fn algorithm(&self) -> Option<Mac> {
+        self.1.map(|(mac_alg, _)| mac_alg)
+    }
+
+    /// Algorithm and key of the operation, if the operation is set up
+    #[cfg(feature = "std")]
+    pub(crate) fn algorithm_and_key(&self) -> Option<(Mac, Id)> {
+        self.1
+    }
+
+    /// Record the algorithm and key of the operation when it is set up, or `None` when it ends
+    #[cfg(feature = "operations")]
+    pub(crate) fn set_algorithm(&mut self, algorithm: Option<(Mac, Id)>) {
+        self.1 = algorithm;
+        self.2.set(algorithm.is_some());
+    }
+}
+
+#[cfg(feature = "operations")]
+impl MultipartOperation for MacOperation {
+    type Raw = psa_crypto_sys::psa_mac_operation_t;
+
+    fn as_mut_ptr(&mut self) -> *mut Self::Raw {
+        MacOperation::as_mut_ptr(self)
+    }
+
+    fn abort(&mut self) -> Result<()> {
+        Status::from(unsafe { psa_crypto_sys::psa_mac_abort(MacOperation::as_mut_ptr(self)) })
+            .to_result()?;
+        self.set_algorithm(None);
+        Ok(())
+    }
+}
+
+/// The wrapper of the C type for hash operation
+// Copying a live operation would let both copies finish it, which the PSA API does not allow.
+#[allow(missing_copy_implementations)]
+pub struct HashOperation(pub psa_crypto_sys::psa_hash_operation_t, Liveness);
+
+impl Default for HashOperation {
+    fn default() -> Self {
+        // See `MacOperation::default`.
+        HashOperation(
+            unsafe { MaybeUninit::zeroed().assume_init() },
+            Default::default(),
+        )
+    }
+}
+
+impl fmt::Debug for HashOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HashOperation: (opaque C struct)")
+    }
+}
+
+/// convert from rust type to C type
+impl From<HashOperation> for psa_crypto_sys::psa_hash_operation_t {
+    fn from(hash_oper: HashOperation) -> Self {
+        hash_oper.0
+    }
+}
+
+impl HashOperation {
+    /// Pointer to the inner type of HashOperation (pointer to psa_hash_operation_t)
+    pub fn as_mut_ptr(&mut self) -> *mut psa_crypto_sys::psa_hash_operation_t {
+        &mut self.0 as *mut _
+    }
+
+    /// Record whether the operation is set up, for `live_operations`
+    #[cfg(feature = "operations")]
+    pub(crate) fn set_live(&mut self, live: bool) {
+        self.1.set(live);
+    }
+}
+
+#[cfg(feature = "operations")]
+impl MultipartOperation for HashOperation {
+    type Raw = psa_crypto_sys::psa_hash_operation_t;
+
+    fn as_mut_ptr(&mut self) -> *mut Self::Raw {
+        HashOperation::as_mut_ptr(self)
+    }
+
+    fn abort(&mut self) -> Result<()> {
+        Status::from(unsafe { psa_crypto_sys::psa_hash_abort(HashOperation::as_mut_ptr(self)) })
+            .to_result()?;
+        self.set_live(false);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::types::operation::{HashOperation, MacOperation};
+
+    // Moves copy the fields but not necessarily the padding, which must not be read afterwards.
+    #[test]
+    fn default_and_pointer() {
+        let mut operation = MacOperation::default();
+        let ptr = operation.as_mut_ptr();
+        assert_eq!(ptr, &mut operation.0 as *mut _);
+        let mut moved = operation;
+        assert_eq!(moved.as_mut_ptr(), &mut moved.0 as *mut _);
+
+        let mut operation = HashOperation::default();
+        let ptr = operation.as_mut_ptr();
+        assert_eq!(ptr, &mut operation.0 as *mut _);
+        let mut moved = operation;
+        assert_eq!(moved.as_mut_ptr(), &mut moved.0 as *mut _);
+    }
+}
@@ -90,6 +90,11 @@ pub enum Error {
     InsufficientData,
     /// The key handle is not valid
     InvalidHandle,
+    /// The operation did not finish in time
+    ///
+    /// This is not a PSA status code: it is only returned by `operations::other::with_timeout`, and
+    /// is converted to `PSA_ERROR_COMMUNICATION_FAILURE`.
+    Timeout,
 }
 
 #[cfg(feature = "std")]
@@ -152,6 +157,7 @@ impl fmt::Display for Error {
                 f,
                 "The key handle is not valid"
             ),
+            Error::Timeout => write!(f, "The operation did not finish in time"),
         }
     }
 }
@@ -231,6 +237,7 @@ impl From<Error> for psa_crypto_sys::psa_status_t {
             Error::InvalidPadding => psa_crypto_sys::PSA_ERROR_INVALID_PADDING,
             Error::InsufficientData => psa_crypto_sys::PSA_ERROR_INSUFFICIENT_DATA,
             Error::InvalidHandle => psa_crypto_sys::PSA_ERROR_INVALID_HANDLE,
+            Error::Timeout => psa_crypto_sys::PSA_ERROR_COMMUNICATION_FAILURE,
         }
     }
 }
@@ -241,6 +248,16 @@ impl From<Status> for Result<()> {
     }
 }
 
+/// Turn `Error::NotSupported` into `Ok(None)`, for the `_or_none` variants of the operations
+#[cfg(feature = "operations")]
+pub(crate) fn none_if_not_supported<T>(result: Result<T>) -> Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(Error::NotSupported) => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::types::status::{Error, Status};
@@ -261,5 +278,25 @@ mod test {
             psa_crypto_sys::PSA_ERROR_HARDWARE_FAILURE.into()
         );
         assert_eq!(Status::Error(Error::GenericError), 0x0EAD_BEEF.into());
+        assert_eq!(
+            psa_crypto_sys::PSA_ERROR_COMMUNICATION_FAILURE,
+            Status::Error(Error::Timeout).into()
+        );
+    }
+
+    #[cfg(feature = "operations")]
+    #[test]
+    fn none_if_not_supported() {
+        use crate::types::status::none_if_not_supported;
+
+        assert_eq!(none_if_not_supported(Ok(3)), Ok(Some(3)));
+        assert_eq!(
+            none_if_not_supported::<()>(Err(Error::NotSupported)),
+            Ok(None)
+        );
+        assert_eq!(
+            none_if_not_supported::<()>(Err(Error::NotPermitted)),
+            Err(Error::NotPermitted)
+        );
     }
 }
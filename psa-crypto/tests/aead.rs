@@ -204,3 +204,379 @@ fn aead_decrypt_aes_ccm_invalid_signature() {
     );
     assert_eq!(Err(Error::InvalidSignature), result);
 }
+
+#[test]
+fn aead_decrypt_aes_ccm_invalid_signature_writes_no_plaintext() {
+    let alg = Aead::AeadWithDefaultLengthTag(AeadWithDefaultLengthTag::Ccm);
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_decrypt();
+    let attributes = Attributes {
+        key_type: Type::Aes,
+        bits: 0,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: alg.into(),
+        },
+    };
+    psa_crypto::init().unwrap();
+    let my_key = key_management::import(attributes, None, &KEY_DATA).unwrap();
+
+    // Only the tag is corrupted: the ciphertext itself still decrypts to DECRYPTED_DATA.
+    let mut corrupted = ENCRYPTED_DATA;
+    corrupted[ENCRYPTED_DATA.len() - 1] ^= 1;
+    let mut output_buffer = [0xa5; DECRYPTED_DATA.len()];
+    let result = aead::decrypt(
+        my_key,
+        alg,
+        &NONCE,
+        &ADDITIONAL_DATA,
+        &corrupted,
+        &mut output_buffer,
+    );
+    assert_eq!(Err(Error::InvalidSignature), result);
+
+    #[cfg(feature = "std")]
+    assert_eq!(output_buffer, [0xa5; DECRYPTED_DATA.len()]);
+    #[cfg(not(feature = "std"))]
+    assert_eq!(output_buffer, [0; DECRYPTED_DATA.len()]);
+
+    unsafe { key_management::destroy(my_key) }.unwrap();
+}
+
+// Test cases 2, 4 and 16 of "The Galois/Counter Mode of Operation (GCM)", McGrew and Viega, as
+// used by NIST for the validation of GCM implementations
+const GCM_KEY: [u8; 32] = [
+    0xfe, 0xff, 0xe9, 0x92, 0x86, 0x65, 0x73, 0x1c, 0x6d, 0x6a, 0x8f, 0x94, 0x67, 0x30, 0x83, 0x08,
+    0xfe, 0xff, 0xe9, 0x92, 0x86, 0x65, 0x73, 0x1c, 0x6d, 0x6a, 0x8f, 0x94, 0x67, 0x30, 0x83, 0x08,
+];
+const GCM_NONCE: [u8; 12] = [
+    0xca, 0xfe, 0xba, 0xbe, 0xfa, 0xce, 0xdb, 0xad, 0xde, 0xca, 0xf8, 0x88,
+];
+const GCM_ADDITIONAL_DATA: [u8; 20] = [
+    0xfe, 0xed, 0xfa, 0xce, 0xde, 0xad, 0xbe, 0xef, 0xfe, 0xed, 0xfa, 0xce, 0xde, 0xad, 0xbe, 0xef,
+    0xab, 0xad, 0xda, 0xd2,
+];
+const GCM_PLAINTEXT: [u8; 60] = [
+    0xd9, 0x31, 0x32, 0x25, 0xf8, 0x84, 0x06, 0xe5, 0xa5, 0x59, 0x09, 0xc5, 0xaf, 0xf5, 0x26, 0x9a,
+    0x86, 0xa7, 0xa9, 0x53, 0x15, 0x34, 0xf7, 0xda, 0x2e, 0x4c, 0x30, 0x3d, 0x8a, 0x31, 0x8a, 0x72,
+    0x1c, 0x3c, 0x0c, 0x95, 0x95, 0x68, 0x09, 0x53, 0x2f, 0xcf, 0x0e, 0x24, 0x49, 0xa6, 0xb5, 0x25,
+    0xb1, 0x6a, 0xed, 0xf5, 0xaa, 0x0d, 0xe6, 0x57, 0xba, 0x63, 0x7b, 0x39,
+];
+const GCM_TEST_CASE_2: [u8; 32] = [
+    0x03, 0x88, 0xda, 0xce, 0x60, 0xb6, 0xa3, 0x92, 0xf3, 0x28, 0xc2, 0xb9, 0x71, 0xb2, 0xfe, 0x78,
+    0xab, 0x6e, 0x47, 0xd4, 0x2c, 0xec, 0x13, 0xbd, 0xf5, 0x3a, 0x67, 0xb2, 0x12, 0x57, 0xbd, 0xdf,
+];
+const GCM_TEST_CASE_4: [u8; 76] = [
+    0x42, 0x83, 0x1e, 0xc2, 0x21, 0x77, 0x74, 0x24, 0x4b, 0x72, 0x21, 0xb7, 0x84, 0xd0, 0xd4, 0x9c,
+    0xe3, 0xaa, 0x21, 0x2f, 0x2c, 0x02, 0xa4, 0xe0, 0x35, 0xc1, 0x7e, 0x23, 0x29, 0xac, 0xa1, 0x2e,
+    0x21, 0xd5, 0x14, 0xb2, 0x54, 0x66, 0x93, 0x1c, 0x7d, 0x8f, 0x6a, 0x5a, 0xac, 0x84, 0xaa, 0x05,
+    0x1b, 0xa3, 0x0b, 0x39, 0x6a, 0x0a, 0xac, 0x97, 0x3d, 0x58, 0xe0, 0x91, 0x5b, 0xc9, 0x4f, 0xbc,
+    0x32, 0x21, 0xa5, 0xdb, 0x94, 0xfa, 0xe9, 0x5a, 0xe7, 0x12, 0x1a, 0x47,
+];
+const GCM_TEST_CASE_16: [u8; 76] = [
+    0x52, 0x2d, 0xc1, 0xf0, 0x99, 0x56, 0x7d, 0x07, 0xf4, 0x7f, 0x37, 0xa3, 0x2a, 0x84, 0x42, 0x7d,
+    0x64, 0x3a, 0x8c, 0xdc, 0xbf, 0xe5, 0xc0, 0xc9, 0x75, 0x98, 0xa2, 0xbd, 0x25, 0x55, 0xd1, 0xaa,
+    0x8c, 0xb0, 0x8e, 0x48, 0x59, 0x0d, 0xbb, 0x3d, 0xa7, 0xb0, 0x8b, 0x10, 0x56, 0x82, 0x88, 0x38,
+    0xc5, 0xf6, 0x1e, 0x63, 0x93, 0xba, 0x7a, 0x0a, 0xbc, 0xc9, 0xf6, 0x62, 0x76, 0xfc, 0x6e, 0xce,
+    0x0f, 0x4e, 0x17, 0x68, 0xcd, 0xdf, 0x88, 0x53, 0xbb, 0x2d, 0x55, 0x1b,
+];
+
+fn gcm_known_answer(
+    key: &[u8],
+    nonce: &[u8],
+    additional_data: &[u8],
+    plaintext: &[u8],
+    expected: &[u8],
+) {
+    let alg = Aead::AeadWithDefaultLengthTag(AeadWithDefaultLengthTag::Gcm);
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_encrypt().set_decrypt();
+    let attributes = Attributes {
+        key_type: Type::Aes,
+        bits: 0,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: alg.into(),
+        },
+    };
+    psa_crypto::init().unwrap();
+    let my_key = key_management::import(attributes, None, key).unwrap();
+
+    let mut ciphertext = vec![0; plaintext.len() + 16];
+    let length = aead::encrypt(
+        my_key,
+        alg,
+        nonce,
+        additional_data,
+        plaintext,
+        &mut ciphertext,
+    )
+    .unwrap();
+    assert_eq!(&ciphertext[..length], expected);
+
+    let mut decrypted = vec![0; plaintext.len()];
+    let length = aead::decrypt(
+        my_key,
+        alg,
+        nonce,
+        additional_data,
+        expected,
+        &mut decrypted,
+    )
+    .unwrap();
+    assert_eq!(&decrypted[..length], plaintext);
+
+    unsafe { key_management::destroy(my_key) }.unwrap();
+}
+
+#[test]
+fn aead_aes_gcm_known_answer_zero_key() {
+    gcm_known_answer(&[0; 16], &[0; 12], &[], &[0; 16], &GCM_TEST_CASE_2);
+}
+
+#[test]
+fn aead_aes_128_gcm_known_answer() {
+    gcm_known_answer(
+        &GCM_KEY[..16],
+        &GCM_NONCE,
+        &GCM_ADDITIONAL_DATA,
+        &GCM_PLAINTEXT,
+        &GCM_TEST_CASE_4,
+    );
+}
+
+#[test]
+fn aead_aes_256_gcm_known_answer() {
+    gcm_known_answer(
+        &GCM_KEY,
+        &GCM_NONCE,
+        &GCM_ADDITIONAL_DATA,
+        &GCM_PLAINTEXT,
+        &GCM_TEST_CASE_16,
+    );
+}
+
+#[test]
+fn authenticate_additional_data_only() {
+    let alg = Aead::AeadWithDefaultLengthTag(AeadWithDefaultLengthTag::Gcm);
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_encrypt().set_decrypt();
+    let attributes = Attributes {
+        key_type: Type::Aes,
+        bits: 256,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: alg.into(),
+        },
+    };
+    psa_crypto::init().unwrap();
+    let my_key = key_management::generate(attributes, None).unwrap();
+    let header: Vec<u8> = (0..1024 * 1024).map(|i| (i % 251) as u8).collect();
+
+    // With an empty plaintext, the ciphertext is just the tag.
+    let mut tag = vec![0; attributes.aead_encrypt_output_size(alg, 0).unwrap()];
+    let length = aead::encrypt(my_key, alg, &GCM_NONCE, &header, &[], &mut tag).unwrap();
+    assert_eq!(length, attributes.aead_tag_length(alg).unwrap());
+
+    assert_eq!(
+        aead::decrypt(my_key, alg, &GCM_NONCE, &header, &tag[..length], &mut []),
+        Ok(0)
+    );
+    let mut tampered = header;
+    tampered[512 * 1024] ^= 1;
+    assert_eq!(
+        aead::decrypt(my_key, alg, &GCM_NONCE, &tampered, &tag[..length], &mut []),
+        Err(Error::InvalidSignature)
+    );
+
+    unsafe { key_management::destroy(my_key) }.unwrap();
+}
+
+#[test]
+fn aead_or_none_separates_unsupported() {
+    let alg = Aead::AeadWithDefaultLengthTag(AeadWithDefaultLengthTag::Chacha20Poly1305);
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_encrypt().set_decrypt();
+    let attributes = Attributes {
+        key_type: Type::Chacha20,
+        bits: 256,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: alg.into(),
+        },
+    };
+
+    psa_crypto::init().unwrap();
+    let key = key_management::generate(attributes, None).unwrap();
+    let mut ciphertext = [0; DECRYPTED_DATA.len() + 16];
+    let mut plaintext = [0; DECRYPTED_DATA.len()];
+
+    let size = aead::encrypt_or_none(
+        key,
+        alg,
+        &NONCE[..12],
+        &ADDITIONAL_DATA,
+        &DECRYPTED_DATA,
+        &mut ciphertext,
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+        aead::decrypt_or_none(
+            key,
+            alg,
+            &NONCE[..12],
+            &ADDITIONAL_DATA,
+            &ciphertext[..size],
+            &mut plaintext
+        ),
+        Ok(Some(DECRYPTED_DATA.len()))
+    );
+    assert_eq!(plaintext, DECRYPTED_DATA);
+
+    // Mbed TLS does not implement the 8-byte nonce variant of ChaCha20-Poly1305.
+    assert_eq!(
+        aead::encrypt_or_none(
+            key,
+            alg,
+            &NONCE[..8],
+            &ADDITIONAL_DATA,
+            &DECRYPTED_DATA,
+            &mut ciphertext
+        ),
+        Ok(None)
+    );
+    assert_eq!(
+        aead::decrypt_or_none(
+            key,
+            alg,
+            &NONCE[..8],
+            &ADDITIONAL_DATA,
+            &ciphertext[..size],
+            &mut plaintext
+        ),
+        Ok(None)
+    );
+    // Real failures
+    ciphertext[0] ^= 1;
+    assert_eq!(
+        aead::decrypt_or_none(
+            key,
+            alg,
+            &NONCE[..12],
+            &ADDITIONAL_DATA,
+            &ciphertext[..size],
+            &mut plaintext
+        ),
+        Err(Error::InvalidSignature)
+    );
+    assert_eq!(
+        aead::encrypt_or_none(
+            key,
+            alg,
+            &NONCE[..12],
+            &ADDITIONAL_DATA,
+            &DECRYPTED_DATA,
+            &mut ciphertext[..8]
+        ),
+        Err(Error::BufferTooSmall)
+    );
+
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[test]
+fn aead_sizes_match_implementation() {
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_encrypt();
+    let sizes = [
+        (
+            AeadWithDefaultLengthTag::Gcm,
+            Type::Aes,
+            aead::GCM_TAG_LENGTH,
+            aead::GCM_NONCE_LENGTH,
+        ),
+        (
+            AeadWithDefaultLengthTag::Ccm,
+            Type::Aes,
+            aead::CCM_TAG_LENGTH,
+            aead::CCM_NONCE_LENGTH,
+        ),
+        (
+            AeadWithDefaultLengthTag::Chacha20Poly1305,
+            Type::Chacha20,
+            aead::CHACHA20_POLY1305_TAG_LENGTH,
+            aead::CHACHA20_POLY1305_NONCE_LENGTH,
+        ),
+    ];
+    for (alg, key_type, tag_length, nonce_length) in sizes.iter() {
+        let alg = Aead::AeadWithDefaultLengthTag(*alg);
+        let attributes = Attributes {
+            key_type: *key_type,
+            bits: 256,
+            lifetime: Lifetime::Volatile,
+            policy: Policy {
+                usage_flags,
+                permitted_algorithms: alg.into(),
+            },
+        };
+        assert_eq!(attributes.aead_tag_length(alg), Ok(*tag_length));
+        assert_eq!(attributes.aead_nonce_length(alg), Ok(*nonce_length));
+        assert_eq!(
+            attributes.aead_encrypt_output_size(alg, 16),
+            Ok(16 + *tag_length)
+        );
+    }
+}
+
+#[test]
+fn aead_gcm_stack_buffers() {
+    const MESSAGE: [u8; 16] = *b"sixteen byte msg";
+    let alg = Aead::AeadWithDefaultLengthTag(AeadWithDefaultLengthTag::Gcm);
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_encrypt().set_decrypt();
+    let attributes = Attributes {
+        key_type: Type::Aes,
+        bits: 128,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: alg.into(),
+        },
+    };
+
+    psa_crypto::init().unwrap();
+    let key = key_management::import(attributes, None, &KEY_DATA).unwrap();
+    let nonce = [0x24; aead::GCM_NONCE_LENGTH];
+    let mut ciphertext = [0; MESSAGE.len() + aead::GCM_TAG_LENGTH];
+    assert_eq!(
+        aead::encrypt(
+            key,
+            alg,
+            &nonce,
+            &ADDITIONAL_DATA,
+            &MESSAGE,
+            &mut ciphertext
+        ),
+        Ok(ciphertext.len())
+    );
+    let mut plaintext = [0; MESSAGE.len()];
+    assert_eq!(
+        aead::decrypt(
+            key,
+            alg,
+            &nonce,
+            &ADDITIONAL_DATA,
+            &ciphertext,
+            &mut plaintext
+        ),
+        Ok(MESSAGE.len())
+    );
+    assert_eq!(plaintext, MESSAGE);
+
+    unsafe { key_management::destroy(key) }.unwrap();
+}
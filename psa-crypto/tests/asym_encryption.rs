@@ -0,0 +1,67 @@
+use psa_crypto::operations::{asym_encryption, key_management};
+use psa_crypto::types::algorithm::AsymmetricEncryption;
+use psa_crypto::types::key::{Attributes, Lifetime, Policy, Type, UsageFlags};
+use psa_crypto::types::status::Error;
+
+const MESSAGE: [u8; 16] = [
+    0x4e, 0x31, 0x74, 0x96, 0x8f, 0xe4, 0xba, 0xb3, 0xaf, 0x77, 0x75, 0x76, 0x61, 0xde, 0xe5, 0xb8,
+];
+
+fn pkcs1v15_attributes() -> Attributes {
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_encrypt().set_decrypt();
+    Attributes {
+        key_type: Type::RsaKeyPair,
+        bits: 1024,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: AsymmetricEncryption::RsaPkcs1v15Crypt.into(),
+        },
+    }
+}
+
+#[test]
+fn pkcs1v15_decrypt_failures_are_indistinguishable() {
+    let alg = AsymmetricEncryption::RsaPkcs1v15Crypt;
+    let attributes = pkcs1v15_attributes();
+
+    psa_crypto::init().unwrap();
+    let key = key_management::generate(attributes, None).unwrap();
+    let mut ciphertext = vec![0; attributes.asymmetric_encrypt_output_size(alg).unwrap()];
+    let size = asym_encryption::encrypt(key, alg, &MESSAGE, None, &mut ciphertext).unwrap();
+    ciphertext.truncate(size);
+
+    let mut plaintext = vec![0; attributes.asymmetric_decrypt_output_size(alg).unwrap()];
+    let size = asym_encryption::decrypt(key, alg, &ciphertext, None, &mut plaintext).unwrap();
+    assert_eq!(&plaintext[..size], &MESSAGE[..]);
+
+    // A ciphertext that decrypts to garbage padding.
+    let mut tampered = ciphertext.clone();
+    tampered[0] ^= 0x01;
+    tampered[64] ^= 0x80;
+    assert_eq!(
+        asym_encryption::decrypt(key, alg, &tampered, None, &mut plaintext).unwrap_err(),
+        Error::InvalidPadding
+    );
+
+    // A ciphertext which is not even a valid input for the modulus.
+    let out_of_range = vec![0xff; ciphertext.len()];
+    assert_eq!(
+        asym_encryption::decrypt(key, alg, &out_of_range, None, &mut plaintext).unwrap_err(),
+        Error::InvalidPadding
+    );
+
+    // An undersized buffer is rejected before decryption, whatever the ciphertext holds.
+    let mut short_plaintext = vec![0; MESSAGE.len()];
+    assert_eq!(
+        asym_encryption::decrypt(key, alg, &ciphertext, None, &mut short_plaintext).unwrap_err(),
+        Error::BufferTooSmall
+    );
+    assert_eq!(
+        asym_encryption::decrypt(key, alg, &tampered, None, &mut short_plaintext).unwrap_err(),
+        Error::BufferTooSmall
+    );
+
+    unsafe { key_management::destroy(key) }.unwrap();
+}
@@ -0,0 +1,270 @@
+use psa_crypto::operations::{asym_signature, key_management};
+use psa_crypto::types::algorithm::{AsymmetricSignature, Hash, SignHash};
+use psa_crypto::types::key::{Attributes, EccFamily, Lifetime, Policy, Type, UsageFlags};
+use psa_crypto::types::status::Error;
+
+const ECDSA_SHA256: AsymmetricSignature = AsymmetricSignature::Ecdsa {
+    hash_alg: SignHash::Specific(Hash::Sha256),
+};
+
+fn ecdsa_attributes(alg: AsymmetricSignature) -> Attributes {
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_sign_hash().set_verify_hash();
+    Attributes {
+        key_type: Type::EccKeyPair {
+            curve_family: EccFamily::SecpR1,
+        },
+        bits: 256,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: alg.into(),
+        },
+    }
+}
+
+#[test]
+fn sign_and_verify_message() {
+    let attributes = ecdsa_attributes(ECDSA_SHA256);
+
+    psa_crypto::init().unwrap();
+    let key = key_management::generate(attributes, None).unwrap();
+    let mut signature = vec![0; attributes.sign_output_size(ECDSA_SHA256).unwrap()];
+    let size = asym_signature::sign_message(key, ECDSA_SHA256, b"message", &mut signature).unwrap();
+    signature.resize(size, 0);
+
+    asym_signature::verify_message(key, ECDSA_SHA256, b"message", &signature).unwrap();
+    assert_eq!(
+        asym_signature::verify_message(key, ECDSA_SHA256, b"massage", &signature),
+        Err(Error::InvalidSignature)
+    );
+
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[test]
+fn verify_message_rejects_other_hash() {
+    let any_hash = AsymmetricSignature::Ecdsa {
+        hash_alg: SignHash::Any,
+    };
+    let ecdsa_sha384 = AsymmetricSignature::Ecdsa {
+        hash_alg: SignHash::Specific(Hash::Sha384),
+    };
+    let attributes = ecdsa_attributes(any_hash);
+
+    psa_crypto::init().unwrap();
+    let key = key_management::generate(attributes, None).unwrap();
+    let mut signature = vec![0; attributes.sign_output_size(ecdsa_sha384).unwrap()];
+    let size = asym_signature::sign_message(key, ecdsa_sha384, b"message", &mut signature).unwrap();
+    signature.resize(size, 0);
+
+    asym_signature::verify_message(key, ecdsa_sha384, b"message", &signature).unwrap();
+    assert_eq!(
+        asym_signature::verify_message(key, ECDSA_SHA256, b"message", &signature),
+        Err(Error::InvalidSignature)
+    );
+    assert_eq!(
+        asym_signature::verify_message(key, any_hash, b"message", &signature),
+        Err(Error::InvalidArgument)
+    );
+
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn verify_large_file_streaming() {
+    use std::fs::{self, File};
+    use std::io::Write;
+
+    // Large enough to need many reads, and not a multiple of the chunk size
+    let message: Vec<u8> = (0..4 * 1024 * 1024 + 17).map(|i| (i % 251) as u8).collect();
+    let path = std::env::temp_dir().join("psa-crypto-verify-message-streaming.bin");
+    File::create(&path).unwrap().write_all(&message).unwrap();
+
+    let attributes = ecdsa_attributes(ECDSA_SHA256);
+    psa_crypto::init().unwrap();
+    let key = key_management::generate(attributes, None).unwrap();
+    let mut signature = vec![0; attributes.sign_output_size(ECDSA_SHA256).unwrap()];
+    let size = asym_signature::sign_message(key, ECDSA_SHA256, &message, &mut signature).unwrap();
+    signature.resize(size, 0);
+
+    asym_signature::verify_message_streaming(
+        key,
+        ECDSA_SHA256,
+        File::open(&path).unwrap(),
+        &signature,
+    )
+    .unwrap();
+
+    let mut tampered = message;
+    tampered[3 * 1024 * 1024] ^= 1;
+    File::create(&path).unwrap().write_all(&tampered).unwrap();
+    assert_eq!(
+        asym_signature::verify_message_streaming(
+            key,
+            ECDSA_SHA256,
+            File::open(&path).unwrap(),
+            &signature,
+        ),
+        Err(Error::InvalidSignature)
+    );
+
+    fs::remove_file(&path).unwrap();
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn verify_streaming_needs_specific_hash() {
+    let alg = AsymmetricSignature::Ecdsa {
+        hash_alg: SignHash::Any,
+    };
+    let attributes = ecdsa_attributes(alg);
+
+    psa_crypto::init().unwrap();
+    let key = key_management::generate(attributes, None).unwrap();
+    assert_eq!(
+        asym_signature::verify_message_streaming(key, alg, &b"message"[..], &[0; 64]),
+        Err(Error::InvalidArgument)
+    );
+
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn verify_streaming_aborts_on_read_error() {
+    use std::io::{self, Read};
+
+    // Gives some data and then fails, as a dropped network connection would
+    struct FailingReader(usize);
+
+    impl Read for FailingReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0 == 0 {
+                return Err(io::Error::new(io::ErrorKind::ConnectionReset, "reset"));
+            }
+            let read = self.0.min(buf.len());
+            buf[..read].iter_mut().for_each(|byte| *byte = 0x5a);
+            self.0 -= read;
+            Ok(read)
+        }
+    }
+
+    let attributes = ecdsa_attributes(ECDSA_SHA256);
+    psa_crypto::init().unwrap();
+    let key = key_management::generate(attributes, None).unwrap();
+    let message = [0x5a; 10_000];
+    let mut signature = vec![0; attributes.sign_output_size(ECDSA_SHA256).unwrap()];
+    let size = asym_signature::sign_message(key, ECDSA_SHA256, &message, &mut signature).unwrap();
+    signature.resize(size, 0);
+
+    assert_eq!(
+        asym_signature::verify_message_streaming(
+            key,
+            ECDSA_SHA256,
+            FailingReader(message.len() / 2),
+            &signature
+        ),
+        Err(Error::InsufficientData)
+    );
+
+    // Nothing was left behind: the key can be used and destroyed straight away.
+    asym_signature::verify_message_streaming(key, ECDSA_SHA256, &message[..], &signature).unwrap();
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn verify_multi_two_of_three() {
+    const HASH: [u8; 32] = [0x5a; 32];
+    let attributes = ecdsa_attributes(ECDSA_SHA256);
+
+    psa_crypto::init().unwrap();
+    let keys = [
+        key_management::generate(attributes, None).unwrap(),
+        key_management::generate(attributes, None).unwrap(),
+        key_management::generate(attributes, None).unwrap(),
+    ];
+    let mut signatures = [[0; 64]; 3];
+    for (key, signature) in keys.iter().zip(signatures.iter_mut()) {
+        assert_eq!(
+            asym_signature::sign_hash(*key, ECDSA_SHA256, &HASH, signature),
+            Ok(64)
+        );
+    }
+    // The second signer signed something else.
+    assert_eq!(
+        asym_signature::sign_hash(keys[1], ECDSA_SHA256, &[0xa5; 32], &mut signatures[1]),
+        Ok(64)
+    );
+    let bundle = [&signatures[0][..], &signatures[1][..], &signatures[2][..]];
+
+    assert_eq!(
+        asym_signature::verify_multi(&keys, ECDSA_SHA256, &HASH, &bundle, 2),
+        Ok(vec![0, 2])
+    );
+    assert_eq!(
+        asym_signature::verify_multi(&keys, ECDSA_SHA256, &HASH, &bundle, 3),
+        Err(Error::InvalidSignature)
+    );
+
+    // Signatures are matched with the keys by position.
+    let swapped = [&signatures[2][..], &signatures[1][..], &signatures[0][..]];
+    assert_eq!(
+        asym_signature::verify_multi(&keys, ECDSA_SHA256, &HASH, &swapped, 1),
+        Err(Error::InvalidSignature)
+    );
+
+    // A signer cannot be counted twice.
+    let repeated = [keys[0], keys[0], keys[2]];
+    let repeated_bundle = [&signatures[0][..], &signatures[0][..], &signatures[2][..]];
+    assert_eq!(
+        asym_signature::verify_multi(&repeated, ECDSA_SHA256, &HASH, &repeated_bundle, 2),
+        Err(Error::InvalidArgument)
+    );
+    // Not even when its public key is imported in another slot.
+    let mut public_key = [0; 65];
+    assert_eq!(
+        key_management::export_public(keys[0], &mut public_key),
+        Ok(65)
+    );
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_verify_hash();
+    let public_attributes = Attributes {
+        key_type: Type::EccPublicKey {
+            curve_family: EccFamily::SecpR1,
+        },
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: ECDSA_SHA256.into(),
+        },
+        ..attributes
+    };
+    let imported = key_management::import(public_attributes, None, &public_key).unwrap();
+    let two_slots = [keys[0], imported];
+    let two_slots_bundle = [&signatures[0][..], &signatures[0][..]];
+    asym_signature::verify_hash(imported, ECDSA_SHA256, &HASH, &signatures[0]).unwrap();
+    assert_eq!(
+        asym_signature::verify_multi(&two_slots, ECDSA_SHA256, &HASH, &two_slots_bundle, 2),
+        Err(Error::InvalidArgument)
+    );
+    unsafe { key_management::destroy(imported) }.unwrap();
+    assert_eq!(
+        asym_signature::verify_multi(&keys, ECDSA_SHA256, &HASH, &bundle[..2], 2),
+        Err(Error::InvalidArgument)
+    );
+    assert_eq!(
+        asym_signature::verify_multi(&keys, ECDSA_SHA256, &HASH, &bundle, 0),
+        Err(Error::InvalidArgument)
+    );
+    assert_eq!(
+        asym_signature::verify_multi(&keys, ECDSA_SHA256, &HASH, &bundle, 4),
+        Err(Error::InvalidArgument)
+    );
+
+    for key in keys.iter() {
+        unsafe { key_management::destroy(*key) }.unwrap();
+    }
+}
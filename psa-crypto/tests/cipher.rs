@@ -0,0 +1,261 @@
+use psa_crypto::operations::{cipher, key_management};
+use psa_crypto::types::algorithm::Cipher;
+use psa_crypto::types::key::{Attributes, Lifetime, Policy, Type, UsageFlags};
+use psa_crypto::types::status::Error;
+
+const KEY_DATA: [u8; 16] = [
+    0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c,
+];
+// First three bytes of AES-128(KEY_DATA, 0^128)
+const KCV: [u8; 3] = [0x7d, 0xf7, 0x6b];
+
+fn kcv_attributes(key_type: Type, bits: usize) -> Attributes {
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_encrypt();
+    Attributes {
+        key_type,
+        bits,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: Cipher::EcbNoPadding.into(),
+        },
+    }
+}
+
+#[test]
+fn key_check_value_same_material() {
+    let attributes = kcv_attributes(Type::Aes, 128);
+
+    psa_crypto::init().unwrap();
+    let first = key_management::import(attributes, None, &KEY_DATA).unwrap();
+    let second = key_management::import(attributes, None, &KEY_DATA).unwrap();
+    assert_eq!(cipher::key_check_value(first).unwrap(), KCV);
+    assert_eq!(cipher::key_check_value(second).unwrap(), KCV);
+
+    unsafe { key_management::destroy(first) }.unwrap();
+    unsafe { key_management::destroy(second) }.unwrap();
+}
+
+#[test]
+fn key_check_value_different_material() {
+    let attributes = kcv_attributes(Type::Aes, 256);
+
+    psa_crypto::init().unwrap();
+    let first = key_management::generate(attributes, None).unwrap();
+    let second = key_management::generate(attributes, None).unwrap();
+    // Two random keys share a KCV with probability 2^-24.
+    assert_ne!(
+        cipher::key_check_value(first).unwrap(),
+        cipher::key_check_value(second).unwrap()
+    );
+
+    unsafe { key_management::destroy(first) }.unwrap();
+    unsafe { key_management::destroy(second) }.unwrap();
+}
+
+#[test]
+fn key_check_value_not_aes() {
+    let attributes = kcv_attributes(Type::Camellia, 128);
+
+    psa_crypto::init().unwrap();
+    let key = key_management::import(attributes, None, &KEY_DATA).unwrap();
+    assert_eq!(
+        cipher::key_check_value(key).unwrap_err(),
+        Error::NotSupported
+    );
+
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[test]
+fn cipher_output_sizes() {
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_encrypt();
+    let attributes = Attributes {
+        key_type: Type::Aes,
+        bits: 128,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: Cipher::CbcPkcs7.into(),
+        },
+    };
+
+    // The padding is only written when finishing.
+    assert_eq!(
+        attributes.cipher_finish_output_size(Cipher::CbcPkcs7),
+        Ok(16)
+    );
+    for (input_len, update_size) in [(0, 0), (1, 16), (16, 16), (17, 32), (100, 112)].iter() {
+        assert_eq!(
+            attributes.cipher_update_output_size(Cipher::CbcPkcs7, *input_len),
+            Ok(*update_size)
+        );
+    }
+
+    // The one-shot encryption fits in the update and finish sizes.
+    psa_crypto::init().unwrap();
+    let key = key_management::generate(attributes, None).unwrap();
+    let message = [0x5a; 100];
+    let mut ciphertext = vec![
+        0;
+        attributes
+            .cipher_update_output_size(Cipher::CbcPkcs7, message.len())
+            .unwrap()
+            + attributes
+                .cipher_finish_output_size(Cipher::CbcPkcs7)
+                .unwrap()
+    ];
+    assert_eq!(
+        cipher::encrypt(key, Cipher::CbcPkcs7, &message, &[0; 16], &mut ciphertext),
+        Ok(112)
+    );
+    unsafe { key_management::destroy(key) }.unwrap();
+
+    assert_eq!(attributes.cipher_finish_output_size(Cipher::Ctr), Ok(0));
+    assert_eq!(
+        attributes.cipher_update_output_size(Cipher::Ctr, 17),
+        Ok(17)
+    );
+}
+
+const SYMMETRIC_KEY: [u8; 32] = [
+    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+    0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f,
+];
+const SYMMETRIC_MESSAGE: &[u8] = b"same key bytes, two key types";
+// AES-256-CTR of `SYMMETRIC_MESSAGE` with `SYMMETRIC_KEY` and a zero IV
+const AES_CTR_CIPHERTEXT: [u8; 29] = [
+    0x81, 0xf1, 0x6d, 0xd3, 0x0a, 0x22, 0xfa, 0xa9, 0x89, 0x91, 0xe3, 0x1e, 0xb8, 0x5d, 0x5b, 0xa0,
+    0x84, 0x2a, 0x19, 0x8e, 0x21, 0xdc, 0xe6, 0xc5, 0xd2, 0x8f, 0xeb, 0x54, 0x3b,
+];
+// ChaCha20 of `SYMMETRIC_MESSAGE` with `SYMMETRIC_KEY`, a zero nonce and a zero counter
+const CHACHA20_CIPHERTEXT: [u8; 29] = [
+    0x4a, 0x9c, 0x46, 0x18, 0xf9, 0xae, 0x7c, 0x13, 0xad, 0xdf, 0x7a, 0x03, 0xdd, 0xaf, 0x66, 0x69,
+    0xfe, 0x42, 0xb7, 0x4f, 0xd7, 0xbb, 0x13, 0xec, 0xc6, 0xb5, 0x0d, 0x29, 0xab,
+];
+
+#[test]
+fn import_same_bytes_as_aes_and_chacha20() {
+    psa_crypto::init().unwrap();
+    let aes =
+        key_management::import_for_cipher(Type::Aes, Cipher::Ctr, None, &SYMMETRIC_KEY).unwrap();
+    let chacha20 = key_management::import_for_cipher(
+        Type::Chacha20,
+        Cipher::StreamCipher,
+        None,
+        &SYMMETRIC_KEY,
+    )
+    .unwrap();
+    assert_eq!(Attributes::from_key_id(aes).unwrap().key_type, Type::Aes);
+    assert_eq!(
+        Attributes::from_key_id(chacha20).unwrap().key_type,
+        Type::Chacha20
+    );
+
+    let mut ciphertext = [0; 29];
+    assert_eq!(
+        cipher::encrypt(
+            aes,
+            Cipher::Ctr,
+            SYMMETRIC_MESSAGE,
+            &[0; 16],
+            &mut ciphertext
+        ),
+        Ok(29)
+    );
+    assert_eq!(ciphertext, AES_CTR_CIPHERTEXT);
+    assert_eq!(
+        cipher::encrypt(
+            chacha20,
+            Cipher::StreamCipher,
+            SYMMETRIC_MESSAGE,
+            &[0; 12],
+            &mut ciphertext
+        ),
+        Ok(29)
+    );
+    assert_eq!(ciphertext, CHACHA20_CIPHERTEXT);
+
+    // Each key is only usable with its own cipher.
+    assert!(cipher::encrypt(
+        aes,
+        Cipher::StreamCipher,
+        SYMMETRIC_MESSAGE,
+        &[0; 12],
+        &mut ciphertext
+    )
+    .is_err());
+    assert!(key_management::import_for_cipher(
+        Type::Aes,
+        Cipher::StreamCipher,
+        None,
+        &SYMMETRIC_KEY
+    )
+    .is_err());
+    assert!(
+        key_management::import_for_cipher(Type::Hmac, Cipher::Ctr, None, &SYMMETRIC_KEY).is_err()
+    );
+
+    unsafe { key_management::destroy(aes) }.unwrap();
+    unsafe { key_management::destroy(chacha20) }.unwrap();
+}
+
+#[test]
+fn detached_and_prefixed_iv_equivalent() {
+    let alg = Cipher::CbcPkcs7;
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_encrypt().set_decrypt();
+    let attributes = Attributes {
+        key_type: Type::Aes,
+        bits: 128,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: alg.into(),
+        },
+    };
+    let message = b"an AES-CBC message longer than a block";
+
+    psa_crypto::init().unwrap();
+    let key = key_management::generate(attributes, None).unwrap();
+    let iv_length = attributes.cipher_iv_length(alg).unwrap();
+    assert_eq!(iv_length, 16);
+    let output_size = attributes
+        .cipher_encrypt_output_size(alg, message.len())
+        .unwrap();
+
+    // IV || ciphertext, split to decrypt with the IV passed separately
+    let mut prefixed = vec![0; output_size];
+    let size = cipher::encrypt_with_iv_prefix(key, alg, message, &mut prefixed).unwrap();
+    assert_eq!(size, iv_length + 48);
+    let (iv, ciphertext) = prefixed[..size].split_at(iv_length);
+    let mut plaintext = [0; 48];
+    let plaintext_size = cipher::decrypt(key, alg, ciphertext, iv, &mut plaintext).unwrap();
+    assert_eq!(&plaintext[..plaintext_size], &message[..]);
+    // The same IV gives the same ciphertext with the detached form.
+    let mut detached = [0; 48];
+    assert_eq!(
+        cipher::encrypt(key, alg, message, iv, &mut detached),
+        Ok(48)
+    );
+    assert_eq!(&detached[..], ciphertext);
+
+    // Separate IV and ciphertext, joined to decrypt as IV || ciphertext
+    let mut iv = [0; 16];
+    let mut ciphertext = [0; 48];
+    assert_eq!(
+        cipher::encrypt_detached_iv(key, alg, message, &mut iv, &mut ciphertext),
+        Ok((16, 48))
+    );
+    let joined = [&iv[..], &ciphertext[..]].concat();
+    let plaintext_size = cipher::decrypt_with_iv_prefix(key, alg, &joined, &mut plaintext).unwrap();
+    assert_eq!(&plaintext[..plaintext_size], &message[..]);
+
+    assert_eq!(
+        cipher::encrypt_detached_iv(key, alg, message, &mut [0; 15], &mut ciphertext),
+        Err(Error::BufferTooSmall)
+    );
+    unsafe { key_management::destroy(key) }.unwrap();
+}
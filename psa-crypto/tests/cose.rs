@@ -0,0 +1,353 @@
+#![cfg(feature = "cose")]
+
+use psa_crypto::operations::{asym_signature, cose, key_management, mac};
+use psa_crypto::types::algorithm::{AsymmetricSignature, FullLengthMac, Hash, Mac};
+use psa_crypto::types::key::{Attributes, EccFamily, Id, Lifetime, Policy, Type, UsageFlags};
+use psa_crypto::types::status::Error;
+
+const ES256: AsymmetricSignature = AsymmetricSignature::Ecdsa {
+    hash_alg: psa_crypto::types::algorithm::SignHash::Specific(Hash::Sha256),
+};
+const HMAC_256: Mac = Mac::FullLength(FullLengthMac::Hmac {
+    hash_alg: Hash::Sha256,
+});
+
+// Public key of meriadoc.brandybuck@buckland.example, from RFC 9052, appendix C.7.1
+const KID: &[u8] = b"meriadoc.brandybuck@buckland.example";
+const X: [u8; 32] = [
+    0x65, 0xed, 0xa5, 0xa1, 0x25, 0x77, 0xc2, 0xba, 0xe8, 0x29, 0x43, 0x7f, 0xe3, 0x38, 0x70, 0x1a,
+    0x10, 0xaa, 0xa3, 0x75, 0xe1, 0xbb, 0x5b, 0x5d, 0xe1, 0x08, 0xde, 0x43, 0x9c, 0x08, 0x55, 0x1d,
+];
+const Y: [u8; 32] = [
+    0x1e, 0x52, 0xed, 0x75, 0x70, 0x11, 0x63, 0xf7, 0xf9, 0xe4, 0x0d, 0xdf, 0x9f, 0x34, 0x1b, 0x3d,
+    0xc9, 0xba, 0x86, 0x0a, 0xf7, 0xe0, 0xca, 0x7c, 0xa7, 0xe9, 0xee, 0xcd, 0x00, 0x84, 0xd1, 0x9c,
+];
+
+// Key 11, from RFC 9052, appendix C.7
+const KEY_11_X: [u8; 32] = [
+    0xba, 0xc5, 0xb1, 0x1c, 0xad, 0x8f, 0x99, 0xf9, 0xc7, 0x2b, 0x05, 0xcf, 0x4b, 0x9e, 0x26, 0xd2,
+    0x44, 0xdc, 0x18, 0x9f, 0x74, 0x52, 0x28, 0x25, 0x5a, 0x21, 0x9a, 0x86, 0xd6, 0xa0, 0x9e, 0xff,
+];
+const KEY_11_Y: [u8; 32] = [
+    0x20, 0x13, 0x8b, 0xf8, 0x2d, 0xc1, 0xb6, 0xd5, 0x62, 0xbe, 0x0f, 0xa5, 0x4a, 0xb7, 0x80, 0x4a,
+    0x3a, 0x64, 0xb6, 0xd7, 0x2c, 0xcf, 0xed, 0x6b, 0x6f, 0xb6, 0xed, 0x28, 0xbb, 0xfc, 0x11, 0x7e,
+];
+const KEY_11_D: [u8; 32] = [
+    0x57, 0xc9, 0x20, 0x77, 0x66, 0x41, 0x46, 0xe8, 0x76, 0x76, 0x0c, 0x95, 0x20, 0xd0, 0x54, 0xaa,
+    0x93, 0xc3, 0xaf, 0xb0, 0x4e, 0x30, 0x67, 0x05, 0xdb, 0x60, 0x90, 0x30, 0x85, 0x07, 0xb4, 0xd3,
+];
+
+fn p256_key_pair() -> Id {
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_sign_message().set_verify_message();
+    let attributes = Attributes {
+        key_type: Type::EccKeyPair {
+            curve_family: EccFamily::SecpR1,
+        },
+        bits: 256,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: ES256.into(),
+        },
+    };
+    key_management::generate(attributes, None).unwrap()
+}
+
+/// `Sig_structure` of a `COSE_Sign1` with `{ alg: ES256 }` as protected header
+fn sig_structure(payload: &[u8]) -> Vec<u8> {
+    let mut structure = vec![0x84, 0x6a];
+    structure.extend_from_slice(b"Signature1");
+    // Protected header, as a byte string, and empty external additional data
+    structure.extend_from_slice(&[0x43, 0xa1, 0x01, 0x26, 0x40]);
+    if payload.len() < 24 {
+        structure.push(0x40 | payload.len() as u8);
+    } else {
+        structure.extend_from_slice(&[0x58, payload.len() as u8]);
+    }
+    structure.extend_from_slice(payload);
+    structure
+}
+
+#[test]
+fn p256_round_trip() {
+    psa_crypto::init().unwrap();
+    let key_pair = p256_key_pair();
+    let cose_key = cose::export_cose_key(key_pair).unwrap();
+    // kty: EC2, alg: ES256, crv: P-256, x, y
+    assert_eq!(cose_key.len(), 77);
+    assert_eq!(
+        cose_key[..10],
+        [0xa5, 0x01, 0x02, 0x03, 0x26, 0x20, 0x01, 0x21, 0x58, 0x20]
+    );
+    assert_eq!(cose_key[42..45], [0x22, 0x58, 0x20]);
+
+    let public_key = cose::import_cose_key(&cose_key).unwrap();
+    let attributes = Attributes::from_key_id(public_key).unwrap();
+    assert_eq!(
+        attributes.key_type,
+        Type::EccPublicKey {
+            curve_family: EccFamily::SecpR1
+        }
+    );
+    assert_eq!(attributes.policy.permitted_algorithms, ES256.into());
+    assert_eq!(cose::export_cose_key(public_key).unwrap(), cose_key);
+
+    // A COSE_Sign1 token signed with the key pair is verified with the imported key.
+    let payload = b"This is the content.";
+    let mut signature = [0; 64];
+    let signature_length =
+        asym_signature::sign_message(key_pair, ES256, &sig_structure(payload), &mut signature)
+            .unwrap();
+    asym_signature::verify_message(
+        public_key,
+        ES256,
+        &sig_structure(payload),
+        &signature[..signature_length],
+    )
+    .unwrap();
+    assert_eq!(
+        asym_signature::verify_message(
+            public_key,
+            ES256,
+            &sig_structure(b"This is other content"),
+            &signature[..signature_length],
+        ),
+        Err(Error::InvalidSignature)
+    );
+
+    unsafe { key_management::destroy(public_key) }.unwrap();
+    unsafe { key_management::destroy(key_pair) }.unwrap();
+}
+
+#[test]
+fn import_rfc_9052_key() {
+    psa_crypto::init().unwrap();
+    let cose_key = [
+        &[0xa6, 0x01, 0x02, 0x02, 0x58, 0x24][..],
+        KID,
+        &[0x03, 0x26, 0x20, 0x01, 0x21, 0x58, 0x20],
+        &X,
+        &[0x22, 0x58, 0x20],
+        &Y,
+    ]
+    .concat();
+    let key = cose::import_cose_key(&cose_key).unwrap();
+
+    let mut public_key = [0; 65];
+    assert_eq!(key_management::export_public(key, &mut public_key), Ok(65));
+    assert_eq!(public_key[0], 0x04);
+    assert_eq!(public_key[1..33], X);
+    assert_eq!(public_key[33..], Y);
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[test]
+fn verify_rfc_9052_sign1() {
+    // COSE_Sign1 of RFC 9052, appendix C.2.1, signed with key 11
+    const PAYLOAD: &[u8] = b"This is the content.";
+    const SIGNATURE: [u8; 64] = [
+        0x8e, 0xb3, 0x3e, 0x4c, 0xa3, 0x1d, 0x1c, 0x46, 0x5a, 0xb0, 0x5a, 0xac, 0x34, 0xcc, 0x6b,
+        0x23, 0xd5, 0x8f, 0xef, 0x5c, 0x08, 0x31, 0x06, 0xc4, 0xd2, 0x5a, 0x91, 0xae, 0xf0, 0xb0,
+        0x11, 0x7e, 0x2a, 0xf9, 0xa2, 0x91, 0xaa, 0x32, 0xe1, 0x4a, 0xb8, 0x34, 0xdc, 0x56, 0xed,
+        0x2a, 0x22, 0x34, 0x44, 0x54, 0x7e, 0x01, 0xf1, 0x1d, 0x3b, 0x09, 0x16, 0xe5, 0xa4, 0xc3,
+        0x45, 0xca, 0xcb, 0x36,
+    ];
+    psa_crypto::init().unwrap();
+    // kty: EC2, kid: '11', alg: ES256, crv: P-256, x, y
+    let cose_key = [
+        &[
+            0xa6, 0x01, 0x02, 0x02, 0x42, 0x31, 0x31, 0x03, 0x26, 0x20, 0x01, 0x21, 0x58, 0x20,
+        ][..],
+        &KEY_11_X,
+        &[0x22, 0x58, 0x20],
+        &KEY_11_Y,
+    ]
+    .concat();
+    let key = cose::import_cose_key(&cose_key).unwrap();
+
+    asym_signature::verify_message(key, ES256, &sig_structure(PAYLOAD), &SIGNATURE).unwrap();
+    assert_eq!(
+        asym_signature::verify_message(
+            key,
+            ES256,
+            &sig_structure(b"This is the content!"),
+            &SIGNATURE
+        ),
+        Err(Error::InvalidSignature)
+    );
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[test]
+fn import_key_pair_checks_public_key() {
+    psa_crypto::init().unwrap();
+    let key_pair = |parameters: &[&[u8]]| {
+        // kty: EC2, alg: ES256, crv: P-256, d
+        let mut cose_key = vec![
+            0xa4 + parameters.len() as u8,
+            0x01,
+            0x02,
+            0x03,
+            0x26,
+            0x20,
+            0x01,
+        ];
+        cose_key.extend_from_slice(&[0x23, 0x58, 0x20]);
+        cose_key.extend_from_slice(&KEY_11_D);
+        for parameter in parameters {
+            cose_key.extend_from_slice(parameter);
+        }
+        cose::import_cose_key(&cose_key)
+    };
+    let x = [&[0x21, 0x58, 0x20][..], &KEY_11_X].concat();
+    let y = [&[0x22, 0x58, 0x20][..], &KEY_11_Y].concat();
+
+    for parameters in [&[][..], &[&x[..]], &[&x[..], &y[..]]].iter() {
+        let key = key_pair(parameters).unwrap();
+        let mut public_key = [0; 65];
+        assert_eq!(key_management::export_public(key, &mut public_key), Ok(65));
+        assert_eq!(public_key[1..33], KEY_11_X);
+        assert_eq!(public_key[33..], KEY_11_Y);
+        unsafe { key_management::destroy(key) }.unwrap();
+    }
+
+    // Coordinates of another key
+    let other_x = [&[0x21, 0x58, 0x20][..], &X].concat();
+    let other_y = [&[0x22, 0x58, 0x20][..], &Y].concat();
+    assert_eq!(key_pair(&[&other_x, &y]), Err(Error::InvalidArgument));
+    assert_eq!(key_pair(&[&x, &other_y]), Err(Error::InvalidArgument));
+    assert_eq!(key_pair(&[&other_y]), Err(Error::InvalidArgument));
+    // Compressed point
+    assert_eq!(key_pair(&[&x, &[0x22, 0xf5]]), Err(Error::NotSupported));
+}
+
+#[test]
+fn symmetric_round_trip() {
+    psa_crypto::init().unwrap();
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_sign_hash().set_export();
+    let attributes = Attributes {
+        key_type: Type::Hmac,
+        bits: 256,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: HMAC_256.into(),
+        },
+    };
+    let key = key_management::generate(attributes, None).unwrap();
+    let cose_key = cose::export_cose_key(key).unwrap();
+    // kty: Symmetric, alg: HMAC 256/256, k
+    assert_eq!(
+        cose_key[..8],
+        [0xa3, 0x01, 0x04, 0x03, 0x05, 0x20, 0x58, 0x20]
+    );
+
+    let imported = cose::import_cose_key(&cose_key).unwrap();
+    let mut expected = [0; 32];
+    let mut actual = [0; 32];
+    mac::compute_mac(key, HMAC_256, b"message", &mut expected).unwrap();
+    mac::compute_mac(imported, HMAC_256, b"message", &mut actual).unwrap();
+    assert_eq!(actual, expected);
+    // The imported key has the usages of its algorithm, which do not include export.
+    assert_eq!(cose::export_cose_key(imported), Err(Error::NotPermitted));
+
+    unsafe { key_management::destroy(imported) }.unwrap();
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[test]
+fn key_ops_restrict_usage() {
+    psa_crypto::init().unwrap();
+    // kty: Symmetric, alg: HMAC 256/256, key_ops: [MAC verify], k
+    let mut cose_key = vec![0xa4, 0x01, 0x04, 0x03, 0x05, 0x04, 0x81, 0x0a, 0x20, 0x50];
+    cose_key.extend_from_slice(&[0x42; 16]);
+    let key = cose::import_cose_key(&cose_key).unwrap();
+
+    let mut tag = [0; 32];
+    assert_eq!(
+        mac::compute_mac(key, HMAC_256, b"message", &mut tag),
+        Err(Error::NotPermitted)
+    );
+    assert_eq!(
+        mac::verify_mac(key, HMAC_256, b"message", &tag),
+        Err(Error::InvalidSignature)
+    );
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[test]
+fn invalid_cose_keys() {
+    psa_crypto::init().unwrap();
+    let ec2 = |parameters: &[&[u8]]| {
+        let mut cose_key = vec![0xa0 | parameters.len() as u8];
+        for parameter in parameters {
+            cose_key.extend_from_slice(parameter);
+        }
+        cose::import_cose_key(&cose_key)
+    };
+    let kty = [0x01, 0x02];
+    let alg = [0x03, 0x26];
+    let crv = [0x20, 0x01];
+    let x = [&[0x21, 0x58, 0x20][..], &X].concat();
+    let y = [&[0x22, 0x58, 0x20][..], &Y].concat();
+    let short_x = [&[0x21, 0x58, 0x1f][..], &X[..31]].concat();
+    let key = ec2(&[&kty, &alg, &crv, &x, &y]).unwrap();
+    unsafe { key_management::destroy(key) }.unwrap();
+
+    // No alg
+    assert_eq!(ec2(&[&kty, &crv, &x, &y]), Err(Error::InvalidArgument));
+    // No y
+    assert_eq!(ec2(&[&kty, &alg, &crv, &x]), Err(Error::InvalidArgument));
+    // Duplicate crv
+    assert_eq!(
+        ec2(&[&kty, &alg, &crv, &crv, &x, &y]),
+        Err(Error::InvalidArgument)
+    );
+    // x of the wrong length
+    assert_eq!(
+        ec2(&[&kty, &alg, &crv, &short_x, &y]),
+        Err(Error::InvalidArgument)
+    );
+    // Compressed point
+    assert_eq!(
+        ec2(&[&kty, &alg, &crv, &x, &[0x22, 0xf5]]),
+        Err(Error::NotSupported)
+    );
+    // X25519 in an OKP key
+    assert_eq!(
+        ec2(&[&[0x01, 0x01], &alg, &[0x20, 0x04], &x]),
+        Err(Error::NotSupported)
+    );
+    // EdDSA
+    assert_eq!(
+        ec2(&[&kty, &[0x03, 0x27], &crv, &x, &y]),
+        Err(Error::NotSupported)
+    );
+    // HMAC with an EC2 key
+    assert_eq!(
+        ec2(&[&kty, &[0x03, 0x05], &crv, &x, &y]),
+        Err(Error::InvalidArgument)
+    );
+    // Truncated
+    assert_eq!(
+        cose::import_cose_key(&[0xa5, 0x01, 0x02]),
+        Err(Error::InvalidArgument)
+    );
+    // Trailing data
+    let mut cose_key = [&[0xa5][..], &kty, &alg, &crv, &x, &y].concat();
+    cose_key.push(0x00);
+    assert_eq!(
+        cose::import_cose_key(&cose_key),
+        Err(Error::InvalidArgument)
+    );
+
+    // A128GCM with a 256-bit key
+    let mut cose_key = vec![0xa3, 0x01, 0x04, 0x03, 0x01, 0x20, 0x58, 0x20];
+    cose_key.extend_from_slice(&[0x42; 32]);
+    assert_eq!(
+        cose::import_cose_key(&cose_key),
+        Err(Error::InvalidArgument)
+    );
+}
@@ -0,0 +1,88 @@
+// The default location is global: these tests are in their own binary so that they do not change
+// the location of the keys created by the other tests.
+use psa_crypto::operations::key_management;
+use psa_crypto::types::algorithm::{Aead, AeadWithDefaultLengthTag};
+use psa_crypto::types::key::{Attributes, KeyLocation, Lifetime, Policy, Type, UsageFlags};
+use psa_crypto::types::status::Error;
+
+fn attributes(lifetime: Lifetime) -> Attributes {
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_encrypt();
+    Attributes {
+        key_type: Type::Aes,
+        bits: 128,
+        lifetime,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: Aead::AeadWithDefaultLengthTag(AeadWithDefaultLengthTag::Gcm)
+                .into(),
+        },
+    }
+}
+
+fn location(key: psa_crypto::types::key::Id) -> KeyLocation {
+    Attributes::from_key_id(key).unwrap().lifetime.location()
+}
+
+#[test]
+fn default_location() {
+    psa_crypto::init().unwrap();
+    assert_eq!(
+        key_management::default_location(),
+        KeyLocation::LocalStorage
+    );
+
+    let before = key_management::generate(attributes(Lifetime::Persistent), Some(6000)).unwrap();
+    assert_eq!(location(before), KeyLocation::LocalStorage);
+
+    key_management::set_default_location(KeyLocation::PrimarySecureElement).unwrap();
+    assert_eq!(
+        key_management::default_location(),
+        KeyLocation::PrimarySecureElement
+    );
+    // There is no secure element driver in the test backend.
+    let result = key_management::generate(attributes(Lifetime::Persistent), Some(6001));
+    let result_from_volatile = key_management::generate(attributes(Lifetime::Volatile), Some(6001));
+    // Volatile keys, explicit lifetimes and existing keys are not moved.
+    let volatile = key_management::generate(attributes(Lifetime::Volatile), None).unwrap();
+    let local = Lifetime::Custom(psa_crypto_sys::PSA_KEY_LIFETIME_PERSISTENT);
+    let explicit = key_management::generate(attributes(local), Some(6002)).unwrap();
+    let explicit_location = location(explicit);
+    let before_location = location(before);
+    let volatile_location = location(volatile);
+    // The default location does not change how the lifetimes of existing keys read back.
+    let before_lifetime = Attributes::from_key_id(before).unwrap().lifetime;
+    key_management::set_default_location(KeyLocation::LocalStorage).unwrap();
+
+    assert_eq!(result, Err(Error::InvalidArgument));
+    assert_eq!(result_from_volatile, Err(Error::InvalidArgument));
+    assert_eq!(explicit_location, KeyLocation::LocalStorage);
+    assert_eq!(before_location, KeyLocation::LocalStorage);
+    assert_eq!(volatile_location, KeyLocation::LocalStorage);
+    assert_eq!(before_lifetime, Lifetime::Persistent);
+    assert_eq!(
+        Lifetime::persistent_in(KeyLocation::LocalStorage),
+        Ok(Lifetime::Persistent)
+    );
+    let secure_element = Lifetime::persistent_in(KeyLocation::PrimarySecureElement).unwrap();
+    assert_eq!(secure_element.location(), KeyLocation::PrimarySecureElement);
+    assert_eq!(
+        Lifetime::persistent_in(KeyLocation::Custom(0x0100_0000)),
+        Err(Error::InvalidArgument)
+    );
+
+    assert_eq!(
+        key_management::set_default_location(KeyLocation::Custom(0x0100_0000)),
+        Err(Error::InvalidArgument)
+    );
+    assert_eq!(
+        key_management::default_location(),
+        KeyLocation::LocalStorage
+    );
+
+    unsafe {
+        key_management::destroy(before).unwrap();
+        key_management::destroy(volatile).unwrap();
+        key_management::destroy(explicit).unwrap();
+    }
+}
@@ -0,0 +1,289 @@
+use psa_crypto::operations::{aead, cipher, hash, key_management, mac};
+use psa_crypto::types::algorithm::{
+    Aead, AeadWithDefaultLengthTag, Algorithm, Cipher, FullLengthMac, Hash, Mac,
+};
+use psa_crypto::types::key::{Attributes, Id, Lifetime, Policy, Type, UsageFlags};
+use psa_crypto::types::status::Error;
+
+// Key of RFC 4493 and of the examples of NIST SP 800-38A
+const AES_KEY: [u8; 16] = [
+    0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c,
+];
+
+fn import(key_type: Type, alg: Algorithm, usage_flags: UsageFlags, data: &[u8]) -> Id {
+    let attributes = Attributes {
+        key_type,
+        bits: 0,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: alg,
+        },
+    };
+    psa_crypto::init().unwrap();
+    key_management::import(attributes, None, data).unwrap()
+}
+
+#[test]
+fn empty_key() {
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_sign_hash();
+    let attributes = Attributes {
+        key_type: Type::Hmac,
+        bits: 0,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: Algorithm::None,
+        },
+    };
+    psa_crypto::init().unwrap();
+    assert_eq!(
+        key_management::import(attributes, None, &[]).unwrap_err(),
+        Error::InvalidArgument
+    );
+}
+
+#[test]
+fn empty_message_hash() {
+    const SHA256_EMPTY: [u8; 32] = [
+        0xe3, 0xb0, 0xc4, 0x42, 0x98, 0xfc, 0x1c, 0x14, 0x9a, 0xfb, 0xf4, 0xc8, 0x99, 0x6f, 0xb9,
+        0x24, 0x27, 0xae, 0x41, 0xe4, 0x64, 0x9b, 0x93, 0x4c, 0xa4, 0x95, 0x99, 0x1b, 0x78, 0x52,
+        0xb8, 0x55,
+    ];
+    psa_crypto::init().unwrap();
+    let mut digest = [0; 32];
+    assert_eq!(hash::hash_compute(Hash::Sha256, &[], &mut digest), Ok(32));
+    assert_eq!(digest, SHA256_EMPTY);
+    hash::hash_compare(Hash::Sha256, &[], &SHA256_EMPTY).unwrap();
+
+    let mut operation = Default::default();
+    hash::hash_setup(&mut operation, Hash::Sha256).unwrap();
+    hash::hash_update(&mut operation, &[]).unwrap();
+    let mut digest = [0; 32];
+    assert_eq!(hash::hash_finish(&mut operation, &mut digest), Ok(32));
+    assert_eq!(digest, SHA256_EMPTY);
+}
+
+#[test]
+fn empty_message_hmac() {
+    // HMAC-SHA-256 with the key of RFC 4231, test case 1
+    const TAG: [u8; 32] = [
+        0x99, 0x9a, 0x90, 0x12, 0x19, 0xf0, 0x32, 0xcd, 0x49, 0x7c, 0xad, 0xb5, 0xe6, 0x05, 0x1e,
+        0x97, 0xb6, 0xa2, 0x9a, 0xb2, 0x97, 0xbd, 0x6a, 0xe7, 0x22, 0xbd, 0x60, 0x62, 0xa2, 0xf5,
+        0x95, 0x42,
+    ];
+    let mac_alg = Mac::FullLength(FullLengthMac::Hmac {
+        hash_alg: Hash::Sha256,
+    });
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_sign_hash().set_verify_hash();
+    let key = import(Type::Hmac, mac_alg.into(), usage_flags, &[0x0b; 20]);
+
+    let mut tag = [0; 32];
+    assert_eq!(mac::compute_mac(key, mac_alg, &[], &mut tag), Ok(32));
+    assert_eq!(tag, TAG);
+    mac::verify_mac(key, mac_alg, &[], &TAG).unwrap();
+
+    let mut operation = Default::default();
+    mac::mac_sign_setup(&mut operation, key, mac_alg).unwrap();
+    mac::mac_update(&mut operation, &[]).unwrap();
+    let mut tag = [0; 32];
+    assert_eq!(mac::mac_sign_finish(&mut operation, &mut tag), Ok(32));
+    assert_eq!(tag, TAG);
+
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[test]
+fn empty_message_cmac() {
+    // RFC 4493, example 1
+    const TAG: [u8; 16] = [
+        0xbb, 0x1d, 0x69, 0x29, 0xe9, 0x59, 0x37, 0x28, 0x7f, 0xa3, 0x7d, 0x12, 0x9b, 0x75, 0x67,
+        0x46,
+    ];
+    let mac_alg = Mac::FullLength(FullLengthMac::Cmac);
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_sign_hash();
+    let key = import(Type::Aes, mac_alg.into(), usage_flags, &AES_KEY);
+
+    let mut tag = [0; 16];
+    assert_eq!(mac::compute_mac(key, mac_alg, &[], &mut tag), Ok(16));
+    assert_eq!(tag, TAG);
+    unsafe { key_management::destroy(key) }.unwrap();
+
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_encrypt();
+    let key = import(
+        Type::Aes,
+        Cipher::EcbNoPadding.into(),
+        usage_flags,
+        &AES_KEY,
+    );
+    let mut tag = [0; 16];
+    assert_eq!(
+        mac::CmacContext::new(key).unwrap().compute(&[], &mut tag),
+        Ok(16)
+    );
+    assert_eq!(tag, TAG);
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[test]
+fn empty_aad_aead() {
+    // Test cases 1 and 2 of the GCM specification
+    const TAG_1: [u8; 16] = [
+        0x58, 0xe2, 0xfc, 0xce, 0xfa, 0x7e, 0x30, 0x61, 0x36, 0x7f, 0x1d, 0x57, 0xa4, 0xe7, 0x45,
+        0x5a,
+    ];
+    const CIPHERTEXT_2: [u8; 32] = [
+        0x03, 0x88, 0xda, 0xce, 0x60, 0xb6, 0xa3, 0x92, 0xf3, 0x28, 0xc2, 0xb9, 0x71, 0xb2, 0xfe,
+        0x78, 0xab, 0x6e, 0x47, 0xd4, 0x2c, 0xec, 0x13, 0xbd, 0xf5, 0x3a, 0x67, 0xb2, 0x12, 0x57,
+        0xbd, 0xdf,
+    ];
+    let alg = Aead::AeadWithDefaultLengthTag(AeadWithDefaultLengthTag::Gcm);
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_encrypt().set_decrypt();
+    let key = import(Type::Aes, alg.into(), usage_flags, &[0; 16]);
+    let nonce = [0; 12];
+
+    let mut ciphertext = [0; 32];
+    assert_eq!(
+        aead::encrypt(key, alg, &nonce, &[], &[], &mut ciphertext),
+        Ok(16)
+    );
+    assert_eq!(ciphertext[..16], TAG_1);
+    let mut plaintext = [0xff; 16];
+    assert_eq!(
+        aead::decrypt(key, alg, &nonce, &[], &TAG_1, &mut plaintext),
+        Ok(0)
+    );
+    // A tag alone over empty data is still authenticated.
+    let mut forged = TAG_1;
+    forged[15] ^= 1;
+    assert_eq!(
+        aead::decrypt(key, alg, &nonce, &[], &forged, &mut plaintext),
+        Err(Error::InvalidSignature)
+    );
+
+    assert_eq!(
+        aead::encrypt(key, alg, &nonce, &[], &[0; 16], &mut ciphertext),
+        Ok(32)
+    );
+    assert_eq!(ciphertext, CIPHERTEXT_2);
+    assert_eq!(
+        aead::decrypt(key, alg, &nonce, &[], &CIPHERTEXT_2, &mut plaintext),
+        Ok(16)
+    );
+    assert_eq!(plaintext, [0; 16]);
+    unsafe { key_management::destroy(key) }.unwrap();
+
+    const CHACHA20_POLY1305_TAG: [u8; 16] = [
+        0x4e, 0xb9, 0x72, 0xc9, 0xa8, 0xfb, 0x3a, 0x1b, 0x38, 0x2b, 0xb4, 0xd3, 0x6f, 0x5f, 0xfa,
+        0xd1,
+    ];
+    let alg = Aead::AeadWithDefaultLengthTag(AeadWithDefaultLengthTag::Chacha20Poly1305);
+    let key = import(Type::Chacha20, alg.into(), usage_flags, &[0; 32]);
+    let mut ciphertext = [0; 16];
+    assert_eq!(
+        aead::encrypt(key, alg, &nonce, &[], &[], &mut ciphertext),
+        Ok(16)
+    );
+    assert_eq!(ciphertext, CHACHA20_POLY1305_TAG);
+    assert_eq!(
+        aead::decrypt(key, alg, &nonce, &[], &ciphertext, &mut []),
+        Ok(0)
+    );
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[test]
+fn empty_plaintext_cipher() {
+    // AES-128-CBC of a block of PKCS#7 padding
+    const PADDING_BLOCK: [u8; 16] = [
+        0xc8, 0x4a, 0xf0, 0xb6, 0x13, 0x43, 0x5d, 0x5d, 0x91, 0x82, 0x80, 0x1a, 0x9b, 0xd9, 0x32,
+        0x0b,
+    ];
+    let iv: Vec<u8> = (0..16).collect();
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_encrypt().set_decrypt();
+
+    let key = import(Type::Aes, Cipher::CbcPkcs7.into(), usage_flags, &AES_KEY);
+    let mut ciphertext = [0; 16];
+    assert_eq!(
+        cipher::encrypt(key, Cipher::CbcPkcs7, &[], &iv, &mut ciphertext),
+        Ok(16)
+    );
+    assert_eq!(ciphertext, PADDING_BLOCK);
+    let mut plaintext = [0; 16];
+    assert_eq!(
+        cipher::decrypt(key, Cipher::CbcPkcs7, &PADDING_BLOCK, &iv, &mut plaintext),
+        Ok(0)
+    );
+    unsafe { key_management::destroy(key) }.unwrap();
+
+    let key = import(Type::Aes, Cipher::Ctr.into(), usage_flags, &AES_KEY);
+    assert_eq!(cipher::encrypt(key, Cipher::Ctr, &[], &iv, &mut []), Ok(0));
+    assert_eq!(cipher::decrypt(key, Cipher::Ctr, &[], &iv, &mut []), Ok(0));
+    // The output is the random IV alone.
+    let mut output = [0; 16];
+    assert_eq!(
+        cipher::encrypt_with_iv_prefix(key, Cipher::Ctr, &[], &mut output),
+        Ok(16)
+    );
+    assert_eq!(
+        cipher::decrypt_with_iv_prefix(key, Cipher::Ctr, &output, &mut []),
+        Ok(0)
+    );
+    unsafe { key_management::destroy(key) }.unwrap();
+
+    let key = import(
+        Type::Aes,
+        Cipher::EcbNoPadding.into(),
+        usage_flags,
+        &AES_KEY,
+    );
+    assert_eq!(
+        cipher::encrypt(key, Cipher::EcbNoPadding, &[], &[], &mut []),
+        Ok(0)
+    );
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn empty_salt_hkdf() {
+    use psa_crypto::operations::key_derivation;
+    use psa_crypto::types::algorithm::KeyDerivation;
+    use psa_crypto::types::key_derivation::{Input, InputSecret, Inputs, Operation};
+
+    // RFC 5869, test case 3
+    const OKM: [u8; 42] = [
+        0x8d, 0xa4, 0xe7, 0x75, 0xa5, 0x63, 0xc1, 0x8f, 0x71, 0x5f, 0x80, 0x2a, 0x06, 0x3c, 0x5a,
+        0x31, 0xb8, 0xa1, 0x1f, 0x5c, 0x5e, 0xe1, 0x87, 0x9e, 0xc3, 0x45, 0x4e, 0x5f, 0x3c, 0x73,
+        0x8d, 0x2d, 0x9d, 0x20, 0x13, 0x95, 0xfa, 0xa4, 0xb6, 0x1a, 0x96, 0xc8,
+    ];
+    let hkdf = KeyDerivation::Hkdf {
+        hash_alg: Hash::Sha256,
+    };
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_derive();
+    let key = import(Type::Derive, hkdf.into(), usage_flags, &[0x0b; 22]);
+    let operation = |salt| Operation {
+        inputs: Inputs::Hkdf {
+            hash_alg: Hash::Sha256,
+            salt,
+            secret: InputSecret::Input(Input::Key(key)),
+            info: Input::Bytes(&[]),
+        },
+        capacity: None,
+    };
+
+    // An empty salt is the same as no salt: both are a string of zeros as HMAC key.
+    let okm = key_derivation::output_key_bytes(operation(Some(Input::Bytes(&[]))), 336).unwrap();
+    assert_eq!(*okm, OKM);
+    let okm = key_derivation::output_key_bytes(operation(None), 336).unwrap();
+    assert_eq!(*okm, OKM);
+
+    unsafe { key_management::destroy(key) }.unwrap();
+}
@@ -0,0 +1,132 @@
+#![cfg(feature = "std")]
+
+use psa_crypto::operations::{aead, envelope, key_management};
+use psa_crypto::types::algorithm::{Aead, AeadWithDefaultLengthTag, AsymmetricSignature, Hash};
+use psa_crypto::types::key::{Attributes, EccFamily, Id, Lifetime, Policy, Type, UsageFlags};
+use psa_crypto::types::status::Error;
+
+const SIG_ALG: AsymmetricSignature = AsymmetricSignature::Ecdsa {
+    hash_alg: psa_crypto::types::algorithm::SignHash::Specific(Hash::Sha256),
+};
+const AEAD_ALG: Aead = Aead::AeadWithDefaultLengthTag(AeadWithDefaultLengthTag::Gcm);
+const MESSAGE: &[u8] = b"a message both signed and encrypted";
+
+fn keys(verify: bool) -> (Id, Id) {
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_sign_hash();
+    if verify {
+        usage_flags.set_verify_hash();
+    }
+    let signing_attributes = Attributes {
+        key_type: Type::EccKeyPair {
+            curve_family: EccFamily::SecpR1,
+        },
+        bits: 256,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: SIG_ALG.into(),
+        },
+    };
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_encrypt().set_decrypt();
+    let aead_attributes = Attributes {
+        key_type: Type::Aes,
+        bits: 128,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: AEAD_ALG.into(),
+        },
+    };
+    psa_crypto::init().unwrap();
+    (
+        key_management::generate(signing_attributes, None).unwrap(),
+        key_management::generate(aead_attributes, None).unwrap(),
+    )
+}
+
+fn destroy(keys: (Id, Id)) {
+    unsafe { key_management::destroy(keys.0) }.unwrap();
+    unsafe { key_management::destroy(keys.1) }.unwrap();
+}
+
+#[test]
+fn sign_then_seal_round_trip() {
+    let (signing_key, aead_key) = keys(true);
+
+    let sealed =
+        envelope::sign_then_seal(signing_key, SIG_ALG, aead_key, AEAD_ALG, MESSAGE).unwrap();
+    assert_eq!(sealed[0], envelope::ENVELOPE_VERSION);
+    // Version, 12 bytes nonce, message, 64 bytes signature, signature length and 16 bytes tag
+    assert_eq!(sealed.len(), 1 + 12 + MESSAGE.len() + 64 + 2 + 16);
+    assert!(!sealed
+        .windows(MESSAGE.len())
+        .any(|window| window == MESSAGE));
+
+    let opened =
+        envelope::unseal_then_verify(signing_key, SIG_ALG, aead_key, AEAD_ALG, &sealed).unwrap();
+    assert_eq!(opened, MESSAGE);
+
+    let empty = envelope::sign_then_seal(signing_key, SIG_ALG, aead_key, AEAD_ALG, &[]).unwrap();
+    assert_eq!(
+        envelope::unseal_then_verify(signing_key, SIG_ALG, aead_key, AEAD_ALG, &empty),
+        Ok(vec![])
+    );
+
+    destroy((signing_key, aead_key));
+}
+
+#[test]
+fn signature_length_is_big_endian() {
+    let (signing_key, aead_key) = keys(true);
+
+    let sealed =
+        envelope::sign_then_seal(signing_key, SIG_ALG, aead_key, AEAD_ALG, MESSAGE).unwrap();
+    let (header, body) = sealed.split_at(1 + 12);
+    let mut contents = vec![0; body.len()];
+    let size = aead::decrypt(
+        aead_key,
+        AEAD_ALG,
+        &header[1..],
+        &header[..1],
+        body,
+        &mut contents,
+    )
+    .unwrap();
+    // 64 bytes signature
+    assert_eq!(&contents[size - 2..size], &[0x00, 0x40]);
+
+    destroy((signing_key, aead_key));
+}
+
+#[test]
+fn tampered_envelope_fails_before_signature_verification() {
+    // The signing key is not allowed to verify: reaching the signature verification fails with
+    // `NotPermitted`, so `InvalidSignature` can only come from the AEAD layer.
+    let (signing_key, aead_key) = keys(false);
+    let sealed =
+        envelope::sign_then_seal(signing_key, SIG_ALG, aead_key, AEAD_ALG, MESSAGE).unwrap();
+    assert_eq!(
+        envelope::unseal_then_verify(signing_key, SIG_ALG, aead_key, AEAD_ALG, &sealed),
+        Err(Error::NotPermitted)
+    );
+
+    for position in [1, 13, 20, sealed.len() - 1].iter() {
+        let mut tampered = sealed.clone();
+        tampered[*position] ^= 0x01;
+        assert_eq!(
+            envelope::unseal_then_verify(signing_key, SIG_ALG, aead_key, AEAD_ALG, &tampered),
+            Err(Error::InvalidSignature)
+        );
+    }
+
+    let mut other_version = sealed;
+    other_version[0] = 2;
+    assert_eq!(
+        envelope::unseal_then_verify(signing_key, SIG_ALG, aead_key, AEAD_ALG, &other_version),
+        Err(Error::NotSupported)
+    );
+
+    destroy((signing_key, aead_key));
+}
@@ -81,3 +81,64 @@ fn hash_compare_ripemd160_fail() {
     let hash_alg = Hash::Ripemd160;
     hash::hash_compare(hash_alg, &MESSAGE, &HASH).unwrap_err();
 }
+
+#[test]
+fn transcript_hash_snapshots() {
+    let hash_alg = Hash::Sha384;
+    let messages: [&[u8]; 3] = [b"ClientHello", b"ServerHello", b"Finished"];
+    psa_crypto::init().unwrap();
+
+    let mut transcript = hash::TranscriptHash::new(hash_alg).unwrap();
+    assert_eq!(transcript.algorithm(), hash_alg);
+    let mut all = Vec::new();
+    for message in messages.iter().take(2) {
+        transcript.update(message).unwrap();
+        all.extend_from_slice(message);
+
+        let mut snapshot = [0; 48];
+        assert_eq!(transcript.snapshot(&mut snapshot), Ok(48));
+        let mut expected = [0; 48];
+        hash::hash_compute(hash_alg, &all, &mut expected).unwrap();
+        assert_eq!(snapshot, expected);
+    }
+
+    transcript.update(messages[2]).unwrap();
+    all.extend_from_slice(messages[2]);
+    let mut digest = [0; 48];
+    assert_eq!(transcript.finish(&mut digest), Ok(48));
+    let mut expected = [0; 48];
+    hash::hash_compute(hash_alg, &all, &mut expected).unwrap();
+    assert_eq!(digest, expected);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn transcript_hash_snapshot_vec() {
+    psa_crypto::init().unwrap();
+    let mut transcript = hash::TranscriptHash::new(Hash::Sha384).unwrap();
+    transcript.update(b"ClientHello").unwrap();
+
+    let mut expected = [0; 48];
+    hash::hash_compute(Hash::Sha384, b"ClientHello", &mut expected).unwrap();
+    assert_eq!(transcript.snapshot_vec().unwrap(), expected);
+    // The transcript is still running.
+    transcript.update(b"ServerHello").unwrap();
+    assert_eq!(transcript.snapshot_vec().unwrap().len(), 48);
+}
+
+#[test]
+fn transcript_hash_snapshot_error() {
+    psa_crypto::init().unwrap();
+    let mut transcript = hash::TranscriptHash::new(Hash::Sha384).unwrap();
+    transcript.update(b"ClientHello").unwrap();
+
+    let mut snapshot = [0; 48];
+    // The copy of the operation fails when finishing.
+    assert_eq!(
+        transcript.snapshot(&mut snapshot[..32]),
+        Err(psa_crypto::types::status::Error::BufferTooSmall)
+    );
+    assert_eq!(transcript.snapshot(&mut snapshot), Ok(48));
+    transcript.update(b"ServerHello").unwrap();
+    assert_eq!(transcript.finish(&mut snapshot), Ok(48));
+}
@@ -1,5 +1,5 @@
 use psa_crypto::operations::{key_derivation, key_management};
-use psa_crypto::types::algorithm::{Hash, KeyAgreement, KeyDerivation, RawKeyAgreement};
+use psa_crypto::types::algorithm::{Algorithm, Hash, KeyAgreement, KeyDerivation, RawKeyAgreement};
 use psa_crypto::types::key::{Attributes, EccFamily, Lifetime, Policy, Type, UsageFlags};
 use psa_crypto::types::key_derivation::{Input, InputSecret, Inputs, Operation};
 
@@ -138,3 +138,204 @@ fn output_key_with_key_agreement() {
     };
     let _new_key = key_derivation::output_key(operation, derived_key_attributes, None).unwrap();
 }
+
+#[cfg(feature = "std")]
+#[test]
+fn output_key_bytes() {
+    use psa_crypto::types::status::Error;
+
+    // RFC 5869, test case 1
+    const IKM: [u8; 22] = [0x0b; 22];
+    const SALT: [u8; 13] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c,
+    ];
+    const INFO: [u8; 10] = [0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9];
+    const OKM: [u8; 32] = [
+        0x3c, 0xb2, 0x5f, 0x25, 0xfa, 0xac, 0xd5, 0x7a, 0x90, 0x43, 0x4f, 0x64, 0xd0, 0x36, 0x2f,
+        0x2a, 0x2d, 0x2d, 0x0a, 0x90, 0xcf, 0x1a, 0x5a, 0x4c, 0x5d, 0xb0, 0x2d, 0x56, 0xec, 0xc4,
+        0xc5, 0xbf,
+    ];
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_derive();
+    let attributes = Attributes {
+        key_type: Type::Derive,
+        bits: 0,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: KeyDerivation::Hkdf {
+                hash_alg: Hash::Sha256,
+            }
+            .into(),
+        },
+    };
+
+    psa_crypto::init().unwrap();
+    let key = key_management::import(attributes, None, &IKM).unwrap();
+    let operation = |capacity| Operation {
+        inputs: Inputs::Hkdf {
+            hash_alg: Hash::Sha256,
+            salt: Some(Input::Bytes(&SALT)),
+            secret: InputSecret::Input(Input::Key(key)),
+            info: Input::Bytes(&INFO),
+        },
+        capacity,
+    };
+
+    let key_material = key_derivation::output_key_bytes(operation(None), 256).unwrap();
+    assert_eq!(*key_material, OKM);
+    assert_eq!(
+        *key_derivation::output_key_bytes(operation(None), 128).unwrap(),
+        OKM[..16]
+    );
+
+    assert_eq!(
+        key_derivation::output_key_bytes(operation(None), 257).unwrap_err(),
+        Error::InvalidArgument
+    );
+    assert_eq!(
+        key_derivation::output_key_bytes(operation(None), 0).unwrap_err(),
+        Error::InvalidArgument
+    );
+    assert_eq!(
+        key_derivation::output_key_bytes(operation(Some(16)), 256).unwrap_err(),
+        Error::InsufficientData
+    );
+
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[test]
+fn derive_encryption_keys() {
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_derive();
+    let master_attributes = Attributes {
+        key_type: Type::Derive,
+        bits: 0,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: KeyDerivation::Hkdf {
+                hash_alg: Hash::Sha256,
+            }
+            .into(),
+        },
+    };
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_export();
+    let key_attributes = Attributes {
+        key_type: Type::RawData,
+        bits: 256,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: Algorithm::None,
+        },
+    };
+
+    psa_crypto::init().unwrap();
+    let master = key_management::import(master_attributes, None, &[0x42; 32]).unwrap();
+    let material = |keys: key_derivation::EncryptionKeys| {
+        let mut enc_key = [0; 32];
+        let mut iv_key = [0; 32];
+        assert_eq!(key_management::export(keys.enc_key, &mut enc_key), Ok(32));
+        assert_eq!(key_management::export(keys.iv_key, &mut iv_key), Ok(32));
+        (enc_key, iv_key)
+    };
+
+    let keys = key_derivation::derive_encryption_keys(
+        master,
+        Some(b"salt"),
+        key_attributes,
+        key_attributes,
+    )
+    .unwrap();
+    let (enc_key, iv_key) = material(keys);
+    assert_ne!(enc_key, iv_key);
+
+    let again = key_derivation::derive_encryption_keys(
+        master,
+        Some(b"salt"),
+        key_attributes,
+        key_attributes,
+    )
+    .unwrap();
+    assert_ne!(again, keys);
+    assert_eq!(material(again), (enc_key, iv_key));
+
+    // The keys are the HKDF output for each info.
+    let operation = Operation {
+        inputs: Inputs::Hkdf {
+            hash_alg: Hash::Sha256,
+            salt: Some(Input::Bytes(b"salt")),
+            secret: InputSecret::Input(Input::Key(master)),
+            info: Input::Bytes(key_derivation::IV_KEY_INFO),
+        },
+        capacity: None,
+    };
+    let iv_key_only = key_derivation::output_key(operation, key_attributes, None).unwrap();
+    let mut material = [0; 32];
+    assert_eq!(key_management::export(iv_key_only, &mut material), Ok(32));
+    assert_eq!(material, iv_key);
+
+    // A master key which does not allow HKDF
+    assert_eq!(
+        key_derivation::derive_encryption_keys(keys.enc_key, None, key_attributes, key_attributes),
+        Err(psa_crypto::types::status::Error::InvalidArgument)
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn tls12_psk_to_ms() {
+    // From the Mbed TLS test suite, checked against RFC 4279 and RFC 5246
+    const PSK: [u8; 4] = [0x01, 0x02, 0x03, 0x04];
+    const CLIENT_RANDOM: [u8; 32] = [
+        0x5b, 0xc0, 0xb1, 0x9b, 0x4a, 0x8b, 0x24, 0xb0, 0x7a, 0xfe, 0x7e, 0xc6, 0x5c, 0x47, 0x1e,
+        0x94, 0xa7, 0xd5, 0x18, 0xfc, 0xef, 0x06, 0xc3, 0x57, 0x43, 0x15, 0x25, 0x5c, 0x52, 0xaf,
+        0xe2, 0x1b,
+    ];
+    const SERVER_RANDOM: [u8; 32] = [
+        0x5b, 0xc0, 0xb1, 0x9b, 0x87, 0x2b, 0x9b, 0x26, 0x50, 0x84, 0x58, 0xf0, 0x36, 0x03, 0x74,
+        0x4d, 0x57, 0x5f, 0x46, 0x3a, 0x11, 0xae, 0x7f, 0x1b, 0x09, 0x0c, 0x01, 0x26, 0x06, 0xfd,
+        0x3e, 0x9f,
+    ];
+    const MASTER_SECRET: [u8; 48] = [
+        0x5a, 0x9d, 0xd5, 0xff, 0xa7, 0x8b, 0x4d, 0x1f, 0x28, 0xf4, 0x0d, 0x91, 0xb4, 0xe6, 0xe6,
+        0xed, 0x37, 0x84, 0x90, 0x42, 0xd6, 0x1b, 0xa3, 0x2c, 0xa4, 0x3d, 0x86, 0x6e, 0x74, 0x4c,
+        0xee, 0x7c, 0xd1, 0xba, 0xaa, 0x49, 0x7e, 0x1e, 0xcd, 0x5c, 0x2e, 0x60, 0xf9, 0xf1, 0x30,
+        0x30, 0xa7, 0x10,
+    ];
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_derive();
+    let attributes = Attributes {
+        key_type: Type::Derive,
+        bits: 0,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: KeyDerivation::Tls12PskToMs {
+                hash_alg: Hash::Sha256,
+            }
+            .into(),
+        },
+    };
+
+    psa_crypto::init().unwrap();
+    let psk = key_management::import(attributes, None, &PSK).unwrap();
+    let seed = [CLIENT_RANDOM, SERVER_RANDOM].concat();
+    let operation = Operation {
+        inputs: Inputs::Tls12PskToMs {
+            hash_alg: Hash::Sha256,
+            seed: Input::Bytes(&seed),
+            secret: InputSecret::Input(Input::Key(psk)),
+            label: Input::Bytes(b"master secret"),
+        },
+        capacity: None,
+    };
+
+    let master_secret = key_derivation::output_key_bytes(operation, 384).unwrap();
+    assert_eq!(*master_secret, MASTER_SECRET);
+
+    unsafe { key_management::destroy(psk) }.unwrap();
+}
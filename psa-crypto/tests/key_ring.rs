@@ -0,0 +1,73 @@
+#[path = "test_tools/mod.rs"]
+mod test_tools;
+
+use psa_crypto::operations::key_ring::KeyRing;
+use psa_crypto::operations::mac;
+use psa_crypto::types::algorithm::{FullLengthMac, Hash, Mac};
+use psa_crypto::types::key::{Attributes, Id, Lifetime, Policy, Type, UsageFlags};
+use psa_crypto::types::status::Error;
+use test_tools::TestClient;
+
+const BASE: u32 = 2000;
+const HMAC_SHA256: Mac = Mac::FullLength(FullLengthMac::Hmac {
+    hash_alg: Hash::Sha256,
+});
+const MESSAGE: &[u8] = b"data protected before the rotation";
+
+fn ring_attributes() -> Attributes {
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_sign_hash().set_verify_hash();
+    Attributes {
+        key_type: Type::Hmac,
+        bits: 256,
+        lifetime: Lifetime::Persistent,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: HMAC_SHA256.into(),
+        },
+    }
+}
+
+#[test]
+fn verify_with_previous_version() {
+    let mut client = TestClient::new();
+    for version in 0..3 {
+        client.track_persistent(BASE + version);
+    }
+    let mut ring = KeyRing::create(ring_attributes(), BASE, 1).unwrap();
+
+    let mut tag = [0; 32];
+    mac::compute_mac(ring.current(), HMAC_SHA256, MESSAGE, &mut tag).unwrap();
+    let tagged_version = ring.version();
+
+    unsafe { ring.rotate() }.unwrap();
+    assert_eq!(ring.version(), tagged_version + 1);
+    assert_eq!(
+        mac::verify_mac(ring.current(), HMAC_SHA256, MESSAGE, &tag),
+        Err(Error::InvalidSignature)
+    );
+    mac::verify_mac(
+        ring.get(tagged_version).unwrap(),
+        HMAC_SHA256,
+        MESSAGE,
+        &tag,
+    )
+    .unwrap();
+
+    // A reopened ring finds the same versions.
+    let reopened = KeyRing::open(BASE, ring.version(), 1).unwrap();
+    assert_eq!(reopened.current(), ring.current());
+    mac::verify_mac(
+        reopened.get(tagged_version).unwrap(),
+        HMAC_SHA256,
+        MESSAGE,
+        &tag,
+    )
+    .unwrap();
+
+    // Out of the retention window, the old version is gone.
+    unsafe { ring.rotate() }.unwrap();
+    assert_eq!(ring.get(tagged_version), Err(Error::DoesNotExist));
+    assert!(Id::from_persistent_key_id(BASE + tagged_version).is_err());
+    assert_eq!(ring.get(ring.version() + 1), Err(Error::DoesNotExist));
+}
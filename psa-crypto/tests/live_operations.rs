@@ -0,0 +1,65 @@
+// The count of live operations is global: these tests are in their own binary so that the
+// operations of the other tests are not counted.
+#![cfg(all(feature = "live-operations", debug_assertions))]
+
+use psa_crypto::operations::{hash, key_management, mac};
+use psa_crypto::types::algorithm::{FullLengthMac, Hash, Mac};
+use psa_crypto::types::key::{Attributes, Lifetime, Policy, Type, UsageFlags};
+use psa_crypto::types::operation::{
+    assert_no_live_operations, live_operations, HashOperation, MacOperation, MultipartOperation,
+};
+use std::panic;
+
+const MAC_ALG: Mac = Mac::FullLength(FullLengthMac::Hmac {
+    hash_alg: Hash::Sha256,
+});
+
+#[test]
+fn live_operations_count() {
+    psa_crypto::init().unwrap();
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_sign_hash().set_verify_hash();
+    let attributes = Attributes {
+        key_type: Type::Hmac,
+        bits: 256,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: MAC_ALG.into(),
+        },
+    };
+    let key = key_management::generate(attributes, None).unwrap();
+    assert_no_live_operations();
+
+    // A MAC operation which is set up and not finished is reported.
+    let mut operation = MacOperation::default();
+    mac::mac_sign_setup(&mut operation, key, MAC_ALG).unwrap();
+    assert_eq!(live_operations(), 1);
+    assert!(panic::catch_unwind(assert_no_live_operations).is_err());
+    operation.abort().unwrap();
+    assert_eq!(live_operations(), 0);
+    // Aborting again does not count it twice.
+    operation.abort().unwrap();
+    assert_eq!(live_operations(), 0);
+
+    // Finishing, successfully or not, ends the operation.
+    let mut tag = [0; 32];
+    mac::mac_sign_setup(&mut operation, key, MAC_ALG).unwrap();
+    mac::mac_update(&mut operation, b"message").unwrap();
+    mac::mac_sign_finish(&mut operation, &mut tag).unwrap();
+    mac::mac_verify_setup(&mut operation, key, MAC_ALG).unwrap();
+    mac::mac_update(&mut operation, b"other message").unwrap();
+    mac::mac_verify_finish(&mut operation, &tag).unwrap_err();
+    assert_eq!(live_operations(), 0);
+
+    // Clones are counted too.
+    let mut running = HashOperation::default();
+    let mut clone = HashOperation::default();
+    hash::hash_setup(&mut running, Hash::Sha256).unwrap();
+    hash::hash_clone(&running, &mut clone).unwrap();
+    assert_eq!(live_operations(), 2);
+    let mut hash = [0; 32];
+    hash::hash_finish(&mut running, &mut hash).unwrap();
+    hash::hash_verify(&mut clone, &hash).unwrap();
+    assert_no_live_operations();
+}
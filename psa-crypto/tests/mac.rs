@@ -0,0 +1,856 @@
+use psa_crypto::ffi::psa_algorithm_t;
+use psa_crypto::operations::{key_derivation, key_management, mac};
+use psa_crypto::types::algorithm::{Cipher, FullLengthMac, Hash, KeyDerivation, Mac};
+use psa_crypto::types::key::{Attributes, Id, Lifetime, Policy, Type, UsageFlags};
+use psa_crypto::types::key_derivation::{Input, InputSecret, Inputs, Operation};
+use psa_crypto::types::operation::{MacOperation, MultipartOperation};
+use psa_crypto::types::status::Error;
+
+const KEY_DATA: [u8; 32] = [
+    0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b,
+    0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b,
+];
+const MESSAGE: &[u8] = b"Hi There";
+
+const HMAC_SHA256: Mac = Mac::FullLength(FullLengthMac::Hmac {
+    hash_alg: Hash::Sha256,
+});
+
+fn hmac_attributes(mac_alg: Mac) -> Attributes {
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_sign_hash().set_verify_hash();
+    Attributes {
+        key_type: Type::Hmac,
+        bits: 0,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: mac_alg.into(),
+        },
+    }
+}
+
+fn abort_operation<O: MultipartOperation>(operation: &mut O) -> Result<(), Error> {
+    operation.abort()
+}
+
+#[test]
+fn abort_through_trait() {
+    psa_crypto::init().unwrap();
+    let key = key_management::import(hmac_attributes(HMAC_SHA256), None, &KEY_DATA).unwrap();
+
+    let mut operation = MacOperation::default();
+    // Aborting an operation which was never set up is fine.
+    abort_operation(&mut operation).unwrap();
+
+    mac::mac_sign_setup(&mut operation, key, HMAC_SHA256).unwrap();
+    mac::mac_update(&mut operation, MESSAGE).unwrap();
+    abort_operation(&mut operation).unwrap();
+    assert_eq!(
+        mac::mac_update(&mut operation, MESSAGE).unwrap_err(),
+        Error::BadState
+    );
+
+    // The operation can be reused once aborted.
+    let mut mac = [0; 32];
+    mac::mac_sign_setup(&mut operation, key, HMAC_SHA256).unwrap();
+    mac::mac_update(&mut operation, MESSAGE).unwrap();
+    assert_eq!(mac::mac_sign_finish(&mut operation, &mut mac).unwrap(), 32);
+
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[test]
+fn mac_or_none_separates_unsupported() {
+    #[allow(deprecated)]
+    let hmac_md2 = Mac::FullLength(FullLengthMac::Hmac {
+        hash_alg: Hash::Md2,
+    });
+
+    psa_crypto::init().unwrap();
+    let key = key_management::import(hmac_attributes(HMAC_SHA256), None, &KEY_DATA).unwrap();
+    let md2_key = key_management::import(hmac_attributes(hmac_md2), None, &KEY_DATA).unwrap();
+    let mut mac = [0; 32];
+
+    let size = mac::compute_mac_or_none(key, HMAC_SHA256, MESSAGE, &mut mac)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        mac::verify_mac_or_none(key, HMAC_SHA256, MESSAGE, &mac[..size]),
+        Ok(Some(()))
+    );
+    assert_eq!(
+        mac::verify_mac_or_none(key, HMAC_SHA256, b"Bye There", &mac[..size]),
+        Err(Error::InvalidSignature)
+    );
+
+    // Not available on this backend
+    assert_eq!(
+        mac::compute_mac_or_none(md2_key, hmac_md2, MESSAGE, &mut mac),
+        Ok(None)
+    );
+    assert_eq!(
+        mac::verify_mac_or_none(md2_key, hmac_md2, MESSAGE, &mac),
+        Ok(None)
+    );
+    // Real failures
+    assert_eq!(
+        mac::compute_mac_or_none(md2_key, HMAC_SHA256, MESSAGE, &mut mac),
+        Err(Error::NotPermitted)
+    );
+    assert_eq!(
+        mac::compute_mac_or_none(key, HMAC_SHA256, MESSAGE, &mut mac[..4]),
+        Err(Error::BufferTooSmall)
+    );
+
+    unsafe { key_management::destroy(key) }.unwrap();
+    unsafe { key_management::destroy(md2_key) }.unwrap();
+}
+
+#[test]
+fn verify_truncated_mac() {
+    let truncated = Mac::Truncated {
+        mac_alg: FullLengthMac::Hmac {
+            hash_alg: Hash::Sha256,
+        },
+        mac_length: 16,
+    };
+
+    psa_crypto::init().unwrap();
+    let key = key_management::import(hmac_attributes(HMAC_SHA256), None, &KEY_DATA).unwrap();
+    let truncated_key =
+        key_management::import(hmac_attributes(truncated), None, &KEY_DATA).unwrap();
+    let mut mac = [0; 32];
+    assert_eq!(
+        mac::compute_mac(key, HMAC_SHA256, MESSAGE, &mut mac),
+        Ok(32)
+    );
+
+    // The truncated MAC is the prefix of the full MAC.
+    mac::verify_mac(truncated_key, truncated, MESSAGE, &mac[..16]).unwrap();
+    assert_eq!(
+        mac::verify_mac(truncated_key, truncated, MESSAGE, &mac[..15]),
+        Err(Error::InvalidSignature)
+    );
+    assert_eq!(
+        mac::verify_mac(truncated_key, truncated, MESSAGE, &mac),
+        Err(Error::InvalidSignature)
+    );
+
+    // A full-length MAC is never compared on a prefix.
+    mac::verify_mac(key, HMAC_SHA256, MESSAGE, &mac).unwrap();
+    assert_eq!(
+        mac::verify_mac(key, HMAC_SHA256, MESSAGE, &mac[..16]),
+        Err(Error::InvalidArgument)
+    );
+    assert_eq!(
+        mac::verify_mac(key, HMAC_SHA256, MESSAGE, &[]),
+        Err(Error::InvalidArgument)
+    );
+
+    unsafe { key_management::destroy(truncated_key) }.unwrap();
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[test]
+fn compute_mac_reuses_buffer() {
+    let hmac_sha512 = Mac::FullLength(FullLengthMac::Hmac {
+        hash_alg: Hash::Sha512,
+    });
+
+    psa_crypto::init().unwrap();
+    let key = key_management::import(hmac_attributes(HMAC_SHA256), None, &KEY_DATA).unwrap();
+    let long_key = key_management::import(hmac_attributes(hmac_sha512), None, &KEY_DATA).unwrap();
+    let mut expected = [0; 32];
+    assert_eq!(
+        mac::compute_mac(key, HMAC_SHA256, MESSAGE, &mut expected),
+        Ok(32)
+    );
+
+    // The MAC written over a longer one is the same as in a fresh buffer.
+    let mut buffer = [0; 64];
+    assert_eq!(
+        mac::compute_mac(long_key, hmac_sha512, MESSAGE, &mut buffer),
+        Ok(64)
+    );
+    let length = mac::compute_mac(key, HMAC_SHA256, MESSAGE, &mut buffer).unwrap();
+    assert_eq!(buffer[..length], expected);
+
+    // A failed call does not get in the way of the next one.
+    let mut buffer = [0; 32];
+    assert_eq!(
+        mac::compute_mac(long_key, hmac_sha512, MESSAGE, &mut buffer),
+        Err(Error::BufferTooSmall)
+    );
+    let length = mac::compute_mac(key, HMAC_SHA256, MESSAGE, &mut buffer).unwrap();
+    assert_eq!(buffer[..length], expected);
+
+    unsafe { key_management::destroy(long_key) }.unwrap();
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn mac_sign_finish_vec_sizes_output() {
+    psa_crypto::init().unwrap();
+    let key = key_management::import(hmac_attributes(HMAC_SHA256), None, &KEY_DATA).unwrap();
+    let mut expected = [0; 32];
+    let _ = mac::compute_mac(key, HMAC_SHA256, MESSAGE, &mut expected).unwrap();
+
+    let mut operation = MacOperation::default();
+    assert_eq!(operation.algorithm(), None);
+    assert_eq!(
+        mac::mac_sign_finish_vec(&mut operation),
+        Err(Error::BadState)
+    );
+    mac::mac_sign_setup(&mut operation, key, HMAC_SHA256).unwrap();
+    assert_eq!(operation.algorithm(), Some(HMAC_SHA256));
+    mac::mac_update(&mut operation, MESSAGE).unwrap();
+    let mac = mac::mac_sign_finish_vec(&mut operation).unwrap();
+    assert_eq!(mac.len(), 32);
+    assert_eq!(mac, expected);
+    assert_eq!(operation.algorithm(), None);
+
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[test]
+fn secure_compare_checks_every_byte() {
+    let hmac_sha512 = Mac::FullLength(FullLengthMac::Hmac {
+        hash_alg: Hash::Sha512,
+    });
+
+    psa_crypto::init().unwrap();
+    let key = key_management::import(hmac_attributes(hmac_sha512), None, &KEY_DATA).unwrap();
+    let mut mac = [0; 64];
+    assert_eq!(
+        mac::compute_mac(key, hmac_sha512, MESSAGE, &mut mac),
+        Ok(64)
+    );
+    assert!(mac::secure_compare(&mac, &mac.clone()));
+
+    // A difference is found wherever it is, including in the last byte.
+    for position in [0, 31, 63] {
+        let mut other = mac;
+        other[position] ^= 0x01;
+        assert!(!mac::secure_compare(&mac, &other));
+        assert_eq!(
+            mac::verify_mac(key, hmac_sha512, MESSAGE, &other),
+            Err(Error::InvalidSignature)
+        );
+    }
+    // A prefix is not equal.
+    assert!(!mac::secure_compare(&mac, &mac[..63]));
+    assert!(!mac::secure_compare(&mac, &[]));
+    assert!(mac::secure_compare(&[], &[]));
+
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[test]
+fn hmac_sha512_truncated_variants_lengths() {
+    for (hash_alg, length) in [(Hash::Sha512_224, 28), (Hash::Sha512_256, 32)] {
+        let mac_alg = Mac::FullLength(FullLengthMac::Hmac { hash_alg });
+        assert_eq!(hash_alg.hash_length(), length);
+        assert_eq!(
+            hmac_attributes(mac_alg).mac_length(mac_alg).unwrap(),
+            length
+        );
+    }
+    // SHA-512/256 is its own algorithm with a different IV, not a truncation of SHA-512.
+    assert_eq!(
+        psa_algorithm_t::from(Hash::Sha512_256),
+        psa_crypto::ffi::PSA_ALG_SHA_512_256
+    );
+    assert_eq!(
+        psa_algorithm_t::from(Hash::Sha512_224),
+        psa_crypto::ffi::PSA_ALG_SHA_512_224
+    );
+}
+
+#[test]
+fn hmac_sha512_256_not_supported() {
+    let mac_alg = Mac::FullLength(FullLengthMac::Hmac {
+        hash_alg: Hash::Sha512_256,
+    });
+
+    psa_crypto::init().unwrap();
+    let key = key_management::import(hmac_attributes(mac_alg), None, &[0x0b; 20]).unwrap();
+    let mut mac = [0; 32];
+    // Mbed TLS does not implement SHA-512/256 yet. It must refuse the algorithm rather than
+    // silently compute something else.
+    assert_eq!(
+        mac::compute_mac(key, mac_alg, MESSAGE, &mut mac),
+        Err(Error::NotSupported)
+    );
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+// For a backend implementing SHA-512/256, which Mbed TLS 3.0 does not: run with `--ignored`.
+#[test]
+#[ignore]
+fn hmac_sha512_256_known_answer() {
+    // RFC 4231 test case 1 key and data
+    const KEY: [u8; 20] = [0x0b; 20];
+    // HMAC-SHA-512/256, which differs from the first 32 bytes of HMAC-SHA-512
+    // (87aa7cdea5ef619d...)
+    const EXPECTED: [u8; 32] = [
+        0x9f, 0x91, 0x26, 0xc3, 0xd9, 0xc3, 0xc3, 0x30, 0xd7, 0x60, 0x42, 0x5c, 0xa8, 0xa2, 0x17,
+        0xe3, 0x1f, 0xea, 0xe3, 0x1b, 0xfe, 0x70, 0x19, 0x6f, 0xf8, 0x16, 0x42, 0xb8, 0x68, 0x40,
+        0x2e, 0xab,
+    ];
+    let mac_alg = Mac::FullLength(FullLengthMac::Hmac {
+        hash_alg: Hash::Sha512_256,
+    });
+
+    psa_crypto::init().unwrap();
+    let key = key_management::import(hmac_attributes(mac_alg), None, &KEY).unwrap();
+    let mut mac = [0; 32];
+    assert_eq!(mac::compute_mac(key, mac_alg, MESSAGE, &mut mac), Ok(32));
+    assert_eq!(mac, EXPECTED);
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+// RFC 4493 section 4 test vectors
+const CMAC_KEY: [u8; 16] = [
+    0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c,
+];
+const CMAC_MESSAGE: [u8; 64] = [
+    0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17, 0x2a,
+    0xae, 0x2d, 0x8a, 0x57, 0x1e, 0x03, 0xac, 0x9c, 0x9e, 0xb7, 0x6f, 0xac, 0x45, 0xaf, 0x8e, 0x51,
+    0x30, 0xc8, 0x1c, 0x46, 0xa3, 0x5c, 0xe4, 0x11, 0xe5, 0xfb, 0xc1, 0x19, 0x1a, 0x0a, 0x52, 0xef,
+    0xf6, 0x9f, 0x24, 0x45, 0xdf, 0x4f, 0x9b, 0x17, 0xad, 0x2b, 0x41, 0x7b, 0xe6, 0x6c, 0x37, 0x10,
+];
+const CMAC_VECTORS: [(usize, [u8; 16]); 4] = [
+    (
+        0,
+        [
+            0xbb, 0x1d, 0x69, 0x29, 0xe9, 0x59, 0x37, 0x28, 0x7f, 0xa3, 0x7d, 0x12, 0x9b, 0x75,
+            0x67, 0x46,
+        ],
+    ),
+    (
+        16,
+        [
+            0x07, 0x0a, 0x16, 0xb4, 0x6b, 0x4d, 0x41, 0x44, 0xf7, 0x9b, 0xdd, 0x9d, 0xd0, 0x4a,
+            0x28, 0x7c,
+        ],
+    ),
+    (
+        40,
+        [
+            0xdf, 0xa6, 0x67, 0x47, 0xde, 0x9a, 0xe6, 0x30, 0x30, 0xca, 0x32, 0x61, 0x14, 0x97,
+            0xc8, 0x27,
+        ],
+    ),
+    (
+        64,
+        [
+            0x51, 0xf0, 0xbe, 0xbf, 0x7e, 0x3b, 0x9d, 0x92, 0xfc, 0x49, 0x74, 0x17, 0x79, 0x36,
+            0x3c, 0xfe,
+        ],
+    ),
+];
+const CMAC: Mac = Mac::FullLength(FullLengthMac::Cmac);
+
+fn cmac_context_attributes(key_type: Type) -> Attributes {
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_encrypt();
+    Attributes {
+        key_type,
+        bits: 128,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: Cipher::EcbNoPadding.into(),
+        },
+    }
+}
+
+fn cmac_attributes() -> Attributes {
+    let mut attributes = hmac_attributes(CMAC);
+    attributes.key_type = Type::Aes;
+    attributes.bits = 128;
+    attributes
+}
+
+#[test]
+fn cmac_known_answer() {
+    psa_crypto::init().unwrap();
+    let key = key_management::import(cmac_attributes(), None, &CMAC_KEY).unwrap();
+
+    for (length, expected) in CMAC_VECTORS.iter() {
+        let mut mac = [0; 16];
+        assert_eq!(
+            mac::compute_mac(key, CMAC, &CMAC_MESSAGE[..*length], &mut mac),
+            Ok(16)
+        );
+        assert_eq!(&mac, expected);
+        mac::verify_mac(key, CMAC, &CMAC_MESSAGE[..*length], expected).unwrap();
+    }
+    // The length of a CMAC comes from the cipher of the key.
+    assert_eq!(
+        mac::verify_mac(key, CMAC, &[], &CMAC_VECTORS[0].1[..8]),
+        Err(Error::InvalidArgument)
+    );
+
+    unsafe { key_management::destroy(key) }.unwrap();
+    assert_eq!(
+        mac::verify_mac(key, CMAC, &[], &CMAC_VECTORS[0].1),
+        Err(Error::InvalidHandle)
+    );
+}
+
+#[test]
+fn cmac_context_known_answer() {
+    psa_crypto::init().unwrap();
+    let key = key_management::import(cmac_context_attributes(Type::Aes), None, &CMAC_KEY).unwrap();
+    let context = mac::CmacContext::new(key).unwrap();
+
+    for (length, expected) in CMAC_VECTORS.iter() {
+        let mut mac = [0; 16];
+        assert_eq!(context.compute(&CMAC_MESSAGE[..*length], &mut mac), Ok(16));
+        assert_eq!(&mac, expected);
+    }
+
+    drop(context);
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[test]
+fn cmac_context_matches_compute_mac() {
+    psa_crypto::init().unwrap();
+    let context_key =
+        key_management::import(cmac_context_attributes(Type::Aes), None, &CMAC_KEY).unwrap();
+    let mac_key = key_management::import(cmac_attributes(), None, &CMAC_KEY).unwrap();
+    let context = mac::CmacContext::new(context_key).unwrap();
+
+    let message: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+    for length in [1, 15, 17, 31, 32, 255, 256, 257, 1000].iter() {
+        let mut expected = [0; 16];
+        mac::compute_mac(mac_key, CMAC, &message[..*length], &mut expected).unwrap();
+        let mut mac = [0; 16];
+        context.compute(&message[..*length], &mut mac).unwrap();
+        assert_eq!(mac, expected);
+    }
+
+    drop(context);
+    unsafe { key_management::destroy(context_key) }.unwrap();
+    unsafe { key_management::destroy(mac_key) }.unwrap();
+}
+
+#[test]
+fn cmac_context_errors() {
+    psa_crypto::init().unwrap();
+    let key = key_management::import(cmac_context_attributes(Type::Aes), None, &CMAC_KEY).unwrap();
+    let context = mac::CmacContext::new(key).unwrap();
+    assert_eq!(
+        context.compute(&CMAC_MESSAGE, &mut [0; 15]),
+        Err(Error::BufferTooSmall)
+    );
+    drop(context);
+    unsafe { key_management::destroy(key) }.unwrap();
+
+    let key =
+        key_management::import(cmac_context_attributes(Type::Camellia), None, &CMAC_KEY).unwrap();
+    assert_eq!(
+        mac::CmacContext::new(key).map(|_| ()),
+        Err(Error::NotSupported)
+    );
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+// Timing comparison, run with `cargo test --release -- --ignored --nocapture cmac_context_speed`
+#[test]
+#[ignore]
+fn cmac_context_speed() {
+    use std::time::Instant;
+
+    const ROUNDS: usize = 100_000;
+
+    psa_crypto::init().unwrap();
+    let context_key =
+        key_management::import(cmac_context_attributes(Type::Aes), None, &CMAC_KEY).unwrap();
+    let mac_key = key_management::import(cmac_attributes(), None, &CMAC_KEY).unwrap();
+    let context = mac::CmacContext::new(context_key).unwrap();
+
+    for length in [16, 64, 1024].iter() {
+        let message = &vec![0x5a; *length][..];
+        let mut mac = [0; 16];
+
+        let start = Instant::now();
+        for _ in 0..ROUNDS {
+            mac::compute_mac(mac_key, CMAC, message, &mut mac).unwrap();
+        }
+        let compute_mac_time = start.elapsed();
+
+        let start = Instant::now();
+        for _ in 0..ROUNDS {
+            context.compute(message, &mut mac).unwrap();
+        }
+        let context_time = start.elapsed();
+
+        println!(
+            "{} byte messages: compute_mac {:?}, CmacContext {:?}",
+            length, compute_mac_time, context_time
+        );
+    }
+
+    drop(context);
+    unsafe { key_management::destroy(context_key) }.unwrap();
+    unsafe { key_management::destroy(mac_key) }.unwrap();
+}
+
+#[test]
+fn sequenced_mac_rejects_replay() {
+    psa_crypto::init().unwrap();
+    let key = key_management::import(hmac_attributes(HMAC_SHA256), None, &KEY_DATA).unwrap();
+    let mut sender = mac::SequencedMac::new(key, HMAC_SHA256, 0);
+    let mut receiver = mac::SequencedMac::new(key, HMAC_SHA256, 0);
+
+    let mut macs = [[0; 32]; 7];
+    for (sequence, mac) in macs.iter_mut().enumerate() {
+        let record = [sequence as u8; 10];
+        assert_eq!(sender.sign(&record, mac), Ok(32));
+        receiver.verify(&record, mac).unwrap();
+    }
+    assert_eq!(receiver.sequence(), 7);
+
+    // Replaying record 5 after record 6
+    assert_eq!(
+        receiver.verify(&[5; 10], &macs[5]),
+        Err(Error::InvalidSignature)
+    );
+    // A failed verification does not move the sequence on.
+    assert_eq!(receiver.sequence(), 7);
+
+    // The same record at another position has another MAC.
+    assert_ne!(macs[5], macs[6]);
+    let mut record_6_at_5 = [0; 32];
+    mac::SequencedMac::new(key, HMAC_SHA256, 5)
+        .sign(&[6; 10], &mut record_6_at_5)
+        .unwrap();
+    assert_ne!(record_6_at_5, macs[6]);
+
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[test]
+fn sequenced_mac_verify_at() {
+    psa_crypto::init().unwrap();
+    let key = key_management::import(hmac_attributes(HMAC_SHA256), None, &KEY_DATA).unwrap();
+    let mut sender = mac::SequencedMac::new(key, HMAC_SHA256, 10);
+    let receiver = mac::SequencedMac::new(key, HMAC_SHA256, 0);
+
+    let mut macs = [[0; 32]; 3];
+    for (sequence, mac) in macs.iter_mut().enumerate() {
+        assert_eq!(sender.sign(&[sequence as u8; 10], mac), Ok(32));
+    }
+    // Out of order, and far from the receiver's own sequence number
+    receiver.verify_at(12, &[2; 10], &macs[2]).unwrap();
+    receiver.verify_at(10, &[0; 10], &macs[0]).unwrap();
+    receiver.verify_at(11, &[1; 10], &macs[1]).unwrap();
+    assert_eq!(receiver.sequence(), 0);
+
+    // A record checked at another position than the one it was signed at
+    assert_eq!(
+        receiver.verify_at(11, &[2; 10], &macs[2]),
+        Err(Error::InvalidSignature)
+    );
+    assert_eq!(
+        receiver.verify_at(12, &[2; 10], &macs[2][..31]),
+        Err(Error::InvalidSignature)
+    );
+
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[test]
+fn sequenced_mac_aborts_on_error() {
+    psa_crypto::init().unwrap();
+    let key = key_management::import(hmac_attributes(HMAC_SHA256), None, &KEY_DATA).unwrap();
+    let mut sender = mac::SequencedMac::new(key, HMAC_SHA256, 0);
+
+    // The operation fails when finishing, after the record has been added.
+    assert_eq!(
+        sender.sign(b"record", &mut [0; 16]),
+        Err(Error::BufferTooSmall)
+    );
+    assert_eq!(sender.sequence(), 0);
+
+    let mut mac = [0; 32];
+    assert_eq!(sender.sign(b"record", &mut mac), Ok(32));
+    let mut receiver = mac::SequencedMac::new(key, HMAC_SHA256, 0);
+    assert_eq!(
+        receiver.verify(b"record", &mac[..31]),
+        Err(Error::InvalidSignature)
+    );
+    receiver.verify(b"record", &mac).unwrap();
+
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[test]
+fn sequenced_mac_input_layout() {
+    psa_crypto::init().unwrap();
+    let key = key_management::import(hmac_attributes(HMAC_SHA256), None, &KEY_DATA).unwrap();
+    let mut sender = mac::SequencedMac::new(key, HMAC_SHA256, 0x0102_0304_0506_0708);
+
+    let mut mac = [0; 32];
+    let size = sender.sign(MESSAGE, &mut mac).unwrap();
+    // Big-endian sequence number, big-endian record length, record
+    let input = b"\x01\x02\x03\x04\x05\x06\x07\x08\0\0\0\0\0\0\0\x08Hi There";
+    mac::verify_mac(key, HMAC_SHA256, input, &mac[..size]).unwrap();
+
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn compute_mac_pull_matches_single_shot() {
+    psa_crypto::init().unwrap();
+    let key = key_management::import(hmac_attributes(HMAC_SHA256), None, &KEY_DATA).unwrap();
+    let chunks: Vec<Vec<u8>> = vec![vec![], vec![0x5a; 100], vec![0xa5; 1], vec![0x3c; 4096]];
+
+    let mut pulled = chunks.clone().into_iter();
+    let mac = mac::compute_mac_pull(key, HMAC_SHA256, || Ok(pulled.next())).unwrap();
+    let mut expected = [0; 32];
+    let size = mac::compute_mac(key, HMAC_SHA256, &chunks.concat(), &mut expected).unwrap();
+    assert_eq!(mac, &expected[..size]);
+
+    // An error from the source aborts the operation, the key stays usable.
+    let mut calls = 0;
+    assert_eq!(
+        mac::compute_mac_pull(key, HMAC_SHA256, || {
+            calls += 1;
+            if calls == 3 {
+                Err(Error::InsufficientData)
+            } else {
+                Ok(Some(vec![0; 10]))
+            }
+        }),
+        Err(Error::InsufficientData)
+    );
+    assert_eq!(
+        mac::compute_mac_pull(key, HMAC_SHA256, || Ok(None))
+            .unwrap()
+            .len(),
+        32
+    );
+
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[test]
+fn versioned_mac_binds_version_and_type() {
+    psa_crypto::init().unwrap();
+    let key = key_management::import(hmac_attributes(HMAC_SHA256), None, &KEY_DATA).unwrap();
+    let v1 = mac::VersionedMac::new(key, HMAC_SHA256, 1);
+    let v2 = mac::VersionedMac::new(key, HMAC_SHA256, 2);
+    let v1_typed = mac::VersionedMac::with_record_type(key, HMAC_SHA256, 1, 7);
+    let v1_other_type = mac::VersionedMac::with_record_type(key, HMAC_SHA256, 1, 8);
+
+    let mut mac = [0; 32];
+    assert_eq!(v1.compute(MESSAGE, &mut mac), Ok(32));
+    v1.verify(MESSAGE, &mac).unwrap();
+    mac::verify_mac(key, HMAC_SHA256, b"\x01\x00Hi There", &mac).unwrap();
+    assert_eq!(v2.verify(MESSAGE, &mac), Err(Error::InvalidSignature));
+    assert_eq!(v1_typed.verify(MESSAGE, &mac), Err(Error::InvalidSignature));
+
+    assert_eq!(v1_typed.compute(MESSAGE, &mut mac), Ok(32));
+    v1_typed.verify(MESSAGE, &mac).unwrap();
+    mac::verify_mac(key, HMAC_SHA256, b"\x01\x01\x07Hi There", &mac).unwrap();
+    assert_eq!(
+        v1_other_type.verify(MESSAGE, &mac),
+        Err(Error::InvalidSignature)
+    );
+    // The type is not confused with the start of an untyped record.
+    assert_eq!(
+        v1.verify(b"\x01\x07Hi There", &mac),
+        Err(Error::InvalidSignature)
+    );
+
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[cfg(feature = "std")]
+struct Record {
+    id: u32,
+    payload: &'static [u8],
+}
+
+#[cfg(feature = "std")]
+impl mac::Maccable for Record {
+    fn mac_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+        let mut bytes = self.id.to_be_bytes().to_vec();
+        bytes.extend_from_slice(self.payload);
+        bytes.into()
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn compute_mac_of_uses_canonical_encoding() {
+    psa_crypto::init().unwrap();
+    let key = key_management::import(hmac_attributes(HMAC_SHA256), None, &KEY_DATA).unwrap();
+    let record = Record {
+        id: 0x0102_0304,
+        payload: MESSAGE,
+    };
+
+    let mac = mac::compute_mac_of(key, HMAC_SHA256, &record).unwrap();
+    let mut expected = [0; 32];
+    let size =
+        mac::compute_mac(key, HMAC_SHA256, b"\x01\x02\x03\x04Hi There", &mut expected).unwrap();
+    assert_eq!(mac, &expected[..size]);
+    mac::verify_mac(key, HMAC_SHA256, b"\x01\x02\x03\x04Hi There", &mac).unwrap();
+
+    // Byte containers are their own encoding.
+    let size = mac::compute_mac(key, HMAC_SHA256, MESSAGE, &mut expected).unwrap();
+    assert_eq!(
+        mac::compute_mac_of(key, HMAC_SHA256, MESSAGE).unwrap(),
+        &expected[..size]
+    );
+    assert_eq!(
+        mac::compute_mac_of(key, HMAC_SHA256, &MESSAGE.to_vec()).unwrap(),
+        &expected[..size]
+    );
+
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+fn ratchet_chain_key() -> Id {
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_derive();
+    let attributes = Attributes {
+        key_type: Type::Derive,
+        bits: 256,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: KeyDerivation::Hkdf {
+                hash_alg: Hash::Sha256,
+            }
+            .into(),
+        },
+    };
+    key_management::import(attributes, None, &KEY_DATA).unwrap()
+}
+
+#[test]
+fn ratchet_mac_verifier_follows() {
+    psa_crypto::init().unwrap();
+    let mut sender = mac::MacRatchet::new(ratchet_chain_key(), HMAC_SHA256, 0).unwrap();
+    let mut receiver = mac::MacRatchet::new(ratchet_chain_key(), HMAC_SHA256, 0).unwrap();
+
+    // More messages than Mbed TLS has key slots: the previous chain keys must have been destroyed.
+    let mut macs = [[0; 32]; 100];
+    for mac in macs.iter_mut() {
+        unsafe {
+            assert_eq!(mac::ratchet_mac(&mut sender, MESSAGE, mac), Ok(32));
+            mac::ratchet_verify_mac(&mut receiver, MESSAGE, mac).unwrap();
+        }
+    }
+    assert_eq!(sender.counter(), 100);
+    assert_eq!(receiver.counter(), 100);
+
+    // Each message has its own key, so the same message never gets the same MAC twice.
+    for (i, mac) in macs.iter().enumerate() {
+        assert!(macs[i + 1..].iter().all(|other| other != mac));
+    }
+}
+
+#[test]
+fn ratchet_hkdf_info_layout() {
+    psa_crypto::init().unwrap();
+    let mut ratchet =
+        mac::MacRatchet::new(ratchet_chain_key(), HMAC_SHA256, 0x0102_0304_0506_0708).unwrap();
+    let mut mac = [0; 32];
+    let size = unsafe { mac::ratchet_mac(&mut ratchet, MESSAGE, &mut mac) }.unwrap();
+
+    // Big-endian counter followed by the message key label
+    let chain_key = ratchet_chain_key();
+    let operation = Operation {
+        inputs: Inputs::Hkdf {
+            hash_alg: Hash::Sha256,
+            salt: None,
+            secret: InputSecret::Input(Input::Key(chain_key)),
+            info: Input::Bytes(b"\x01\x02\x03\x04\x05\x06\x07\x08\x00"),
+        },
+        capacity: None,
+    };
+    let mut message_key_attributes = hmac_attributes(HMAC_SHA256);
+    message_key_attributes.bits = 256;
+    let message_key = key_derivation::output_key(operation, message_key_attributes, None).unwrap();
+    mac::verify_mac(message_key, HMAC_SHA256, MESSAGE, &mac[..size]).unwrap();
+
+    unsafe { key_management::destroy(message_key) }.unwrap();
+    unsafe { key_management::destroy(chain_key) }.unwrap();
+}
+
+#[test]
+fn ratchet_verify_mac_rejects_forgery() {
+    psa_crypto::init().unwrap();
+    let mut sender = mac::MacRatchet::new(ratchet_chain_key(), HMAC_SHA256, 0).unwrap();
+    let mut receiver = mac::MacRatchet::new(ratchet_chain_key(), HMAC_SHA256, 0).unwrap();
+
+    let mut mac = [0; 32];
+    unsafe {
+        let _ = mac::ratchet_mac(&mut sender, MESSAGE, &mut mac).unwrap();
+        let mut forged = mac;
+        forged[0] ^= 1;
+        assert_eq!(
+            mac::ratchet_verify_mac(&mut receiver, MESSAGE, &forged),
+            Err(Error::InvalidSignature)
+        );
+        // The forgery does not move the receiver on.
+        assert_eq!(receiver.counter(), 0);
+        mac::ratchet_verify_mac(&mut receiver, MESSAGE, &mac).unwrap();
+        // Replaying the message fails with the key of the next message.
+        assert_eq!(
+            mac::ratchet_verify_mac(&mut receiver, MESSAGE, &mac),
+            Err(Error::InvalidSignature)
+        );
+    }
+}
+
+#[test]
+fn ratchet_mac_destroys_message_key_on_error() {
+    psa_crypto::init().unwrap();
+    let mut sender = mac::MacRatchet::new(ratchet_chain_key(), HMAC_SHA256, 0).unwrap();
+
+    // More failures than Mbed TLS has key slots: each one-time key must have been destroyed.
+    for _ in 0..100 {
+        assert_eq!(
+            unsafe { mac::ratchet_mac(&mut sender, MESSAGE, &mut [0; 16]) },
+            Err(Error::BufferTooSmall)
+        );
+    }
+    assert_eq!(sender.counter(), 0);
+    assert_eq!(
+        unsafe { mac::ratchet_mac(&mut sender, MESSAGE, &mut [0; 32]) },
+        Ok(32)
+    );
+}
+
+#[test]
+fn ratchet_destroys_chain_key() {
+    psa_crypto::init().unwrap();
+    let chain_key = ratchet_chain_key();
+    drop(mac::MacRatchet::new(chain_key, HMAC_SHA256, 0).unwrap());
+    assert_eq!(
+        Attributes::from_key_id(chain_key),
+        Err(Error::InvalidHandle)
+    );
+
+    // The ratchet owns the chain key even if it cannot be started.
+    let chain_key = ratchet_chain_key();
+    assert_eq!(
+        mac::MacRatchet::new(chain_key, Mac::FullLength(FullLengthMac::Cmac), 0).unwrap_err(),
+        Error::NotSupported
+    );
+    assert_eq!(
+        Attributes::from_key_id(chain_key),
+        Err(Error::InvalidHandle)
+    );
+}
@@ -4,11 +4,23 @@
 
 use psa_crypto::operations::key_management;
 use psa_crypto::types::algorithm::{Algorithm, AsymmetricSignature, Hash};
-use psa_crypto::types::key::{Attributes, EccFamily, Lifetime, Policy, Type, UsageFlags};
+use psa_crypto::types::key::{Attributes, EccFamily, Id, Lifetime, Policy, Type, UsageFlags};
+use psa_crypto::types::status::Error;
 
 mod aead;
+mod asym_encryption;
+mod asym_signature;
+mod cipher;
+mod cose;
+mod empty_inputs;
+mod envelope;
 mod hash;
 mod key_agreement;
+mod mac;
+mod pkcs11;
+mod pkcs8;
+mod single_use;
+mod spki;
 
 #[test]
 fn generate_integration_test() {
@@ -71,6 +83,128 @@ fn import_integration_test() {
     }
 }
 
+// Key material which records that it has been zeroized
+struct TrackedKeyData {
+    data: Vec<u8>,
+    zeroized: std::rc::Rc<std::cell::Cell<bool>>,
+}
+
+impl zeroize::Zeroize for TrackedKeyData {
+    fn zeroize(&mut self) {
+        self.data.zeroize();
+        self.zeroized.set(true);
+    }
+}
+
+impl AsRef<[u8]> for TrackedKeyData {
+    fn as_ref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn provision_batch_rolls_back() {
+    const BASE: u32 = 4000;
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_export();
+    let attributes = Attributes {
+        lifetime: Lifetime::Persistent,
+        key_type: Type::Aes,
+        bits: 128,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: Algorithm::None,
+        },
+    };
+
+    let mut test_client = test_tools::TestClient::new();
+    for id in BASE..BASE + 4 {
+        test_client.track_persistent(id);
+    }
+    // The third key has the wrong size.
+    assert_eq!(
+        key_management::provision_batch(&[
+            (attributes, BASE, vec![1; 16]),
+            (attributes, BASE + 1, vec![2; 16]),
+            (attributes, BASE + 2, vec![3; 15]),
+            (attributes, BASE + 3, vec![4; 16]),
+        ]),
+        Err(Error::InvalidArgument)
+    );
+    for id in BASE..BASE + 4 {
+        assert_eq!(
+            Id::from_persistent_key_id(id).unwrap_err(),
+            Error::InvalidHandle
+        );
+    }
+
+    // A key existing before the batch is kept.
+    let existing = key_management::import(attributes, Some(BASE + 1), &[5; 16]).unwrap();
+    assert_eq!(
+        key_management::provision_batch(&[
+            (attributes, BASE, vec![1; 16]),
+            (attributes, BASE + 1, vec![2; 16]),
+        ]),
+        Err(Error::AlreadyExists)
+    );
+    assert!(Id::from_persistent_key_id(BASE).is_err());
+    let mut data = [0; 16];
+    assert_eq!(key_management::export(existing, &mut data), Ok(16));
+    assert_eq!(data, [5; 16]);
+    unsafe { key_management::destroy(existing) }.unwrap();
+
+    let keys = key_management::provision_batch(&[
+        (attributes, BASE, vec![1; 16]),
+        (attributes, BASE + 1, vec![2; 16]),
+    ])
+    .unwrap();
+    assert_eq!(keys.len(), 2);
+    for (key, byte) in keys.iter().zip(1..) {
+        assert_eq!(key_management::export(*key, &mut data), Ok(16));
+        assert_eq!(data, [byte; 16]);
+    }
+}
+
+#[test]
+fn import_zeroizing() {
+    use zeroize::Zeroizing;
+
+    let key_data = [0x42; 16];
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_export();
+    let attributes = Attributes {
+        lifetime: Lifetime::Volatile,
+        key_type: Type::Aes,
+        bits: 128,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: Algorithm::None,
+        },
+    };
+
+    psa_crypto::init().unwrap();
+    for (data, imported) in [(key_data.to_vec(), true), (vec![0x42; 15], false)].iter() {
+        let zeroized = std::rc::Rc::new(std::cell::Cell::new(false));
+        let tracked = Zeroizing::new(TrackedKeyData {
+            data: data.clone(),
+            zeroized: zeroized.clone(),
+        });
+        let result = key_management::import_zeroizing(attributes, None, tracked);
+        // The buffer is erased on success and on failure.
+        assert!(zeroized.get());
+        if *imported {
+            let key = result.unwrap();
+            let mut exported = [0; 16];
+            assert_eq!(key_management::export(key, &mut exported), Ok(16));
+            assert_eq!(exported, key_data);
+            unsafe { key_management::destroy(key) }.unwrap();
+        } else {
+            assert_eq!(result, Err(Error::InvalidArgument));
+        }
+    }
+}
+
 #[test]
 fn export_key_pair_test() {
     const PRIVATE_KEY: &str = "MIICWwIBAAKBgQCd+EKeRmZCKLmg7LasWqpKA9/01linY75ujilf6v/Kb8UP9r/E\
@@ -176,54 +310,209 @@ fn copy_key_incompatible_copy_attrs() {
     let _copied_key_id = key_management::copy(key_id, incompatible_copy_attrs, None).unwrap_err();
 }
 
-mod test_tools {
-    use psa_crypto::operations::key_management;
-    use psa_crypto::types::key::{Attributes, Id};
-    use psa_crypto::types::status::Result;
+#[test]
+fn same_key() {
+    const PERSISTENT_ID: u32 = 3000;
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_copy().set_export();
+    let attributes = Attributes {
+        lifetime: Lifetime::Persistent,
+        key_type: Type::Aes,
+        bits: 128,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: Algorithm::None,
+        },
+    };
+    let mut volatile_attributes = attributes;
+    volatile_attributes.lifetime = Lifetime::Volatile;
+    let mut test_client = test_tools::TestClient::new();
+
+    let key = test_client.generate(attributes, Some(PERSISTENT_ID));
+    let opened = Id::from_persistent_key_id(PERSISTENT_ID).unwrap();
+    let opened_again = Id::from_persistent_key_id(PERSISTENT_ID).unwrap();
+    assert!(key_management::same_key(key, opened).unwrap());
+    assert!(key_management::same_key(opened, opened_again).unwrap());
+
+    // A copy has the same material but is another key.
+    let copy = test_client.copy_key(key, volatile_attributes, None);
+    assert!(key_management::same_key(copy, copy).unwrap());
+    assert!(!key_management::same_key(key, copy).unwrap());
+
+    let other = test_client.generate(volatile_attributes, None);
+    assert!(!key_management::same_key(copy, other).unwrap());
+}
+
+#[test]
+fn validate_attributes() {
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_sign_hash().set_verify_hash();
+    let attributes = Attributes {
+        key_type: Type::EccKeyPair {
+            curve_family: EccFamily::SecpR1,
+        },
+        bits: 256,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: AsymmetricSignature::Ecdsa {
+                hash_alg: Hash::Sha256.into(),
+            }
+            .into(),
+        },
+    };
+    key_management::validate_attributes(attributes).unwrap();
+
+    // Curve size which does not exist in the family
+    let mut wrong_size = attributes;
+    wrong_size.bits = 255;
+    assert_eq!(
+        key_management::validate_attributes(wrong_size).unwrap_err(),
+        Error::InvalidArgument
+    );
 
-    pub struct TestClient {
-        keys: Vec<Id>,
+    // Algorithm which can not be used with the key type
+    let mut wrong_alg = attributes;
+    wrong_alg.policy.permitted_algorithms = AsymmetricSignature::RsaPkcs1v15Sign {
+        hash_alg: Hash::Sha256.into(),
     }
+    .into();
+    assert_eq!(
+        key_management::validate_attributes(wrong_alg).unwrap_err(),
+        Error::NotPermitted
+    );
 
-    impl TestClient {
-        pub fn new() -> Self {
-            psa_crypto::init().unwrap();
-            TestClient { keys: Vec::new() }
-        }
+    // RSA key larger than the implementation supports
+    let mut too_large = wrong_alg;
+    too_large.key_type = Type::RsaKeyPair;
+    too_large.bits = 16384;
+    assert_eq!(
+        key_management::validate_attributes(too_large).unwrap_err(),
+        Error::NotSupported
+    );
 
-        pub fn generate(&mut self, attributes: Attributes, key_id: Option<u32>) -> Id {
-            let id = key_management::generate(attributes, key_id).unwrap();
-            self.keys.push(id);
-            id
-        }
+    // Attributes which pass validation can be used to create a key
+    let mut test_client = test_tools::TestClient::new();
+    let key = test_client.generate(attributes, None);
+    let generated = Attributes::from_key_id(key).unwrap();
+    assert_eq!(generated.key_type, attributes.key_type);
+    assert_eq!(generated.bits, attributes.bits);
+    assert_eq!(
+        generated.policy.permitted_algorithms,
+        attributes.policy.permitted_algorithms
+    );
+}
 
-        pub fn import(&mut self, attributes: Attributes, key_id: u32, key_data: &[u8]) -> Id {
-            let id = key_management::import(attributes, Some(key_id), key_data).unwrap();
-            self.keys.push(id);
-            id
-        }
+#[cfg(feature = "std")]
+#[test]
+fn with_timeout() {
+    use psa_crypto::operations::{hash, other};
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
 
-        pub fn export_key_pair(&mut self, key_id: Id, key_data: &mut [u8]) -> Result<usize> {
-            key_management::export(key_id, key_data)
-        }
+    psa_crypto::init().unwrap();
+    let digest = other::with_timeout(Duration::from_secs(10), || {
+        let mut digest = [0; 32];
+        hash::hash_compute(Hash::Sha256, b"abc", &mut digest).map(|_| digest)
+    })
+    .unwrap();
+    assert_eq!(digest[..4], [0xba, 0x78, 0x16, 0xbf]);
+    assert_eq!(
+        other::with_timeout(Duration::from_secs(10), || hash::hash_compute(
+            Hash::Sha256,
+            b"abc",
+            &mut [0; 4]
+        )),
+        Err(Error::BufferTooSmall)
+    );
 
-        pub fn copy_key(
-            &mut self,
-            key_id: Id,
-            attributes: Attributes,
-            id_for_new_persistent_key: Option<u32>,
-        ) -> Id {
-            let id = key_management::copy(key_id, attributes, id_for_new_persistent_key).unwrap();
-            self.keys.push(id);
-            id
-        }
-    }
+    // The caller gets control back, the operation still finishes.
+    let (finished, has_finished) = mpsc::channel();
+    let start = Instant::now();
+    assert_eq!(
+        other::with_timeout(Duration::from_millis(50), move || {
+            std::thread::sleep(Duration::from_millis(500));
+            finished.send(()).unwrap();
+            Ok(())
+        }),
+        Err(Error::Timeout)
+    );
+    assert!(start.elapsed() < Duration::from_millis(500));
+    // No other PSA call is made before the operation has returned.
+    has_finished.recv_timeout(Duration::from_secs(10)).unwrap();
 
-    impl Drop for TestClient {
-        fn drop(&mut self) {
-            for key in self.keys.clone() {
-                unsafe { key_management::destroy(key) }.unwrap();
-            }
+    assert_eq!(
+        other::with_timeout::<(), _>(Duration::from_secs(10), || panic!("operation failure")),
+        Err(Error::GenericError)
+    );
+}
+
+#[test]
+fn backend_capabilities() {
+    use psa_crypto::operations::{hash, other};
+    use psa_crypto::types::algorithm::{Aead, AeadWithDefaultLengthTag, SignHash};
+
+    let capabilities = other::backend_capabilities();
+    capabilities.require(Hash::Sha256).unwrap();
+    assert!(capabilities.supports(Aead::AeadWithDefaultLengthTag(
+        AeadWithDefaultLengthTag::Gcm
+    )));
+    assert!(capabilities.supports(AsymmetricSignature::Ecdsa {
+        hash_alg: Hash::Sha256.into(),
+    }));
+    // The algorithms the tests rely on are listed, along with any others of the backend.
+    assert!(capabilities.hashes().any(|alg| alg == Hash::Sha256));
+    assert!(capabilities
+        .aeads()
+        .any(|alg| alg == AeadWithDefaultLengthTag::Gcm));
+    assert!(capabilities.asymmetric_signatures().any(|alg| alg
+        == AsymmetricSignature::Ecdsa {
+            hash_alg: SignHash::Any,
+        }));
+    assert!(capabilities
+        .aeads()
+        .all(|alg| capabilities.supports(Aead::AeadWithDefaultLengthTag(alg))));
+    assert!(capabilities
+        .asymmetric_signatures()
+        .all(|alg| capabilities.supports(alg)));
+    // Raw PKCS#1 v1.5 signatures come with the hashed ones.
+    assert_eq!(
+        capabilities.supports(AsymmetricSignature::RsaPkcs1v15SignRaw),
+        capabilities.supports(AsymmetricSignature::RsaPkcs1v15Sign {
+            hash_alg: SignHash::Any,
+        })
+    );
+    #[allow(deprecated)]
+    let md2 = Hash::Md2;
+    assert_eq!(capabilities.require(md2), Err(Error::NotSupported));
+    assert!(!capabilities.supports(AsymmetricSignature::Ecdsa {
+        hash_alg: md2.into()
+    }));
+
+    // The reported hashes are the ones the implementation can compute.
+    psa_crypto::init().unwrap();
+    let hashes: Vec<Hash> = capabilities.hashes().collect();
+    assert!(!hashes.is_empty());
+    #[allow(deprecated)]
+    let probed = [
+        Hash::Md2,
+        Hash::Md5,
+        Hash::Sha1,
+        Hash::Sha256,
+        Hash::Sha512_224,
+        Hash::Sha512_256,
+        Hash::Sha3_256,
+        Hash::Sha3_512,
+    ];
+    for hash_alg in probed.iter() {
+        let mut digest = [0; 64];
+        let computed = hash::hash_compute(*hash_alg, b"abc", &mut digest);
+        if hashes.contains(hash_alg) {
+            let _ = computed.unwrap();
+        } else {
+            assert_eq!(computed, Err(Error::NotSupported));
         }
     }
 }
+
+mod test_tools;
@@ -0,0 +1,219 @@
+#![cfg(feature = "pkcs11")]
+
+use psa_crypto::operations::key_management::{self, Pkcs11PublicKey};
+use psa_crypto::types::algorithm::{AsymmetricSignature, Hash};
+use psa_crypto::types::key::{Policy, UsageFlags};
+use psa_crypto::types::status::Error;
+
+// PKCS#1 RSAPublicKey of a 1024-bit key, generated with OpenSSL
+const RSA_PUBLIC_KEY: [u8; 140] = [
+    0x30, 0x81, 0x89, 0x02, 0x81, 0x81, 0x00, 0xb3, 0x7a, 0x11, 0xe6, 0xa6, 0xe3, 0x53, 0x38, 0x73,
+    0xf4, 0xc1, 0x6c, 0x91, 0x21, 0x66, 0x2a, 0x97, 0x39, 0x3b, 0x10, 0xcc, 0x56, 0x5a, 0x56, 0xc1,
+    0xde, 0x8d, 0x91, 0xb2, 0xa6, 0x91, 0xf4, 0xf8, 0x8f, 0xb4, 0x29, 0x0b, 0xd9, 0x23, 0xb9, 0xad,
+    0x92, 0xab, 0x51, 0x85, 0xce, 0x8f, 0x36, 0x75, 0x28, 0x1f, 0x35, 0x10, 0x66, 0x97, 0xe4, 0x06,
+    0x9d, 0xb1, 0xc5, 0x18, 0x29, 0xd2, 0xb6, 0xc6, 0x26, 0xe1, 0x4e, 0x0e, 0x7b, 0xc2, 0xb2, 0x7c,
+    0xf5, 0x8c, 0x18, 0x22, 0x14, 0x2e, 0xbe, 0x95, 0x9b, 0x61, 0x8e, 0x58, 0xca, 0x30, 0x68, 0x0f,
+    0x1d, 0x14, 0xae, 0x39, 0xdf, 0xab, 0xde, 0x2b, 0x26, 0x1d, 0xf5, 0xa1, 0x00, 0x84, 0x57, 0xc4,
+    0xeb, 0xbe, 0x34, 0xea, 0xbb, 0x82, 0xbe, 0x34, 0x9d, 0xcf, 0x51, 0x2a, 0x7e, 0x4a, 0x8c, 0x00,
+    0x04, 0xda, 0x3d, 0xc6, 0x58, 0x27, 0xbd, 0x02, 0x03, 0x01, 0x00, 0x01,
+];
+const RSA_PUBLIC_EXPONENT: &[u8] = &[0x01, 0x00, 0x01];
+
+// prime256v1
+const EC_PARAMS: [u8; 10] = [0x06, 0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+const EC_POINT: [u8; 65] = [
+    0x04, 0x24, 0x8a, 0xac, 0x63, 0x0d, 0x0c, 0x60, 0x22, 0xba, 0xd4, 0xa4, 0xa9, 0x4b, 0xcc, 0xf0,
+    0x87, 0x33, 0xb2, 0x84, 0x59, 0x69, 0xa6, 0x86, 0x4c, 0x3a, 0x81, 0x7b, 0x99, 0xc7, 0x60, 0x1f,
+    0x73, 0xc7, 0x57, 0x9a, 0x21, 0x7a, 0x99, 0x43, 0xf5, 0x69, 0x74, 0xa9, 0x04, 0x63, 0xc7, 0x0f,
+    0xdb, 0x1e, 0x32, 0xb1, 0x3c, 0x77, 0xbb, 0xbe, 0xe0, 0x6d, 0x0c, 0xf9, 0xbf, 0xfe, 0xce, 0xc5,
+    0x10,
+];
+
+fn verify_policy(alg: AsymmetricSignature) -> Policy {
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_verify_hash();
+    Policy {
+        usage_flags,
+        permitted_algorithms: alg.into(),
+    }
+}
+
+#[test]
+fn import_rsa_public_key() {
+    let policy = verify_policy(AsymmetricSignature::RsaPkcs1v15Sign {
+        hash_alg: Hash::Sha256.into(),
+    });
+    psa_crypto::init().unwrap();
+
+    let modulus = &RSA_PUBLIC_KEY[7..135];
+    // Tokens may return the modulus with leading zeros.
+    let mut padded_modulus = [0; 130];
+    padded_modulus[2..].copy_from_slice(modulus);
+    for modulus in [modulus, &padded_modulus[..]].iter() {
+        let key = key_management::import_pkcs11_public_key(
+            Pkcs11PublicKey::Rsa {
+                modulus,
+                public_exponent: RSA_PUBLIC_EXPONENT,
+            },
+            policy,
+            None,
+        )
+        .unwrap();
+
+        let mut exported = [0; 256];
+        let size = key_management::export_public(key, &mut exported).unwrap();
+        assert_eq!(&exported[..size], &RSA_PUBLIC_KEY[..]);
+        unsafe { key_management::destroy(key) }.unwrap();
+    }
+}
+
+#[test]
+fn import_ec_public_key() {
+    let policy = verify_policy(AsymmetricSignature::Ecdsa {
+        hash_alg: Hash::Sha256.into(),
+    });
+    psa_crypto::init().unwrap();
+
+    let mut der_point = [0; 67];
+    der_point[..2].copy_from_slice(&[0x04, 0x41]);
+    der_point[2..].copy_from_slice(&EC_POINT);
+    for ec_point in [&der_point[..], &EC_POINT[..]].iter() {
+        let key = key_management::import_pkcs11_public_key(
+            Pkcs11PublicKey::Ec {
+                ec_params: &EC_PARAMS,
+                ec_point,
+            },
+            policy,
+            None,
+        )
+        .unwrap();
+
+        let mut exported = [0; 65];
+        let size = key_management::export_public(key, &mut exported).unwrap();
+        assert_eq!(&exported[..size], &EC_POINT[..]);
+        unsafe { key_management::destroy(key) }.unwrap();
+    }
+}
+
+#[test]
+fn import_ec_public_key_errors() {
+    let policy = verify_policy(AsymmetricSignature::Ecdsa {
+        hash_alg: Hash::Sha256.into(),
+    });
+    psa_crypto::init().unwrap();
+
+    assert_eq!(
+        key_management::import_pkcs11_public_key(
+            Pkcs11PublicKey::Ec {
+                ec_params: &EC_PARAMS,
+                ec_point: &EC_POINT[..64],
+            },
+            policy,
+            None,
+        ),
+        Err(Error::InvalidArgument)
+    );
+
+    // secp112r1 (1.3.132.0.6)
+    assert_eq!(
+        key_management::import_pkcs11_public_key(
+            Pkcs11PublicKey::Ec {
+                ec_params: &[0x06, 0x05, 0x2b, 0x81, 0x04, 0x00, 0x06],
+                ec_point: &EC_POINT,
+            },
+            policy,
+            None,
+        ),
+        Err(Error::NotSupported)
+    );
+}
+
+#[test]
+fn import_from_pkcs11_without_module() {
+    psa_crypto::init().unwrap();
+    assert_eq!(
+        key_management::import_from_pkcs11("/nonexistent/libpkcs11.so", "key"),
+        Err(Error::CommunicationFailure)
+    );
+}
+
+// The token is set up by ci.sh: SoftHSM, with a user PIN of 1234.
+#[test]
+#[ignore = "needs the PKCS#11 module of an initialized token in PKCS11_MODULE"]
+fn import_from_pkcs11_token() {
+    use cryptoki::context::{CInitializeArgs, Pkcs11};
+    use cryptoki::mechanism::Mechanism;
+    use cryptoki::object::Attribute;
+    use cryptoki::session::UserType;
+    use cryptoki::types::AuthPin;
+    use psa_crypto::operations::{asym_signature, hash};
+
+    const LABEL: &str = "psa-crypto import_from_pkcs11";
+    let module = std::env::var("PKCS11_MODULE").unwrap();
+    let label = Attribute::Label(LABEL.as_bytes().to_vec());
+    let open_session = || {
+        let pkcs11 = Pkcs11::new(&module).unwrap();
+        pkcs11.initialize(CInitializeArgs::OsThreads).unwrap();
+        let slot = pkcs11.get_slots_with_token().unwrap()[0];
+        let session = pkcs11.open_rw_session(slot).unwrap();
+        session
+            .login(UserType::User, Some(&AuthPin::new("1234".into())))
+            .unwrap();
+        // The session keeps the module initialized until it is dropped.
+        session
+    };
+
+    psa_crypto::init().unwrap();
+    let mut message_hash = [0; 32];
+    let _ = hash::hash_compute(Hash::Sha256, b"signed on the token", &mut message_hash).unwrap();
+    let mut other_hash = [0; 32];
+    let _ = hash::hash_compute(Hash::Sha256, b"not signed", &mut other_hash).unwrap();
+
+    let session = open_session();
+    let (_, private_key) = session
+        .generate_key_pair(
+            &Mechanism::EccKeyPairGen,
+            &[
+                Attribute::Token(true),
+                Attribute::Private(false),
+                Attribute::Verify(true),
+                Attribute::EcParams(EC_PARAMS.to_vec()),
+                label.clone(),
+            ],
+            &[
+                Attribute::Token(true),
+                Attribute::Private(true),
+                Attribute::Sign(true),
+                label.clone(),
+            ],
+        )
+        .unwrap();
+    let signature = session
+        .sign(&Mechanism::Ecdsa, private_key, &message_hash)
+        .unwrap();
+    // Finalize the module before import_from_pkcs11 initializes it again.
+    drop(session);
+
+    let result = key_management::import_from_pkcs11(&module, LABEL);
+    let session = open_session();
+    for object in session.find_objects(&[label]).unwrap() {
+        session.destroy_object(object).unwrap();
+    }
+    drop(session);
+
+    let key = result.unwrap();
+    let alg = AsymmetricSignature::Ecdsa {
+        hash_alg: Hash::Sha256.into(),
+    };
+    asym_signature::verify_hash(key, alg, &message_hash, &signature).unwrap();
+    assert_eq!(
+        asym_signature::verify_hash(key, alg, &other_hash, &signature),
+        Err(Error::InvalidSignature)
+    );
+    unsafe { key_management::destroy(key) }.unwrap();
+
+    assert_eq!(
+        key_management::import_from_pkcs11(&module, LABEL),
+        Err(Error::DoesNotExist)
+    );
+}
@@ -0,0 +1,115 @@
+use psa_crypto::operations::single_use::SingleUseKey;
+use psa_crypto::operations::{aead, key_management};
+use psa_crypto::types::algorithm::{Aead, AeadWithDefaultLengthTag, FullLengthMac, Hash, Mac};
+use psa_crypto::types::key::{Attributes, Id, Lifetime, Policy, Type, UsageFlags};
+use psa_crypto::types::status::Error;
+
+const HMAC_SHA256: Mac = Mac::FullLength(FullLengthMac::Hmac {
+    hash_alg: Hash::Sha256,
+});
+
+fn hmac_key() -> Id {
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_sign_hash();
+    let attributes = Attributes {
+        key_type: Type::Hmac,
+        bits: 256,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: HMAC_SHA256.into(),
+        },
+    };
+    key_management::generate(attributes, None).unwrap()
+}
+
+#[test]
+fn second_use_fails() {
+    psa_crypto::init().unwrap();
+    let id = hmac_key();
+    let mut key = unsafe { SingleUseKey::new(id) };
+
+    let mut mac = [0; 32];
+    assert_eq!(key.compute_mac(HMAC_SHA256, b"token", &mut mac), Ok(32));
+    assert!(key.is_used());
+    let _ = Attributes::from_key_id(id).unwrap_err();
+    assert_eq!(
+        key.compute_mac(HMAC_SHA256, b"token", &mut mac),
+        Err(Error::BadState)
+    );
+    assert_eq!(
+        key.encrypt(
+            Aead::AeadWithDefaultLengthTag(AeadWithDefaultLengthTag::Gcm),
+            &[0; 12],
+            &[],
+            b"token",
+            &mut [0; 21]
+        ),
+        Err(Error::BadState)
+    );
+}
+
+#[test]
+fn failed_use_destroys_key() {
+    psa_crypto::init().unwrap();
+    let id = hmac_key();
+    let mut key = unsafe { SingleUseKey::new(id) };
+
+    assert_eq!(
+        key.compute_mac(HMAC_SHA256, b"token", &mut [0; 16]),
+        Err(Error::BufferTooSmall)
+    );
+    assert!(key.is_used());
+    let _ = Attributes::from_key_id(id).unwrap_err();
+}
+
+#[test]
+fn unused_key_destroyed_on_drop() {
+    psa_crypto::init().unwrap();
+    let id = hmac_key();
+    drop(unsafe { SingleUseKey::new(id) });
+    let _ = Attributes::from_key_id(id).unwrap_err();
+}
+
+#[test]
+fn single_encryption() {
+    let alg = Aead::AeadWithDefaultLengthTag(AeadWithDefaultLengthTag::Gcm);
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_encrypt().set_decrypt();
+    let attributes = Attributes {
+        key_type: Type::Aes,
+        bits: 128,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: alg.into(),
+        },
+    };
+    let key_data = [0x42; 16];
+
+    psa_crypto::init().unwrap();
+    let mut key =
+        unsafe { SingleUseKey::new(key_management::import(attributes, None, &key_data).unwrap()) };
+    let mut ciphertext = [0; 21];
+    assert_eq!(
+        key.encrypt(alg, &[0; 12], b"header", b"token", &mut ciphertext),
+        Ok(21)
+    );
+
+    // The same key material, imported again, decrypts the message.
+    let decryption_key = key_management::import(attributes, None, &key_data).unwrap();
+    let mut plaintext = [0; 5];
+    assert_eq!(
+        aead::decrypt(
+            decryption_key,
+            alg,
+            &[0; 12],
+            b"header",
+            &ciphertext,
+            &mut plaintext
+        ),
+        Ok(5)
+    );
+    assert_eq!(&plaintext, b"token");
+    unsafe { key_management::destroy(decryption_key) }.unwrap();
+}
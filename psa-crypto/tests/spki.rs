@@ -0,0 +1,255 @@
+use psa_crypto::operations::{asym_signature, key_management};
+use psa_crypto::types::algorithm::{AsymmetricSignature, Hash};
+use psa_crypto::types::key::{EccFamily, Type};
+use psa_crypto::types::status::Error;
+#[cfg(feature = "std")]
+use psa_crypto::{
+    operations::hash,
+    types::algorithm::{KeyAgreement, RawKeyAgreement},
+    types::key::{Attributes, Lifetime, Policy, UsageFlags},
+};
+
+// Generated with `openssl pkey -pubout -outform DER` from a P-256 key
+const P256_SPKI: [u8; 91] = [
+    0x30, 0x59, 0x30, 0x13, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, 0x06, 0x08, 0x2a,
+    0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07, 0x03, 0x42, 0x00, 0x04, 0x70, 0x53, 0x8b, 0xa3, 0x78,
+    0x09, 0xa1, 0xae, 0x47, 0xae, 0x46, 0xb6, 0x87, 0x80, 0x5d, 0xca, 0x9d, 0x8a, 0x3e, 0xab, 0x76,
+    0xa3, 0x14, 0xd7, 0x4d, 0x55, 0x14, 0x06, 0xe9, 0x3e, 0x37, 0x85, 0x0e, 0x85, 0xff, 0xdd, 0x34,
+    0x03, 0x71, 0xf4, 0x93, 0x2d, 0x9c, 0x96, 0xba, 0xbc, 0x0a, 0x4d, 0x2b, 0x29, 0xe5, 0x3a, 0x62,
+    0xd9, 0x68, 0x3d, 0xf6, 0x82, 0x78, 0x64, 0x14, 0x7d, 0x6e, 0x73,
+];
+
+// Another P-256 key, with an ECDSA SHA-256 signature of `SIGNED_MESSAGE` made with it
+const SIGNING_P256_SPKI: [u8; 91] = [
+    0x30, 0x59, 0x30, 0x13, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, 0x06, 0x08, 0x2a,
+    0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07, 0x03, 0x42, 0x00, 0x04, 0x9e, 0xdb, 0xba, 0x12, 0x40,
+    0x89, 0x86, 0x72, 0xc0, 0x0c, 0x07, 0xfd, 0xeb, 0x31, 0x07, 0x11, 0x92, 0x8b, 0x4b, 0x6f, 0x5a,
+    0xbd, 0x8b, 0x01, 0x51, 0xbe, 0x20, 0xdb, 0x21, 0x2f, 0xc6, 0x76, 0xb9, 0x9f, 0x80, 0x38, 0xa7,
+    0x3b, 0xba, 0x15, 0xde, 0xa8, 0x92, 0xe3, 0x4e, 0xf9, 0xdf, 0xfe, 0xf0, 0x41, 0x2e, 0x55, 0xbe,
+    0x4d, 0xb7, 0x93, 0xf2, 0xd9, 0x8e, 0x6b, 0xb8, 0x3c, 0x51, 0xa0,
+];
+const SIGNED_MESSAGE: &[u8] = b"message signed with a P-256 key";
+const SIGNATURE: [u8; 64] = [
+    0x9d, 0x54, 0x04, 0xda, 0x2b, 0x7b, 0x63, 0x1b, 0x92, 0x3e, 0x48, 0x9a, 0xf5, 0x73, 0xc8, 0x2f,
+    0x5a, 0x6c, 0xad, 0xcf, 0x67, 0x6d, 0x11, 0x1a, 0xee, 0x9e, 0x17, 0x33, 0x9d, 0xeb, 0x17, 0x43,
+    0x2a, 0x0b, 0xe2, 0x21, 0xfa, 0xa3, 0x01, 0x80, 0x11, 0xb0, 0x96, 0x85, 0x51, 0x46, 0xfc, 0x5a,
+    0xdc, 0x1d, 0x79, 0x26, 0x52, 0x9a, 0xf5, 0xc7, 0x07, 0x66, 0x98, 0xbd, 0xd6, 0x7f, 0xcb, 0x1b,
+];
+
+// `SIGNING_P256_SPKI` with the point compressed
+const COMPRESSED_P256_SPKI: [u8; 59] = [
+    0x30, 0x39, 0x30, 0x13, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, 0x06, 0x08, 0x2a,
+    0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07, 0x03, 0x22, 0x00, 0x02, 0x9e, 0xdb, 0xba, 0x12, 0x40,
+    0x89, 0x86, 0x72, 0xc0, 0x0c, 0x07, 0xfd, 0xeb, 0x31, 0x07, 0x11, 0x92, 0x8b, 0x4b, 0x6f, 0x5a,
+    0xbd, 0x8b, 0x01, 0x51, 0xbe, 0x20, 0xdb, 0x21, 0x2f, 0xc6, 0x76,
+];
+
+// Generated with `openssl pkey -pubout -outform DER` from an RSA-2048 key
+const RSA_2048_SPKI: [u8; 294] = [
+    0x30, 0x82, 0x01, 0x22, 0x30, 0x0d, 0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01,
+    0x01, 0x05, 0x00, 0x03, 0x82, 0x01, 0x0f, 0x00, 0x30, 0x82, 0x01, 0x0a, 0x02, 0x82, 0x01, 0x01,
+    0x00, 0xbc, 0x4a, 0xdd, 0x79, 0xe5, 0xc0, 0x52, 0xe3, 0xd6, 0xf0, 0x79, 0x2d, 0x41, 0x30, 0xdd,
+    0x1f, 0x21, 0x1d, 0x4f, 0x92, 0x5d, 0x6a, 0x5a, 0x57, 0xa5, 0x12, 0xae, 0xa1, 0xf1, 0xd0, 0xf3,
+    0xdf, 0xe2, 0xbc, 0x11, 0xbc, 0xa8, 0x0c, 0x17, 0xd9, 0xf4, 0x19, 0x8a, 0x42, 0x1b, 0x8d, 0xfe,
+    0x73, 0x7a, 0x32, 0x55, 0xd8, 0x1e, 0x1b, 0xf0, 0x50, 0x0a, 0xc8, 0xdf, 0x36, 0x78, 0xfb, 0xc1,
+    0xee, 0x79, 0xc7, 0x68, 0x2b, 0xac, 0xb1, 0xca, 0x79, 0xa5, 0x56, 0x98, 0x54, 0x3a, 0x77, 0x37,
+    0x46, 0xce, 0x12, 0x51, 0x5f, 0x96, 0xcf, 0xf3, 0x3a, 0xec, 0xec, 0xe6, 0x77, 0x83, 0x6c, 0x01,
+    0x39, 0x8b, 0xf0, 0xe3, 0x70, 0xed, 0x6f, 0x00, 0x91, 0x14, 0xaf, 0x3f, 0x8e, 0x84, 0x56, 0x43,
+    0x79, 0x8b, 0x5f, 0xf4, 0x04, 0x87, 0x2f, 0xb1, 0x54, 0x65, 0xb6, 0x0a, 0xfe, 0xb7, 0x67, 0x8b,
+    0x2e, 0x52, 0x44, 0x86, 0xb3, 0x72, 0x1a, 0xbf, 0x50, 0x91, 0xa7, 0xdd, 0x40, 0xc9, 0xcb, 0x27,
+    0xe6, 0x15, 0x65, 0x6c, 0xcc, 0x07, 0x67, 0xd1, 0x8c, 0x48, 0x5f, 0xfb, 0x3e, 0x54, 0x13, 0xeb,
+    0x57, 0xd0, 0x61, 0xc7, 0x1c, 0xae, 0x74, 0xe0, 0x64, 0xdd, 0xf8, 0x8c, 0xd2, 0x8d, 0xb2, 0xfc,
+    0x3c, 0x44, 0x23, 0x8f, 0x11, 0x0b, 0x64, 0xd4, 0x75, 0x6c, 0x91, 0x96, 0x3c, 0xea, 0x47, 0xdc,
+    0xc7, 0x0e, 0x94, 0x59, 0x70, 0xfb, 0x1a, 0x42, 0x44, 0x50, 0x0d, 0xdd, 0xc7, 0x9b, 0xd6, 0x13,
+    0x85, 0x3d, 0x6d, 0x56, 0x81, 0x6c, 0x3a, 0x70, 0x59, 0x54, 0x5f, 0xd9, 0x8f, 0xd2, 0x6b, 0xf1,
+    0xf6, 0xf0, 0x57, 0x5a, 0x31, 0x90, 0x1c, 0x2a, 0xc6, 0x6f, 0x7e, 0x2d, 0x65, 0x45, 0x97, 0x27,
+    0x47, 0xda, 0x65, 0xae, 0xd3, 0x20, 0xa7, 0x7d, 0x12, 0x2d, 0xef, 0xd1, 0x62, 0x76, 0x83, 0xc9,
+    0xa1, 0x02, 0x03, 0x01, 0x00, 0x01,
+];
+
+// `parse_spki_metadata` does not use the PSA implementation at all, so no key is imported for it.
+
+#[test]
+fn spki_metadata_p256() {
+    assert_eq!(
+        key_management::parse_spki_metadata(&P256_SPKI).unwrap(),
+        (
+            Type::EccPublicKey {
+                curve_family: EccFamily::SecpR1
+            },
+            256
+        )
+    );
+}
+
+#[test]
+fn spki_metadata_rsa_2048() {
+    assert_eq!(
+        key_management::parse_spki_metadata(&RSA_2048_SPKI).unwrap(),
+        (Type::RsaPublicKey, 2048)
+    );
+}
+
+#[test]
+fn spki_metadata_malformed() {
+    assert_eq!(
+        key_management::parse_spki_metadata(&P256_SPKI[..P256_SPKI.len() - 1]).unwrap_err(),
+        Error::InvalidArgument
+    );
+    let mut unknown_curve = P256_SPKI;
+    // Last byte of the secp256r1 OID
+    unknown_curve[22] = 0x08;
+    assert_eq!(
+        key_management::parse_spki_metadata(&unknown_curve).unwrap_err(),
+        Error::NotSupported
+    );
+}
+
+#[test]
+fn import_ec_spki_and_verify() {
+    let alg = AsymmetricSignature::Ecdsa {
+        hash_alg: Hash::Sha256.into(),
+    };
+
+    psa_crypto::init().unwrap();
+    let key = key_management::import_ec_spki(&SIGNING_P256_SPKI, alg).unwrap();
+    asym_signature::verify_message(key, alg, SIGNED_MESSAGE, &SIGNATURE).unwrap();
+    assert_eq!(
+        asym_signature::verify_message(key, alg, b"another message", &SIGNATURE),
+        Err(Error::InvalidSignature)
+    );
+    // The key is only for verification
+    assert_eq!(
+        asym_signature::sign_message(key, alg, SIGNED_MESSAGE, &mut [0; 64]),
+        Err(Error::NotPermitted)
+    );
+    unsafe { key_management::destroy(key) }.unwrap();
+}
+
+#[test]
+fn import_ec_spki_errors() {
+    let alg = AsymmetricSignature::Ecdsa {
+        hash_alg: Hash::Sha256.into(),
+    };
+
+    psa_crypto::init().unwrap();
+    assert_eq!(
+        key_management::import_ec_spki(&COMPRESSED_P256_SPKI, alg).unwrap_err(),
+        Error::NotSupported
+    );
+    assert_eq!(
+        key_management::import_ec_spki(&RSA_2048_SPKI, alg).unwrap_err(),
+        Error::InvalidArgument
+    );
+    let mut unknown_curve = P256_SPKI;
+    unknown_curve[22] = 0x08;
+    assert_eq!(
+        key_management::import_ec_spki(&unknown_curve, alg).unwrap_err(),
+        Error::NotSupported
+    );
+}
+
+#[cfg(feature = "std")]
+fn sha256(data: &[u8]) -> Vec<u8> {
+    let mut digest = vec![0; 32];
+    let size = hash::hash_compute(Hash::Sha256, data, &mut digest).unwrap();
+    digest.truncate(size);
+    digest
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn public_key_fingerprint_hashes_spki() {
+    let alg = AsymmetricSignature::Ecdsa {
+        hash_alg: Hash::Sha256.into(),
+    };
+
+    psa_crypto::init().unwrap();
+    let key = key_management::import_ec_spki(&P256_SPKI, alg).unwrap();
+    let fingerprint = key_management::public_key_fingerprint(key, Hash::Sha256).unwrap();
+    assert_eq!(fingerprint, sha256(&P256_SPKI));
+    assert_eq!(
+        key_management::public_key_fingerprint(key, Hash::Sha256).unwrap(),
+        fingerprint
+    );
+    assert_eq!(
+        key_management::public_key_fingerprint(key, Hash::Sha512)
+            .unwrap()
+            .len(),
+        64
+    );
+    let other_key = key_management::import_ec_spki(&SIGNING_P256_SPKI, alg).unwrap();
+    assert_ne!(
+        key_management::public_key_fingerprint(other_key, Hash::Sha256).unwrap(),
+        fingerprint
+    );
+    unsafe { key_management::destroy(key) }.unwrap();
+    unsafe { key_management::destroy(other_key) }.unwrap();
+
+    // The subjectPublicKey of an RSA SubjectPublicKeyInfo is the PSA export format.
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_verify_hash();
+    let attributes = Attributes {
+        key_type: Type::RsaPublicKey,
+        bits: 2048,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: AsymmetricSignature::RsaPkcs1v15Sign {
+                hash_alg: Hash::Sha256.into(),
+            }
+            .into(),
+        },
+    };
+    let key = key_management::import(attributes, None, &RSA_2048_SPKI[24..]).unwrap();
+    assert_eq!(
+        key_management::public_key_fingerprint(key, Hash::Sha256).unwrap(),
+        sha256(&RSA_2048_SPKI)
+    );
+    unsafe { key_management::destroy(key) }.unwrap();
+
+    // Key pairs have the fingerprint of their public part.
+    let mut usage_flags: UsageFlags = Default::default();
+    usage_flags.set_derive();
+    let attributes = Attributes {
+        key_type: Type::EccKeyPair {
+            curve_family: EccFamily::Montgomery,
+        },
+        bits: 255,
+        lifetime: Lifetime::Volatile,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: KeyAgreement::Raw(RawKeyAgreement::Ecdh).into(),
+        },
+    };
+    let key = key_management::generate(attributes, None).unwrap();
+    let mut public_key = [0; 32];
+    assert_eq!(key_management::export_public(key, &mut public_key), Ok(32));
+    // id-X25519 AlgorithmIdentifier
+    let mut spki = vec![
+        0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x6e, 0x03, 0x21, 0x00,
+    ];
+    spki.extend_from_slice(&public_key);
+    assert_eq!(
+        key_management::public_key_fingerprint(key, Hash::Sha256).unwrap(),
+        sha256(&spki)
+    );
+    unsafe { key_management::destroy(key) }.unwrap();
+
+    let key = key_management::import_for_mac(
+        Type::Hmac,
+        psa_crypto::types::algorithm::Mac::FullLength(
+            psa_crypto::types::algorithm::FullLengthMac::Hmac {
+                hash_alg: Hash::Sha256,
+            },
+        ),
+        None,
+        &[0; 32],
+    )
+    .unwrap();
+    assert_eq!(
+        key_management::public_key_fingerprint(key, Hash::Sha256),
+        Err(Error::InvalidArgument)
+    );
+    unsafe { key_management::destroy(key) }.unwrap();
+}
@@ -0,0 +1,70 @@
+// Copyright 2020 Contributors to the Parsec project.
+// SPDX-License-Identifier: Apache-2.0
+
+// Shared by the test binaries, which each use a part of it.
+#![allow(dead_code)]
+
+use psa_crypto::operations::key_management;
+use psa_crypto::types::key::{Attributes, Id};
+use psa_crypto::types::status::Result;
+
+pub struct TestClient {
+    keys: Vec<Id>,
+    persistent_key_ids: Vec<u32>,
+}
+
+impl TestClient {
+    pub fn new() -> Self {
+        psa_crypto::init().unwrap();
+        TestClient {
+            keys: Vec::new(),
+            persistent_key_ids: Vec::new(),
+        }
+    }
+
+    pub fn generate(&mut self, attributes: Attributes, key_id: Option<u32>) -> Id {
+        let id = key_management::generate(attributes, key_id).unwrap();
+        self.keys.push(id);
+        id
+    }
+
+    pub fn import(&mut self, attributes: Attributes, key_id: u32, key_data: &[u8]) -> Id {
+        let id = key_management::import(attributes, Some(key_id), key_data).unwrap();
+        self.keys.push(id);
+        id
+    }
+
+    // For the persistent keys created by the code under test: the key is destroyed on drop if it
+    // exists then.
+    pub fn track_persistent(&mut self, key_id: u32) {
+        self.persistent_key_ids.push(key_id);
+    }
+
+    pub fn export_key_pair(&mut self, key_id: Id, key_data: &mut [u8]) -> Result<usize> {
+        key_management::export(key_id, key_data)
+    }
+
+    pub fn copy_key(
+        &mut self,
+        key_id: Id,
+        attributes: Attributes,
+        id_for_new_persistent_key: Option<u32>,
+    ) -> Id {
+        let id = key_management::copy(key_id, attributes, id_for_new_persistent_key).unwrap();
+        self.keys.push(id);
+        id
+    }
+}
+
+impl Drop for TestClient {
+    fn drop(&mut self) {
+        for key in self.keys.clone() {
+            unsafe { key_management::destroy(key) }.unwrap();
+        }
+        for key_id in self.persistent_key_ids.clone() {
+            if let Ok(key) = Id::from_persistent_key_id(key_id) {
+                unsafe { key_management::destroy(key) }.unwrap();
+            }
+        }
+    }
+}
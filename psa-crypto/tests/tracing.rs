@@ -0,0 +1,93 @@
+#![cfg(feature = "tracing")]
+
+#[path = "test_tools/mod.rs"]
+mod test_tools;
+
+use psa_crypto::operations::mac;
+use psa_crypto::types::algorithm::{FullLengthMac, Hash, Mac};
+use psa_crypto::types::key::{Attributes, Lifetime, Policy, Type, UsageFlags};
+use std::io;
+use std::sync::{Arc, Mutex};
+use test_tools::TestClient;
+use tracing::Level;
+
+const KEY_ID: u32 = 0x7ace;
+const KEY_DATA: [u8; 32] = [0xa5; 32];
+const MESSAGE: &[u8] = b"attack at dawn";
+
+// Output of the subscriber, shared with the test
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Renderings a secret could take in a log line
+fn renderings(secret: &[u8]) -> Vec<String> {
+    let hex: String = secret.iter().map(|byte| format!("{:02x}", byte)).collect();
+    vec![
+        format!("{:?}", secret),
+        String::from_utf8_lossy(secret).into_owned(),
+        hex.to_uppercase(),
+        hex,
+    ]
+}
+
+#[test]
+fn compute_mac_span() {
+    let mac_alg = Mac::FullLength(FullLengthMac::Hmac {
+        hash_alg: Hash::Sha256,
+    });
+    let mut usage_flags: UsageFlags = Default::default();
+    // Mbed TLS checks the hash usage flags for MAC operations.
+    let _ = usage_flags.set_sign_hash().set_verify_hash();
+    let attributes = Attributes {
+        key_type: Type::Hmac,
+        bits: 0,
+        lifetime: Lifetime::Persistent,
+        policy: Policy {
+            usage_flags,
+            permitted_algorithms: mac_alg.into(),
+        },
+    };
+    let capture = Capture::default();
+    let writer = capture.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(Level::DEBUG)
+        .without_time()
+        .with_writer(move || writer.clone())
+        .finish();
+
+    let mut client = TestClient::new();
+    let mut mac = [0; 32];
+    // The subscriber only sees the events of this thread.
+    let size = tracing::subscriber::with_default(subscriber, || {
+        let key = client.import(attributes, KEY_ID, &KEY_DATA);
+        mac::compute_mac(key, mac_alg, MESSAGE, &mut mac).unwrap()
+    });
+
+    let output = String::from_utf8(capture.0.lock().unwrap().clone()).unwrap();
+    let events: Vec<&str> = output.lines().collect();
+    assert_eq!(
+        events,
+        [
+            "DEBUG import{alg=Mac(FullLength(Hmac { hash_alg: Sha256 })) key_id=31438}: \
+             psa_crypto::operations: status=ok",
+            "DEBUG compute_mac{alg=Mac(FullLength(Hmac { hash_alg: Sha256 })) key_id=31438}: \
+             psa_crypto::operations: status=ok",
+        ]
+    );
+    for secret in [&KEY_DATA[..], MESSAGE, &mac[..size]].iter() {
+        for rendering in renderings(secret) {
+            assert!(!output.contains(&rendering));
+        }
+    }
+}